@@ -0,0 +1,30 @@
+//! The renderer sources are included directly, so the features they are gated on are those of
+//! the main crate. Turn every one of them on, rather than keeping a copy of the list here.
+
+use std::fs;
+
+fn main() {
+    let manifest = "../Cargo.toml";
+    println!("cargo:rerun-if-changed={}", manifest);
+    let manifest = fs::read_to_string(manifest).expect("couldn't read the main crate's manifest");
+    let features = manifest
+        .lines()
+        .skip_while(|line| line.trim() != "[features]")
+        .skip(1)
+        .take_while(|line| !line.trim_start().starts_with('['))
+        .filter_map(|line| line.split('=').next())
+        .map(str::trim)
+        .filter(|name| !name.is_empty() && !name.starts_with('#'))
+        .collect::<Vec<_>>();
+    println!(
+        "cargo:rustc-check-cfg=cfg(feature, values({}))",
+        features
+            .iter()
+            .map(|name| format!("{:?}", name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    for name in features {
+        println!("cargo:rustc-cfg=feature={:?}", name);
+    }
+}
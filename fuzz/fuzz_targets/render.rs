@@ -0,0 +1,37 @@
+//! Arbitrary doc-comment input must never produce roff that fails `check_hardened`.
+//!
+//! Run with `cargo fuzz run render`; interesting inputs belong in `corpus/render`, which
+//! `tests/hardened.rs` replays on every `cargo test`.
+
+#![no_main]
+#![allow(dead_code)]
+
+#[path = "../../src/manpage/mod.rs"]
+mod manpage;
+#[path = "../../tests/fixtures/mod.rs"]
+mod fixtures;
+
+use libfuzzer_sys::fuzz_target;
+use manpage::Format;
+
+fuzz_target!(|data: &[u8]| {
+    let input = String::from_utf8_lossy(data);
+    let mut page = fixtures::from_fuzz_input(&input);
+    for format in &[
+        Format::Mdoc,
+        #[cfg(feature = "man")]
+        Format::Man,
+    ] {
+        page.format(*format);
+        for output in &[
+            page.render_header(),
+            page.render(),
+            page.render_footer(),
+            page.render_cheatsheet(),
+        ] {
+            if let Err(err) = manpage::roff::check_hardened(output) {
+                panic!("{}\n{}", err, output);
+            }
+        }
+    }
+});
@@ -17,7 +17,8 @@ use proc_macro2::{Span, TokenStream};
 use proc_macro_error::abort;
 use quote::{quote, quote_spanned, ToTokens};
 use syn::{
-    self, ext::IdentExt, spanned::Spanned, Attribute, Expr, Ident, LitStr, MetaNameValue, Type,
+    self, ext::IdentExt, spanned::Spanned, Attribute, Expr, ExprLit, ExprUnary, Generics, Ident,
+    Lit, LitStr, MetaNameValue, Type, TypePath, UnOp,
 };
 
 #[derive(Clone)]
@@ -92,7 +93,20 @@ pub struct Attrs {
     about: Option<Method>,
     version: Option<Method>,
     no_version: Option<Ident>,
+    no_provenance: Option<Ident>,
+    no_builtin_flags: Option<Ident>,
+    /// The `#[manpage(doc = "...")]` text, which replaces the doc comment in the manpage only.
+    manpage_doc: Option<LitStr>,
+    manpage_skip: Option<Ident>,
+    /// What the value stands for, after `value_hint`. clap 2 has no value hints, so only the
+    /// manpage uses it.
+    value_hint: Option<crate::manpage::ValueKind>,
+    /// The heading options are listed under, after `help_heading` on them or on their struct.
+    /// clap 2 has no headings, so only the manpage uses it.
+    help_heading: Option<String>,
     verbatim_doc_comment: Option<Ident>,
+    /// The type parameters of the struct or enum, whose pages are not known.
+    type_params: Vec<Ident>,
     has_custom_parser: bool,
     kind: Sp<Kind>,
 }
@@ -125,6 +139,19 @@ impl Method {
 
         Method::new(ident, quote!(#lit))
     }
+
+    /// The value of the method's argument when it is a string literal.
+    pub fn value(&self) -> String {
+        literal_value(&self.args)
+    }
+}
+
+/// The value a string literal stands for, with its quotes and escapes resolved, or the tokens as
+/// written when they are not a string literal.
+pub fn literal_value(tokens: &TokenStream) -> String {
+    syn::parse2::<LitStr>(tokens.clone())
+        .map(|lit| lit.value())
+        .unwrap_or_else(|_| tokens.to_string())
 }
 
 impl ToTokens for Method {
@@ -238,8 +265,12 @@ impl Attrs {
             .as_ref()
             .map(|attrs| attrs.no_version.clone())
             .unwrap_or(None);
+        let type_params = parent_attrs
+            .map(|attrs| attrs.type_params.clone())
+            .unwrap_or_default();
+        let help_heading = parent_attrs.and_then(|attrs| attrs.help_heading.clone());
         let mut manpage = crate::manpage::Manpage::new();
-        manpage.name(name.clone().translate(*casing).to_string());
+        manpage.name(literal_value(&name.clone().translate(*casing)));
 
         Self {
             name,
@@ -254,7 +285,14 @@ impl Attrs {
             author: None,
             version: None,
             no_version,
+            no_provenance: None,
+            no_builtin_flags: None,
+            manpage_doc: None,
+            manpage_skip: None,
+            value_hint: None,
+            help_heading,
             verbatim_doc_comment: None,
+            type_params,
 
             has_custom_parser: false,
             kind: Sp::new(Kind::Arg(Sp::new(Ty::Other, default_span)), default_span),
@@ -313,6 +351,8 @@ impl Attrs {
                     self.set_kind(kind);
                 }
 
+                Ignored(_) => {}
+
                 Skip(ident, expr) => {
                     let kind = Sp::new(Kind::Skip(expr), ident.span());
                     self.set_kind(kind);
@@ -320,39 +360,33 @@ impl Attrs {
 
                 NoVersion(ident) => self.no_version = Some(ident),
 
-                VerbatimDocComment(ident) => self.verbatim_doc_comment = Some(ident),
+                VerbatimDocComment(ident) => {
+                    self.verbatim_doc_comment = Some(ident);
+                    self.manpage.verbatim(true);
+                }
 
                 DefaultValue(ident, lit) => {
                     let val = if let Some(lit) = lit {
                         quote!(#lit)
                     } else {
-                        let ty = if let Some(ty) = self.ty.as_ref() {
-                            ty
-                        } else {
-                            abort!(
-                                ident,
-                                "#[structopt(default_value)] (without an argument) can be used \
-                                only on field level";
-
-                                note = "see \
-                                    https://docs.rs/structopt/0.3.5/structopt/#magical-methods")
-                        };
-
-                        quote_spanned!(ident.span()=> {
-                            ::structopt::lazy_static::lazy_static! {
-                                static ref DEFAULT_VALUE: &'static str = {
-                                    let val = <#ty as ::std::default::Default>::default();
-                                    let s = ::std::string::ToString::to_string(&val);
-                                    ::std::boxed::Box::leak(s.into_boxed_str())
-                                };
-                            }
-                            *DEFAULT_VALUE
-                        })
+                        self.computed_default(&ident, "default_value", None)
                     };
 
                     self.methods.push(Method::new(ident, val));
                 }
 
+                DefaultValueT(ident, expr) => {
+                    // clap 2 only takes strings, so literals are written out as one, which also
+                    // lets the manpage show them.
+                    let val = match expr.as_ref().and_then(literal_default) {
+                        Some(val) => LitStr::new(&val, ident.span()).into_token_stream(),
+                        None => self.computed_default(&ident, "default_value_t", expr),
+                    };
+
+                    let name = Ident::new("default_value", ident.span());
+                    self.methods.push(Method::new(name, val));
+                }
+
                 About(ident, about) => {
                     self.about = Some(Method::from_lit_or_env(
                         ident,
@@ -360,7 +394,7 @@ impl Attrs {
                         "CARGO_PKG_DESCRIPTION",
                     ));
                     self.manpage
-                        .description(self.about.as_ref().map(|m| m.args.to_string()));
+                        .description(self.about.as_ref().map(Method::value));
                 }
 
                 Manpage(_ident, path) => {
@@ -374,21 +408,49 @@ impl Attrs {
                 }
                 Author(ident, author) => {
                     self.author = Some(Method::from_lit_or_env(ident, author, "CARGO_PKG_AUTHORS"));
-                    self.manpage
-                        .author(self.author.as_ref().map(|m| m.args.to_string()));
+                    self.manpage.author(self.author.as_ref().map(Method::value));
                 }
 
                 Version(ident, version) => {
                     self.push_method(ident, version);
                     self.manpage
-                        .version(self.version.as_ref().map(|m| m.args.to_string()));
+                        .version(self.version.as_ref().map(Method::value));
                 }
 
                 NameLitStr(name, lit) => {
-                    self.manpage.name(lit.value());
+                    if name == "name" {
+                        self.manpage.name(lit.value());
+                    }
                     self.push_method(name, lit);
                 }
 
+                HelpHeading(_, heading) => self.help_heading = Some(heading.value()),
+
+                ValueHint(_, expr) => {
+                    use crate::manpage::ValueKind;
+
+                    let hint = match &expr {
+                        Expr::Path(path) => path.path.segments.last().map(|seg| &seg.ident),
+                        _ => None,
+                    };
+                    self.value_hint = match hint.map(Ident::to_string).as_deref() {
+                        Some("Unknown") | Some("Other") => None,
+                        Some("AnyPath") => Some(ValueKind::Path),
+                        Some("FilePath") => Some(ValueKind::File),
+                        Some("DirPath") => Some(ValueKind::Directory),
+                        Some("ExecutablePath") => Some(ValueKind::Executable),
+                        Some("CommandName")
+                        | Some("CommandString")
+                        | Some("CommandWithArguments") => Some(ValueKind::Command),
+                        Some("Username") => Some(ValueKind::Username),
+                        Some("Hostname") => Some(ValueKind::Hostname),
+                        Some("Url") => Some(ValueKind::Url),
+                        Some("EmailAddress") => Some(ValueKind::Email),
+                        _ => abort!(expr, "unknown value hint";
+                            help = "use a `ValueHint` variant, such as `value_hint = ValueHint::FilePath`"),
+                    };
+                }
+
                 NameExpr(name, expr) => {
                     self.push_method(name, expr);
                 }
@@ -397,6 +459,8 @@ impl Attrs {
 
                 RenameAll(_, casing_lit) => {
                     self.casing = CasingStyle::from_lit(casing_lit);
+                    // A variant's own casing renames its subcommand as well.
+                    self.manpage.name(literal_value(&self.cased_name()));
                 }
 
                 RenameAllEnv(_, casing_lit) => {
@@ -411,6 +475,481 @@ impl Attrs {
         }
     }
 
+    fn push_manpage_attrs(&mut self, attrs: &[Attribute]) {
+        use crate::parse::ManpageAttr::*;
+
+        for attr in parse_manpage_attributes(attrs) {
+            match attr {
+                Hardened(_) => {
+                    self.manpage.hardened(true);
+                }
+
+                NoProvenance(ident) => self.no_provenance = Some(ident),
+
+                NoBuiltinFlags(ident) => self.no_builtin_flags = Some(ident),
+
+                Doc(_, doc) => self.manpage_doc = Some(doc),
+
+                // Each attribute is a paragraph of its own.
+                Standards(_, standards) => {
+                    let standards = match self.manpage.standards.take() {
+                        Some(prev) => format!("{}\n\n{}", prev, standards.value()),
+                        None => standards.value(),
+                    };
+                    self.manpage.standards = Some(standards);
+                }
+
+                Skip(ident) => self.manpage_skip = Some(ident),
+
+                Stable(_) => {
+                    self.manpage.stable(true);
+                }
+
+                StdExitStatus(_) => {
+                    self.manpage.std_exit_status(true);
+                }
+
+                EscapeHyphens(_) => {
+                    self.manpage.escape_hyphens(true);
+                }
+
+                SubcommandPages(_) => {
+                    self.manpage.subcommand_pages(true);
+                }
+
+                Utf8(_) => {
+                    self.manpage.utf8(true);
+                }
+
+                Featured(_) => {
+                    self.manpage.featured(true);
+                }
+
+                Negatable(_) => {
+                    self.manpage.negatable(true);
+                }
+
+                Compress(ident) => {
+                    if !cfg!(feature = "gzip") {
+                        abort!(ident, "compressed manpages are not enabled";
+                            help = "enable the `gzip` feature of structopt-derive");
+                    }
+                    self.manpage.compress(true);
+                }
+
+                TagWidth(_, width) => {
+                    self.manpage.tag_width(Some(width.value()));
+                }
+
+                Os(_, os) => {
+                    self.manpage.os(Some(os.value()));
+                }
+
+                Bugs(_, bugs) => {
+                    self.manpage.bugs(Some(bugs.value()));
+                }
+
+                Copyright(_, copyright) => {
+                    self.manpage.copyright(Some(copyright.value()));
+                }
+
+                CopyrightFile(ident, path) => {
+                    self.manpage.copyright(Some(read_crate_file(&ident, &path)));
+                }
+
+                History(_, history) => {
+                    self.manpage.history(Some(history.value()));
+                }
+
+                HistoryFile(ident, path) => {
+                    self.manpage.history(Some(read_crate_file(&ident, &path)));
+                }
+
+                Caveats(_, caveats) => {
+                    self.manpage.caveats(Some(caveats.value()));
+                }
+
+                CaveatsFile(ident, path) => {
+                    self.manpage.caveats(Some(read_crate_file(&ident, &path)));
+                }
+
+                Version(_, version) => {
+                    self.manpage.version(Some(version.value()));
+                }
+
+                Section(ident, section) => {
+                    let mut chars = section.chars();
+                    let valid = matches!(chars.next(), Some('1'..='9'))
+                        && chars.all(|c| c.is_ascii_alphanumeric());
+                    if !valid {
+                        abort!(ident, "`{}` is not a manual section", section;
+                            help = "use a section from 1 to 9, optionally followed by a suffix as in `3p`";
+                            help = "to give a flag an entry in a section of the page, use `#[manpage(under = \"{}\")]`", section);
+                    }
+                    self.manpage.section(Some(section));
+                }
+
+                Under(ident, section) => {
+                    let section = section.value();
+                    if crate::manpage::OWN_SECTIONS.contains(&section.to_uppercase().as_str()) {
+                        abort!(ident, "flags can't have entries in the {} section", section;
+                            note = "the page writes it itself");
+                    }
+                    self.manpage.under(Some(section));
+                }
+
+                Date(_, date) => {
+                    let date = match date.value().as_str() {
+                        "build" => build_date(),
+                        date => date.to_string(),
+                    };
+                    self.manpage.date(Some(date));
+                }
+
+                ValueNames(_, style) => {
+                    use crate::manpage::ValueNames;
+
+                    let style = match style.value().as_str() {
+                        "uppercase" => ValueNames::Uppercase,
+                        "verbatim" => ValueNames::Verbatim,
+                        other => abort!(style, "unknown value name style `{}`", other;
+                            help = "use `value_names = \"uppercase\"` or `value_names = \"verbatim\"`"),
+                    };
+                    self.manpage.value_names(style);
+                }
+
+                SortOptions(_, order) => {
+                    use crate::manpage::OptionOrder;
+
+                    let order = match order.value().as_str() {
+                        "declaration" => OptionOrder::Declaration,
+                        "alpha" => OptionOrder::Alphabetical,
+                        other => abort!(order, "unknown option order `{}`", other;
+                            help = "use `sort_options = \"declaration\"` or `sort_options = \"alpha\"`"),
+                    };
+                    self.manpage.sort_options(order);
+                }
+
+                Cheatsheet(_, path) => {
+                    self.manpage.cheatsheet_path(PathBuf::from(path.value()));
+                }
+
+                Json(ident, path) => {
+                    if !cfg!(feature = "json") {
+                        abort!(ident, "JSON export is not enabled";
+                            help = "enable the `json` feature of structopt-derive");
+                    }
+                    self.manpage.json_path(PathBuf::from(path.value()));
+                }
+
+                Yaml(ident, path) => {
+                    if !cfg!(feature = "yaml") {
+                        abort!(ident, "YAML export is not enabled";
+                            help = "enable the `yaml` feature of structopt-derive");
+                    }
+                    self.manpage.yaml_path(PathBuf::from(path.value()));
+                }
+
+                Format(_, format) => {
+                    let name = format.value();
+                    let format = match crate::manpage::Format::from_name(&name) {
+                        Some(format) => format,
+                        None if crate::manpage::Format::NAMES.contains(&name.as_str()) => {
+                            abort!(format, "manpage format `{}` is not enabled", name;
+                                help = "enable the `{}` feature of structopt-derive", name)
+                        }
+                        None => abort!(format, "unknown manpage format";
+                            help = "use one of `mdoc`, `man`, `rst`, `docbook`, `texinfo`, `text` or `markdown`"),
+                    };
+                    self.manpage.format(format);
+                }
+
+                InvokedAs(_, names) => {
+                    for name in names {
+                        self.manpage.push_invocation(name.value());
+                    }
+                }
+
+                SeeAlso(_, pages) => {
+                    for page in pages {
+                        let val = page.value();
+                        match val.strip_suffix(')').and_then(|rest| rest.rsplit_once('(')) {
+                            Some((name, section)) if !name.is_empty() && !section.is_empty() => {
+                                self.manpage.see_also.push(crate::manpage::SeeAlso::new(
+                                    name.to_string(),
+                                    section.to_string(),
+                                ))
+                            }
+                            _ => abort!(page, "expected a page reference such as `git(1)`";
+                                help = "use `see_also(\"name(section)\", ...)`"),
+                        }
+                    }
+                }
+
+                Keybinding(ident, fields) => {
+                    let mut key = None;
+                    let mut action = None;
+                    let mut doc = None;
+                    for LitField { name, lit } in fields {
+                        match name.to_string().as_str() {
+                            "key" => key = Some(lit.value()),
+                            "action" => action = Some(lit.value()),
+                            "doc" => doc = Some(lit.value()),
+                            _ => abort!(name, "unexpected keybinding field: {}", name),
+                        }
+                    }
+                    let key = key.unwrap_or_else(|| {
+                        abort!(ident, "keybinding needs a key";
+                            help = "use `keybinding(key = \"...\", action = \"...\", doc = \"...\")`")
+                    });
+                    let mut binding = crate::manpage::Keybinding::new(key);
+                    if let Some(action) = action {
+                        binding.action(action);
+                    }
+                    if let Some(doc) = doc {
+                        binding.doc(doc);
+                    }
+                    self.manpage.keybindings.push(binding);
+                }
+
+                Example(ident, fields) => {
+                    let mut cmd = None;
+                    let mut desc = None;
+                    for LitField { name, lit } in fields {
+                        match name.to_string().as_str() {
+                            "cmd" => cmd = Some(lit.value()),
+                            "desc" => desc = Some(lit.value()),
+                            _ => abort!(name, "unexpected example field: {}", name),
+                        }
+                    }
+                    let cmd = cmd.unwrap_or_else(|| {
+                        abort!(ident, "example needs a command";
+                            help = "use `example(cmd = \"...\", desc = \"...\")`")
+                    });
+                    let mut example = crate::manpage::Example::new(cmd);
+                    if let Some(desc) = desc {
+                        example.doc(desc);
+                    }
+                    self.manpage.examples.push(example);
+                }
+
+                ExitStatus(ident, fields) => {
+                    let mut code = None;
+                    let mut doc = None;
+                    for LitField { name, lit } in fields {
+                        match name.to_string().as_str() {
+                            "code" => code = Some(lit.value()),
+                            "doc" => doc = Some(lit.value()),
+                            _ => abort!(name, "unexpected exit_status field: {}", name),
+                        }
+                    }
+                    let (code, doc) = match (code, doc) {
+                        (Some(code), Some(doc)) => (code, doc),
+                        _ => abort!(ident, "exit_status needs a code and its meaning";
+                            help = "use `exit_status(code = \"...\", doc = \"...\")`"),
+                    };
+                    self.manpage
+                        .exit_statuses
+                        .push(crate::manpage::ExitStatus::new(code, doc));
+                }
+
+                EnvVar(ident, fields) => {
+                    let mut var = None;
+                    let mut doc = None;
+                    for LitField { name, lit } in fields {
+                        match name.to_string().as_str() {
+                            "name" => var = Some(lit.value()),
+                            "doc" => doc = Some(lit.value()),
+                            _ => abort!(name, "unexpected env_var field: {}", name),
+                        }
+                    }
+                    match (var, doc) {
+                        (Some(var), Some(doc)) => self
+                            .manpage
+                            .env_vars
+                            .push(crate::manpage::Definition::new(var, doc)),
+                        _ => abort!(ident, "env_var needs a name and its effect";
+                            help = "use `env_var(name = \"...\", doc = \"...\")`"),
+                    }
+                }
+
+                Diagnostic(ident, fields) => {
+                    let mut message = None;
+                    let mut doc = None;
+                    for LitField { name, lit } in fields {
+                        match name.to_string().as_str() {
+                            "message" => message = Some(lit.value()),
+                            "doc" => doc = Some(lit.value()),
+                            _ => abort!(name, "unexpected diagnostic field: {}", name),
+                        }
+                    }
+                    match (message, doc) {
+                        (Some(message), Some(doc)) => self
+                            .manpage
+                            .diagnostics
+                            .push(crate::manpage::Definition::new(message, doc)),
+                        _ => abort!(ident, "diagnostic needs a message and a doc";
+                            help = "use `diagnostic(message = \"...\", doc = \"...\")`"),
+                    }
+                }
+
+                File(ident, fields) => {
+                    let mut path = None;
+                    let mut doc = None;
+                    for LitField { name, lit } in fields {
+                        match name.to_string().as_str() {
+                            "path" => path = Some(lit.value()),
+                            "doc" => doc = Some(lit.value()),
+                            _ => abort!(name, "unexpected file field: {}", name),
+                        }
+                    }
+                    match (path, doc) {
+                        (Some(path), Some(doc)) => self
+                            .manpage
+                            .files
+                            .push(crate::manpage::Definition::new(path, doc)),
+                        _ => abort!(ident, "file needs a path and a doc";
+                            help = "use `file(path = \"...\", doc = \"...\")`"),
+                    }
+                }
+
+                Definition(ident, fields) => {
+                    let mut term = None;
+                    let mut doc = None;
+                    for LitField { name, lit } in fields {
+                        match name.to_string().as_str() {
+                            "term" => term = Some(lit.value()),
+                            "doc" => doc = Some(lit.value()),
+                            _ => abort!(name, "unexpected definition field: {}", name),
+                        }
+                    }
+                    match (term, doc) {
+                        (Some(term), Some(doc)) => self
+                            .manpage
+                            .definitions
+                            .push(crate::manpage::Definition::new(term, doc)),
+                        _ => abort!(ident, "definition needs a term and a doc";
+                            help = "use `definition(term = \"...\", doc = \"...\")`"),
+                    }
+                }
+
+                Value(ident, fields) => {
+                    let mut name = None;
+                    let mut doc = None;
+                    for LitField { name: field, lit } in fields {
+                        match field.to_string().as_str() {
+                            "name" => name = Some(lit.value()),
+                            "doc" => doc = Some(lit.value()),
+                            _ => abort!(field, "unexpected value field: {}", field),
+                        }
+                    }
+                    match (name, doc) {
+                        (Some(name), Some(doc)) => self
+                            .manpage
+                            .values
+                            .push(crate::manpage::PossibleValue::new(name, Some(doc))),
+                        _ => abort!(ident, "value needs a name and a doc";
+                            help = "use `value(name = \"...\", doc = \"...\")`"),
+                    }
+                }
+
+                Subsection(ident, fields) => {
+                    let mut title = None;
+                    let mut doc = None;
+                    for LitField { name, lit } in fields {
+                        match name.to_string().as_str() {
+                            "title" => title = Some(lit.value()),
+                            "doc" => doc = Some(lit.value()),
+                            _ => abort!(name, "unexpected subsection field: {}", name),
+                        }
+                    }
+                    match (title, doc) {
+                        (Some(title), Some(doc)) => self
+                            .manpage
+                            .subsections
+                            .push(crate::manpage::Subsection::new(title, doc)),
+                        _ => abort!(ident, "subsection needs a title and a doc";
+                            help = "use `subsection(title = \"...\", doc = \"...\")`"),
+                    }
+                }
+
+                Settings(_, sources) => {
+                    use syn::{Meta, NestedMeta};
+
+                    let mut settings = crate::manpage::Settings::default();
+                    for source in sources {
+                        match source {
+                            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("environment") => {
+                                settings.environment = true;
+                            }
+                            NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                                path,
+                                lit: syn::Lit::Str(lit),
+                                ..
+                            })) if path.is_ident("config_file") => {
+                                settings.config_file = Some(lit.value());
+                            }
+                            other => abort!(other, "unexpected settings source";
+                                help = "use `settings(environment, config_file = \"...\")`"),
+                        }
+                    }
+                    self.manpage.settings = Some(settings);
+                }
+
+                FrontMatter(_, fields) => {
+                    use crate::manpage::FrontMatterStyle;
+                    use syn::{Meta, NestedMeta};
+
+                    let mut front_matter = crate::manpage::FrontMatter::default();
+                    for field in fields {
+                        match field {
+                            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("yaml") => {
+                                front_matter.style = FrontMatterStyle::Yaml;
+                            }
+                            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("toml") => {
+                                front_matter.style = FrontMatterStyle::Toml;
+                            }
+                            NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                                path,
+                                lit: syn::Lit::Str(lit),
+                                ..
+                            })) if path.is_ident("title") => {
+                                front_matter.title = Some(lit.value());
+                            }
+                            NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                                path,
+                                lit: syn::Lit::Str(lit),
+                                ..
+                            })) if path.is_ident("date") => {
+                                front_matter.date = Some(lit.value());
+                            }
+                            NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                                path,
+                                lit: syn::Lit::Str(lit),
+                                ..
+                            })) if path.is_ident("section") => {
+                                front_matter.section = Some(lit.value());
+                            }
+                            other => abort!(other, "unexpected front matter field";
+                                help = "use `front_matter(yaml | toml, title = \"...\", date = \"...\", section = \"...\")`"),
+                        }
+                    }
+                    self.manpage.front_matter = Some(front_matter);
+                }
+
+                OptionsList(_, fields) => {
+                    let style = list_style(crate::manpage::ListStyle::options(), fields);
+                    self.manpage.options_list(style);
+                }
+
+                SubcommandsList(_, fields) => {
+                    let style = list_style(crate::manpage::ListStyle::subcommands(), fields);
+                    self.manpage.subcommands_list(style);
+                }
+            }
+        }
+    }
+
     fn push_doc_comment(&mut self, attrs: &[Attribute], name: &str) {
         use crate::Lit::*;
         use crate::Meta::*;
@@ -431,15 +970,61 @@ impl Attrs {
 
         self.doc_comment =
             process_doc_comment(comment_parts, name, self.verbatim_doc_comment.is_none());
-        self.manpage
-            .long_description(Some(self.doc_comment.iter().fold(
-                String::new(),
-                |mut acc, method| {
-                    acc.extend(method.args.to_string().chars());
-                    acc.push('\n');
-                    acc
-                },
-            )));
+        let doc = self.manpage_doc(name);
+        self.manpage.long_description(Some(doc));
+    }
+
+    /// A `default_value` computed once at run time: `expr`, or the type's `Default`, as a string.
+    fn computed_default(&self, ident: &Ident, attr: &str, expr: Option<Expr>) -> TokenStream {
+        let ty = if let Some(ty) = self.ty.as_ref() {
+            ty
+        } else if expr.is_none() {
+            abort!(
+                ident,
+                "#[structopt({})] (without an argument) can be used only on field level", attr;
+
+                note = "see https://docs.rs/structopt/0.3.5/structopt/#magical-methods")
+        } else {
+            abort!(
+                ident,
+                "#[structopt({})] can be used only on field level",
+                attr
+            )
+        };
+        let val = match expr {
+            Some(expr) => quote!(#expr),
+            None => quote!(<#ty as ::std::default::Default>::default()),
+        };
+
+        quote_spanned!(ident.span()=> {
+            ::structopt::lazy_static::lazy_static! {
+                static ref DEFAULT_VALUE: &'static str = {
+                    let val: #ty = #val;
+                    let s = ::std::string::ToString::to_string(&val);
+                    ::std::boxed::Box::leak(s.into_boxed_str())
+                };
+            }
+            *DEFAULT_VALUE
+        })
+    }
+
+    /// The text for the item's manpage entry: its `long_help` (`long_about` for commands), or its
+    /// `help` (`about`) when it has none. As in clap, methods take precedence over the doc comment,
+    /// whose paragraphs after the first only go into the long text. A `#[manpage(doc = "...")]`
+    /// overrides all of them, leaving `--help` as it is.
+    pub fn manpage_doc(&self, name: &str) -> String {
+        if let Some(doc) = &self.manpage_doc {
+            return doc.value() + "\n";
+        }
+        let long_name = format!("long_{}", name);
+        [long_name.as_str(), name]
+            .iter()
+            .find_map(|name| {
+                self.find_method(name)
+                    .or_else(|| self.doc_comment.iter().find(|m| m.name == name))
+            })
+            .map(|method| method.value() + "\n")
+            .unwrap_or_default()
     }
 
     pub fn from_struct(
@@ -453,6 +1038,7 @@ impl Attrs {
     ) -> Self {
         let mut res = Self::new(span, name, parent_attrs, None, argument_casing, env_casing);
         res.push_attrs(attrs);
+        res.push_manpage_attrs(attrs);
         res.push_doc_comment(attrs, "about");
 
         if res.has_custom_parser {
@@ -470,13 +1056,19 @@ impl Attrs {
         }
     }
 
+    /// The attributes of `field`, the `index`th of its struct. A tuple struct field is named
+    /// `arg<index>` unless a `name` attribute names it.
     pub fn from_field(
         field: &syn::Field,
+        index: usize,
         parent_attrs: Option<&Attrs>,
         struct_casing: Sp<CasingStyle>,
         env_casing: Sp<CasingStyle>,
     ) -> Self {
-        let name = field.ident.clone().unwrap();
+        let name = field
+            .ident
+            .clone()
+            .unwrap_or_else(|| Ident::new(&format!("arg{}", index), field.span()));
         let mut res = Self::new(
             field.span(),
             Name::Derived(name),
@@ -486,6 +1078,7 @@ impl Attrs {
             env_casing,
         );
         res.push_attrs(&field.attrs);
+        res.push_manpage_attrs(&field.attrs);
         res.push_doc_comment(&field.attrs, "help");
 
         match &*res.kind {
@@ -550,6 +1143,16 @@ impl Attrs {
                         "methods are not allowed for skipped fields"
                     );
                 }
+                if let Some(attr) = field
+                    .attrs
+                    .iter()
+                    .find(|attr| attr.path.is_ident("manpage"))
+                {
+                    abort!(attr,
+                        "manpage attributes are not allowed for skipped fields";
+                        help = "skipped fields are not arguments, so the manpage leaves them out"
+                    );
+                }
             }
             Kind::Arg(orig_ty) => {
                 let mut ty = Ty::from_syn_ty(&field.ty);
@@ -577,6 +1180,13 @@ impl Attrs {
                         if let Some(m) = res.find_method("required") {
                             abort!(m.name, "required is meaningless for bool")
                         }
+                        if res.manpage.negatable && !res.has_method("long") {
+                            abort!(field, "only flags with a long name can be negated";
+                                help = "add `long` to the field's `structopt` attribute")
+                        }
+                    }
+                    _ if res.manpage.negatable => {
+                        abort!(field.ty, "only `bool` flags can be negated")
                     }
                     Ty::Option => {
                         if let Some(m) = res.find_method("default_value") {
@@ -586,21 +1196,17 @@ impl Attrs {
                             abort!(m.name, "required is meaningless for Option")
                         }
                     }
-                    Ty::OptionOption => {
-                        if res.is_positional() {
-                            abort!(
-                                field.ty,
-                                "Option<Option<T>> type is meaningless for positional argument"
-                            )
-                        }
+                    Ty::OptionOption if res.is_positional() => {
+                        abort!(
+                            field.ty,
+                            "Option<Option<T>> type is meaningless for positional argument"
+                        )
                     }
-                    Ty::OptionVec => {
-                        if res.is_positional() {
-                            abort!(
-                                field.ty,
-                                "Option<Vec<T>> type is meaningless for positional argument"
-                            )
-                        }
+                    Ty::OptionVec if res.is_positional() => {
+                        abort!(
+                            field.ty,
+                            "Option<Vec<T>> type is meaningless for positional argument"
+                        )
                     }
 
                     _ => (),
@@ -631,6 +1237,56 @@ impl Attrs {
         self.methods.iter().find(|m| m.name == name)
     }
 
+    /// The heading the option is listed under, if it or its struct has a `help_heading`.
+    pub fn help_heading(&self) -> Option<String> {
+        self.help_heading.clone()
+    }
+
+    /// What the value stands for, if `value_hint` says.
+    pub fn value_hint(&self) -> Option<crate::manpage::ValueKind> {
+        self.value_hint
+    }
+
+    /// Whether the item is left out of the manpage with `#[manpage(skip)]`, while clap still
+    /// parses and documents it in `--help`.
+    pub fn is_manpage_skipped(&self) -> bool {
+        self.manpage_skip.is_some()
+    }
+
+    /// Whether an argument or subcommand is hidden, with `hidden = true`, `hide = true` or, for a
+    /// subcommand, `setting = AppSettings::Hidden`.
+    pub fn is_hidden(&self) -> bool {
+        self.has_setting("Hidden")
+            || ["hidden", "hide"].iter().any(|name| {
+                self.find_method(name)
+                    .map_or(false, |m| m.args.to_string() == "true")
+            })
+    }
+
+    /// Whether the `AppSettings` variant named `setting` is given to `setting` or `settings`.
+    pub fn has_setting(&self, setting: &str) -> bool {
+        self.methods
+            .iter()
+            .filter(|m| m.name == "setting" || m.name == "settings")
+            .any(|m| {
+                m.args
+                    .to_string()
+                    .split(|c: char| !c.is_alphanumeric() && c != '_')
+                    .any(|word| word == setting)
+            })
+    }
+
+    /// List the `--help` and `--version` flags clap adds, unless the app turns them off or has
+    /// `#[manpage(no_builtin_flags)]`.
+    pub fn push_builtin_flags(&mut self) {
+        if self.no_builtin_flags.is_some() {
+            return;
+        }
+        let help = !self.has_setting("DisableHelpFlags");
+        let version = !self.has_setting("DisableVersion") && !self.version().is_empty();
+        self.manpage.push_builtin_flags(help, version);
+    }
+
     /// generate methods from attributes on top of struct or enum
     pub fn top_level_methods(&self) -> TokenStream {
         let author = &self.author;
@@ -660,6 +1316,35 @@ impl Attrs {
         }
     }
 
+    /// Record which item the manpage is generated from, unless `no_provenance` is set.
+    pub fn set_provenance(&mut self, item: &str, ident: &Ident) {
+        if self.no_provenance.is_none() {
+            self.manpage.provenance(Some(format!(
+                "structopt-derive-manpage v{} from {} {}",
+                env!("CARGO_PKG_VERSION"),
+                item,
+                ident
+            )));
+        }
+    }
+
+    pub fn set_type_params(&mut self, generics: &Generics) {
+        self.type_params = generics
+            .type_params()
+            .map(|param| param.ident.clone())
+            .collect();
+    }
+
+    /// Whether `ty` is one of the type parameters of the struct or enum.
+    pub fn is_type_param(&self, ty: &Type) -> bool {
+        match ty {
+            Type::Path(TypePath { qself: None, path }) => path
+                .get_ident()
+                .map_or(false, |ident| self.type_params.contains(ident)),
+            _ => false,
+        }
+    }
+
     pub fn cased_name(&self) -> TokenStream {
         self.name.clone().translate(*self.casing)
     }
@@ -727,3 +1412,116 @@ fn process_author_str(author: &str) -> String {
 
     res
 }
+
+/// `expr` as its `to_string` would spell it, when it is a literal whose value is known here.
+fn literal_default(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Lit(ExprLit { lit, .. }) => match lit {
+            Lit::Str(lit) => Some(lit.value()),
+            Lit::Char(lit) => Some(lit.value().to_string()),
+            Lit::Int(lit) => Some(lit.base10_digits().to_string()),
+            Lit::Float(lit) => lit.base10_parse::<f64>().ok().map(|val| val.to_string()),
+            Lit::Bool(lit) => Some(lit.value.to_string()),
+            _ => None,
+        },
+        Expr::Unary(ExprUnary {
+            op: UnOp::Neg(_),
+            expr,
+            ..
+        }) => match &**expr {
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(_) | Lit::Float(_),
+                ..
+            }) => literal_default(expr).map(|val| format!("-{}", val)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Today's date in the `May 1, 2022` form mdoc uses, or the date `SOURCE_DATE_EPOCH` is set to
+/// for reproducible builds.
+fn build_date() -> String {
+    const MONTHS: [&str; 12] = [
+        "January",
+        "February",
+        "March",
+        "April",
+        "May",
+        "June",
+        "July",
+        "August",
+        "September",
+        "October",
+        "November",
+        "December",
+    ];
+
+    let secs = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|val| val.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs())
+        });
+    // Howard Hinnant's days-to-civil conversion, for days since 1970-01-01.
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{} {}, {}", MONTHS[month as usize - 1], day, year)
+}
+
+/// The trimmed contents of the file at `path`, which is resolved against the crate root when it
+/// is relative, like `include_str!`'s paths are against the source file.
+fn read_crate_file(ident: &Ident, path: &LitStr) -> String {
+    let mut file = PathBuf::from(path.value());
+    if file.is_relative() {
+        if let Ok(dir) = env::var("CARGO_MANIFEST_DIR") {
+            file = PathBuf::from(dir).join(file);
+        }
+    }
+    match std::fs::read_to_string(&file) {
+        Ok(contents) => contents.trim().to_string(),
+        Err(err) => abort!(ident, "cannot read {}: {}", file.display(), err;
+            help = "`{}` paths are relative to the crate root", ident),
+    }
+}
+
+/// `style` changed by the fields of an `options_list(...)` or `subcommands_list(...)` attribute.
+fn list_style(
+    mut style: crate::manpage::ListStyle,
+    fields: Vec<syn::NestedMeta>,
+) -> crate::manpage::ListStyle {
+    use syn::{Meta, NestedMeta};
+
+    for field in fields {
+        match field {
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("compact") => {
+                style.compact = true;
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("spaced") => {
+                style.compact = false;
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("no_offset") => {
+                style.offset = None;
+            }
+            NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                path,
+                lit: syn::Lit::Str(lit),
+                ..
+            })) if path.is_ident("offset") => {
+                style.offset = Some(lit.value());
+            }
+            other => abort!(other, "unexpected list field";
+                help = "use `compact`, `spaced`, `no_offset` or `offset = \"...\"`"),
+        }
+    }
+    style
+}
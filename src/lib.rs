@@ -23,9 +23,10 @@ mod manpage;
 mod parse;
 mod spanned;
 mod ty;
+mod warnings;
 
 use crate::{
-    attrs::{Attrs, CasingStyle, Kind, Name, ParserKind},
+    attrs::{literal_value, Attrs, CasingStyle, Kind, Name, ParserKind},
     spanned::Sp,
     ty::{is_simple_ty, sub_type, subty_if_name, Ty},
 };
@@ -52,14 +53,50 @@ struct GenOutput {
 }
 
 /// Generates the `StructOpt` impl.
-#[proc_macro_derive(StructOpt, attributes(structopt))]
+#[proc_macro_derive(StructOpt, attributes(structopt, manpage))]
 #[proc_macro_error]
 pub fn structopt(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input: DeriveInput = syn::parse(input).unwrap();
-    let gen = impl_structopt(&input);
+    // Whatever an aborted expansion warned about is not this one's concern.
+    warnings::take();
+    let mut gen = impl_structopt(&input);
+    gen.extend(warnings::take());
     gen.into()
 }
 
+/// The primitive types whose values are documented as numbers.
+const NUMBER_TYPES: &[&str] = &[
+    "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize", "f32",
+    "f64",
+];
+
+/// Warn that the flags of the struct or enum `ty`, or `T` in `Option<T>`, are left out of the
+/// manpage of the one `parent_attribute` belongs to, if that one writes a page.
+///
+/// Each derive only sees the item it is on, so what `ty` adds to the app is only known to its own
+/// page.
+fn left_out(ty: &Type, parent_attribute: &Attrs) {
+    if parent_attribute.manpage.path.is_none() {
+        return;
+    }
+    let ty = subty_if_name(ty, "Option").unwrap_or(ty);
+    let reason = if parent_attribute.is_type_param(ty) {
+        "it is a type parameter"
+    } else {
+        "it is derived separately"
+    };
+    warnings::emit(
+        ty.span(),
+        format!(
+            "the flags of `{}` are left out of the manpage of `{}`, as {}; \
+             `#[manpage(skip)]` leaves them out without this warning",
+            quote!(#ty),
+            parent_attribute.manpage.name,
+            reason
+        ),
+    );
+}
+
 /// Generate a block of code to add arguments/subcommands corresponding to
 /// the `fields` to an app.
 fn gen_augmentation(
@@ -67,9 +104,21 @@ fn gen_augmentation(
     app_var: &Ident,
     parent_attribute: &mut Attrs,
 ) -> TokenStream {
-    let mut subcmds = fields.iter().filter_map(|field| {
+    // Groups are defined before the fields that join them.
+    let groups = parent_attribute
+        .methods
+        .iter()
+        .filter(|method| method.name == "group")
+        .filter_map(|method| arg_group(&method.args))
+        .collect::<Vec<_>>();
+    parent_attribute.manpage.groups.extend(groups);
+    if parent_attribute.has_setting("TrailingVarArg") {
+        parent_attribute.manpage.trailing_var_arg(true);
+    }
+    let mut subcmds = fields.iter().enumerate().filter_map(|(index, field)| {
         let attrs = Attrs::from_field(
             field,
+            index,
             Some(parent_attribute),
             parent_attribute.casing(),
             parent_attribute.env_casing(),
@@ -113,9 +162,10 @@ fn gen_augmentation(
 
     let app_methods = parent_attribute.top_level_methods();
     let version = parent_attribute.version();
-    let args = fields.iter().filter_map(|field| {
+    let args = fields.iter().enumerate().filter_map(|(index, field)| {
         let attrs = Attrs::from_field(
             field,
+            index,
             Some(parent_attribute),
             parent_attribute.casing(),
             parent_attribute.env_casing(),
@@ -126,7 +176,11 @@ fn gen_augmentation(
                 kind.span(),
                 "`external_subcommand` is only allowed on enum variants"
             ),
-            Kind::Subcommand(_) => {
+            Kind::Subcommand(_) if attrs.is_manpage_skipped() => None,
+            Kind::Subcommand(ty) => {
+                parent_attribute
+                    .manpage
+                    .optional_subcommand(**ty == Ty::Option);
                 parent_attribute
                     .manpage
                     .push_subcommand(attrs.manpage.clone());
@@ -135,6 +189,9 @@ fn gen_augmentation(
             Kind::Skip(_) => None,
             Kind::Flatten => {
                 let ty = &field.ty;
+                if !attrs.is_manpage_skipped() {
+                    left_out(ty, parent_attribute);
+                }
                 Some(quote_spanned! { kind.span()=>
                     let #app_var = <#ty as ::structopt::StructOptInternal>::augment_clap(#app_var);
                     let #app_var = if <#ty as ::structopt::StructOptInternal>::is_subcommand() {
@@ -226,65 +283,223 @@ fn gen_augmentation(
                     }
                 };
 
+                // The arguments clap requires, as set by `modifier` above.
+                let required = matches!(**ty, Ty::Other)
+                    && !occurrences
+                    && !flag
+                    && !attrs.has_method("default_value");
                 let name = attrs.cased_name();
                 let methods = attrs.field_methods();
                 {
-                    let doc = attrs
-                        .doc_comment
-                        .iter()
-                        .fold(String::new(), |mut acc, doc| {
-                            acc.push_str(doc.args.to_string().trim_matches('"'));
-                            acc.push('\n');
-                            acc
-                        });
+                    let doc = attrs.manpage_doc("help");
+                    // A `value_hint` says what the value is. Otherwise the parser says as much as the
+                    // type: `OsStr` values are nearly always paths.
+                    let kind = if let Some(hint) = attrs.value_hint() {
+                        Some(hint)
+                    } else if is_simple_ty(convert_type, "PathBuf")
+                        || matches!(
+                            *attrs.parser().kind,
+                            ParserKind::FromOsStr | ParserKind::TryFromOsStr
+                        )
+                    {
+                        Some(crate::manpage::ValueKind::Path)
+                    } else if NUMBER_TYPES.iter().any(|ty| is_simple_ty(convert_type, ty)) {
+                        Some(crate::manpage::ValueKind::Number)
+                    } else {
+                        None
+                    };
                     let mut flag = crate::manpage::Flag::new();
-                    flag.doc(doc);
+                    flag.featured(attrs.manpage.featured)
+                        .negatable(attrs.manpage.negatable)
+                        .verbatim(attrs.manpage.verbatim);
+                    if !doc.trim().is_empty() {
+                        flag.doc(doc);
+                    }
                     match **ty {
                         Ty::Bool => {}
                         Ty::Option => {
                             flag.args(crate::manpage::TakesValue {
-                                kind: None,
+                                kind,
                                 multiple: false,
+                                names: vec![],
+                                number_of_values: None,
+                                optional_value: false,
                             });
                         }
                         Ty::OptionOption => {
                             flag.args(crate::manpage::TakesValue {
-                                kind: None,
+                                kind,
                                 multiple: false,
+                                names: vec![],
+                                number_of_values: None,
+                                optional_value: true,
                             });
                         }
                         Ty::OptionVec => {
                             flag.args(crate::manpage::TakesValue {
-                                kind: None,
+                                kind,
                                 multiple: true,
+                                names: vec![],
+                                number_of_values: None,
+                                optional_value: true,
                             });
                         }
                         Ty::Vec => {
                             flag.args(crate::manpage::TakesValue {
-                                kind: None,
+                                kind,
                                 multiple: true,
+                                names: vec![],
+                                number_of_values: None,
+                                optional_value: false,
                             });
                         }
                         Ty::Other if occurrences => {}
                         //Ty::Other if flag => {}
                         Ty::Other => {
                             flag.args(crate::manpage::TakesValue {
-                                kind: None,
+                                kind,
                                 multiple: false,
+                                names: vec![],
+                                number_of_values: None,
+                                optional_value: false,
                             });
                         }
                     };
+                    flag.required(required).id(literal_value(&name));
+                    let mut group = None;
                     for method in attrs.methods.iter() {
                         if method.name == "long" {
-                            flag.long(method.args.to_string());
+                            flag.long(method.value());
                         } else if method.name == "short" {
-                            flag.short(method.args.to_string());
+                            // clap keeps the first character, which for a bare `short` is that of
+                            // the field's cased name.
+                            if let Some(short) =
+                                method.value().trim_start_matches('-').chars().next()
+                            {
+                                flag.short(short.to_string());
+                            }
+                        } else if method.name == "required" {
+                            flag.required(method.args.to_string() == "true");
+                        } else if method.name == "global" {
+                            flag.global(method.args.to_string() == "true");
+                        } else if method.name == "conflicts_with"
+                            || method.name == "conflicts_with_all"
+                        {
+                            for id in string_literals(&method.args) {
+                                flag.push_conflict(id);
+                            }
+                        } else if method.name == "requires" || method.name == "requires_all" {
+                            for id in string_literals(&method.args) {
+                                flag.push_requirement(id);
+                            }
+                        } else if method.name == "required_unless"
+                            || method.name == "required_unless_one"
+                            || method.name == "required_unless_all"
+                        {
+                            // Such arguments can be left out, so the synopsis brackets them.
+                            for id in string_literals(&method.args) {
+                                flag.push_required_unless(id);
+                            }
+                            flag.required(false)
+                                .required_unless_all(method.name == "required_unless_all");
+                        } else if method.name == "required_if" || method.name == "required_ifs" {
+                            for pair in string_literals(&method.args).chunks(2) {
+                                if let [id, value] = pair {
+                                    flag.push_required_if(id.clone(), value.clone());
+                                }
+                            }
+                        } else if method.name == "env" {
+                            flag.env(method.value());
+                        } else if method.name == "default_value" {
+                            // Only literal defaults are known here; the others are computed at run time.
+                            if let Ok(lit) = syn::parse2::<LitStr>(method.args.clone()) {
+                                flag.default_value(lit.value());
+                            }
+                        } else if method.name == "possible_values"
+                            || method.name == "possible_value"
+                        {
+                            // Only literal values are known here, not those of `arg_enum!`.
+                            for value in string_literals(&method.args) {
+                                flag.push_possible_value(value);
+                            }
+                        } else if method.name == "visible_alias" || method.name == "visible_aliases"
+                        {
+                            for alias in string_literals(&method.args) {
+                                flag.push_visible_alias(alias);
+                            }
+                        } else if method.name == "alias" || method.name == "aliases" {
+                            for alias in string_literals(&method.args) {
+                                flag.push_alias(alias);
+                            }
+                        } else if method.name == "value_name" {
+                            flag.value_name(method.value());
+                        } else if method.name == "value_names" {
+                            for name in string_literals(&method.args) {
+                                flag.push_value_name(name);
+                            }
+                        } else if method.name == "number_of_values" {
+                            if let Ok(lit) = syn::parse2::<LitInt>(method.args.clone()) {
+                                if let Ok(number) = lit.base10_parse() {
+                                    flag.number_of_values(number);
+                                }
+                            }
+                        } else if method.name == "group" {
+                            group = string_literals(&method.args).pop();
+                        } else if method.name == "last" {
+                            flag.last(method.args.to_string() == "true");
                         }
                     }
-                    parent_attribute.manpage.flags.push(flag);
+                    parent_attribute.manpage.join_groups(&mut flag, group);
+                    if let Some(section) = attrs.manpage.under.clone() {
+                        flag.section(section);
+                    }
+                    if let Some(heading) = attrs.help_heading() {
+                        flag.heading(heading);
+                    }
+                    flag.case_insensitive(
+                        attrs
+                            .find_method("case_insensitive")
+                            .map_or(false, |method| method.args.to_string() == "true"),
+                    );
+                    // The docs of `arg_enum!` variants are not visible from here, so the values
+                    // are documented on the field, and must be among those it lists if any.
+                    if let Some(value) = attrs
+                        .manpage
+                        .values
+                        .iter()
+                        .find(|value| !flag.accepts_value(value.name()))
+                    {
+                        abort!(
+                            field,
+                            "`{}` is not one of the possible values of `{}`",
+                            value.name(),
+                            literal_value(&name);
+                            help = "document the values given to `possible_values`"
+                        );
+                    }
+                    for value in attrs.manpage.values.iter() {
+                        flag.document_value(value.clone());
+                    }
+                    if !attrs.has_method("long") && !attrs.has_method("short") {
+                        flag.positional(literal_value(&name));
+                    } else {
+                        // `Vec` options, those set `multiple` and those counting their
+                        // occurrences can all be given more than once.
+                        let multiple = attrs
+                            .find_method("multiple")
+                            .map_or(false, |method| method.args.to_string() == "true");
+                        flag.repeatable(
+                            occurrences || multiple || matches!(**ty, Ty::Vec | Ty::OptionVec),
+                        );
+                    }
+                    // Hidden arguments are internal, so the page leaves them out, as it does those
+                    // marked `#[manpage(skip)]`.
+                    if !attrs.is_hidden() && !attrs.is_manpage_skipped() {
+                        parent_attribute.manpage.flags.push(flag);
+                    }
                     parent_attribute
                         .manpage
-                        .push_long_flag(Some(methods.to_string()), name.to_string().into());
+                        .push_long_flag(Some(methods.to_string()), name.to_string());
                 }
 
                 Some(quote_spanned! { field.span()=>
@@ -306,6 +521,62 @@ fn gen_augmentation(
     }}
 }
 
+/// The group a `group = ArgGroup::with_name("...")` attribute defines, with the settings and
+/// arguments it gives as literals.
+fn arg_group(args: &TokenStream) -> Option<crate::manpage::ArgGroup> {
+    let mut ret: Option<crate::manpage::ArgGroup> = None;
+    let mut method = None;
+    for tree in args.clone() {
+        match tree {
+            proc_macro2::TokenTree::Ident(ident) => method = Some(ident.to_string()),
+            proc_macro2::TokenTree::Group(args) => {
+                let args = args.stream();
+                match (method.take().as_deref(), ret.as_mut()) {
+                    (Some("with_name"), _) => {
+                        ret = string_literals(&args)
+                            .pop()
+                            .map(crate::manpage::ArgGroup::new);
+                    }
+                    (Some("required"), Some(group)) => {
+                        group.required(args.to_string() == "true");
+                    }
+                    (Some("multiple"), Some(group)) => {
+                        group.multiple(args.to_string() == "true");
+                    }
+                    (Some("arg" | "args"), Some(group)) => {
+                        for arg in string_literals(&args) {
+                            group.push_arg(arg);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    ret
+}
+
+/// The string literals in the arguments of a method, such as the names given to
+/// `conflicts_with_all`.
+fn string_literals(args: &TokenStream) -> Vec<String> {
+    let mut ret = vec![];
+    for tree in args.clone() {
+        match tree {
+            proc_macro2::TokenTree::Group(group) => ret.extend(string_literals(&group.stream())),
+            proc_macro2::TokenTree::Literal(lit) => {
+                if let Ok(lit) =
+                    syn::parse2::<LitStr>(TokenStream::from(proc_macro2::TokenTree::Literal(lit)))
+                {
+                    ret.push(lit.value());
+                }
+            }
+            _ => {}
+        }
+    }
+    ret
+}
+
 fn gen_constructor(fields: &Punctuated<Field, Comma>, parent_attribute: &mut Attrs) -> TokenStream {
     // This ident is used in several match branches below,
     // and the `quote[_spanned]` invocations have different spans.
@@ -317,14 +588,22 @@ fn gen_constructor(fields: &Punctuated<Field, Comma>, parent_attribute: &mut Att
     // is always the same, we factor it out.
     let matches = format_ident!("matches");
 
-    let fields = fields.iter().map(|field| {
+    let fields = fields.iter().enumerate().map(|(index, field)| {
         let attrs = Attrs::from_field(
             field,
+            index,
             Some(parent_attribute),
             parent_attribute.casing(),
             parent_attribute.env_casing(),
         );
-        let field_name = field.ident.as_ref().unwrap();
+        // Tuple struct fields are set by index, as in `Args { 0: value }`.
+        let field_name = match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index {
+                index: index as u32,
+                span: field.span(),
+            }),
+        };
         let kind = attrs.kind();
         match &*kind {
             Kind::ExternalSubcommand => abort!(
@@ -474,7 +753,7 @@ fn gen_from_clap(
 fn gen_clap(attrs: &[Attribute]) -> GenOutput {
     let name = std::env::var("CARGO_PKG_NAME").ok().unwrap_or_default();
 
-    let attrs = Attrs::from_struct(
+    let mut attrs = Attrs::from_struct(
         Span::call_site(),
         attrs,
         Name::Assigned(quote!(#name)),
@@ -483,8 +762,57 @@ fn gen_clap(attrs: &[Attribute]) -> GenOutput {
         Sp::call_site(DEFAULT_ENV_CASING),
         false,
     );
+    // Without an `author` attribute the page credits the package's authors, `:`-separated.
+    if attrs.manpage.author.is_none() {
+        attrs
+            .manpage
+            .author(std::env::var("CARGO_PKG_AUTHORS").ok());
+    }
+    // Bugs are reported to the package's repository unless `bugs` says otherwise; `bugs = ""`
+    // leaves the BUGS section out.
+    match attrs.manpage.bugs.as_deref() {
+        None => {
+            let repository = std::env::var("CARGO_PKG_REPOSITORY").ok();
+            attrs
+                .manpage
+                .bugs(repository.filter(|repository| !repository.is_empty()));
+        }
+        Some("") => {
+            attrs.manpage.bugs(None);
+        }
+        Some(_) => {}
+    }
+    // Likewise the page closes with the package's license unless `copyright` says otherwise.
+    match attrs.manpage.copyright.as_deref() {
+        None => {
+            let license = std::env::var("CARGO_PKG_LICENSE").ok();
+            let copyright = license
+                .filter(|license| !license.is_empty())
+                .map(|license| format!("{} is licensed under {}.", attrs.manpage.name, license));
+            attrs.manpage.copyright(copyright);
+        }
+        Some("") => {
+            attrs.manpage.copyright(None);
+        }
+        Some(_) => {}
+    }
     let tokens = {
         let name = attrs.cased_name();
+        // Only a literal name is known here; the page keeps the package name otherwise.
+        if attrs.manpage.path.is_some() && syn::parse2::<LitStr>(name.clone()).is_err() {
+            let span = name
+                .clone()
+                .into_iter()
+                .next()
+                .map_or_else(Span::call_site, |token| token.span());
+            warnings::emit(
+                span,
+                format!(
+                    "`name = {}` is not a string literal, so the manpage is named `{}`",
+                    name, attrs.manpage.name
+                ),
+            );
+        }
         quote!(::structopt::clap::App::new(#name))
     };
 
@@ -565,6 +893,7 @@ fn gen_augment_clap_enum(
             Kind::Skip(_) => None,
 
             Kind::ExternalSubcommand => {
+                parent_attribute.manpage.external_subcommands(true);
                 let app_var = Ident::new("app", Span::call_site());
                 Some(quote_spanned! { attrs.kind().span()=>
                     let #app_var = #app_var.setting(
@@ -577,6 +906,9 @@ fn gen_augment_clap_enum(
                 match variant.fields {
                     Unnamed(FieldsUnnamed { ref unnamed, .. }) if unnamed.len() == 1 => {
                         let ty = &unnamed[0];
+                        if !attrs.is_manpage_skipped() {
+                            left_out(&ty.ty, parent_attribute);
+                        }
                         Some(quote! {
                             let app = <#ty as ::structopt::StructOptInternal>::augment_clap(app);
                         })
@@ -593,6 +925,8 @@ fn gen_augment_clap_enum(
                 let from_attrs = attrs.top_level_methods();
                 let version = attrs.version();
 
+                // The flags of a tuple variant's payload are not known here.
+                let own_page = !matches!(variant.fields, Unnamed(..));
                 let arg_block = match variant.fields {
                     // If the variant is named, then gen_augmentation already generates the
                     // top level methods (#from_attrs) and version.
@@ -600,6 +934,7 @@ fn gen_augment_clap_enum(
                     Unit => quote!( #app_var#from_attrs#version ),
                     Unnamed(FieldsUnnamed { ref unnamed, .. }) if unnamed.len() == 1 => {
                         let ty = &unnamed[0];
+                        left_out(&ty.ty, parent_attribute);
                         quote_spanned! { ty.span()=>
                             {
                                 let #app_var = <#ty as ::structopt::StructOptInternal>::augment_clap(
@@ -619,7 +954,36 @@ fn gen_augment_clap_enum(
                 };
 
                 let name = attrs.cased_name();
-                parent_attribute.manpage.push_subcommand(attrs.manpage);
+                if !attrs.is_hidden() && !attrs.is_manpage_skipped() {
+                    if own_page
+                        && parent_attribute.manpage.subcommand_pages
+                        && parent_attribute.manpage.path.is_some()
+                        && parent_attribute.manpage.format.is_roff()
+                        && !attrs.manpage.has_description()
+                    {
+                        abort!(
+                            variant,
+                            "the manpage of subcommand `{}` has no description for its NAME section",
+                            variant.ident;
+                            help = "add a doc comment or `#[structopt(about = \"...\")]`"
+                        );
+                    }
+                    parent_attribute.manpage.push_subcommand(attrs.manpage);
+                    if let Some(cmd) = parent_attribute.manpage.subcommands.last_mut() {
+                        cmd.own_page(own_page);
+                        for method in attrs.methods.iter() {
+                            if method.name == "visible_alias" || method.name == "visible_aliases" {
+                                for alias in string_literals(&method.args) {
+                                    cmd.push_visible_alias(alias);
+                                }
+                            } else if method.name == "alias" || method.name == "aliases" {
+                                for alias in string_literals(&method.args) {
+                                    cmd.push_alias(alias);
+                                }
+                            }
+                        }
+                    }
+                }
                 Some(quote! {
                     let app = app.subcommand({
                         let #app_var = ::structopt::clap::SubCommand::with_name(#name);
@@ -762,11 +1126,6 @@ fn gen_from_subcommand(
         None => quote!(None),
     };
 
-    for (_, attrs) in &variants {
-        parent_attribute
-            .manpage
-            .push_subcommand(attrs.manpage.clone());
-    }
     let match_arms = variants.iter_mut().map(|(variant, attrs)| {
         let sub_name = attrs.cased_name();
         let variant_name = &variant.ident;
@@ -790,11 +1149,6 @@ fn gen_from_subcommand(
         }
     });
 
-    for (_, attrs) in &flatten_variants {
-        parent_attribute
-            .manpage
-            .push_subcommand(attrs.manpage.clone());
-    }
     let child_subcommands = flatten_variants.iter().map(|(variant, _attrs)| {
         let variant_name = &variant.ident;
         match variant.fields {
@@ -854,7 +1208,7 @@ fn gen_paw_impl(_: &ImplGenerics, _: &Ident, _: &TypeGenerics, _: &TokenStream)
 
 fn split_structopt_generics_for_impl(
     generics: &Generics,
-) -> (ImplGenerics, TypeGenerics, TokenStream) {
+) -> (ImplGenerics<'_>, TypeGenerics<'_>, TokenStream) {
     use syn::{token::Add, TypeParamBound::Trait};
 
     fn path_ends_with(path: &Path, ident: &str) -> bool {
@@ -869,7 +1223,7 @@ fn split_structopt_generics_for_impl(
                 }
             }
         }
-        return false;
+        false
     }
 
     struct TraitBoundAmendments {
@@ -947,18 +1301,50 @@ fn split_structopt_generics_for_impl(
     (impl_generics, ty_generics, where_clause)
 }
 
+/// Write every output configured for `page`, aborting if one can't be written and warning about
+/// what looks wrong in the others.
+///
+/// A page whose NAME section is written describes the program as its package does, unless a doc
+/// comment or `about` does.
+fn write_manpage(name: &Ident, page: &mut crate::manpage::Manpage) {
+    if page.header_path.is_some() && !page.has_description() {
+        match std::env::var("CARGO_PKG_DESCRIPTION") {
+            Ok(description) if !description.trim().is_empty() => {
+                page.description(Some(description));
+            }
+            _ => abort!(
+                name,
+                "the manpage of `{}` has no description for its NAME section", name;
+                help = "add a doc comment or `#[structopt(about = \"...\")]`"
+            ),
+        }
+    }
+    match crate::manpage::output::write(page) {
+        Ok(findings) => {
+            for finding in findings {
+                warnings::emit(name.span(), finding);
+            }
+        }
+        Err(err) => abort_call_site!("{}", err),
+    }
+}
+
 fn impl_structopt_for_struct(
     name: &Ident,
     fields: &Punctuated<Field, Comma>,
     attrs: &[Attribute],
     generics: &Generics,
 ) -> TokenStream {
-    let (impl_generics, ty_generics, where_clause) = split_structopt_generics_for_impl(&generics);
+    let (impl_generics, ty_generics, where_clause) = split_structopt_generics_for_impl(generics);
 
     let mut basic_clap_app_gen = gen_clap_struct(attrs);
+    basic_clap_app_gen.attrs.set_provenance("struct", name);
+    basic_clap_app_gen.attrs.set_type_params(generics);
     let augment_clap = gen_augment_clap(fields, &mut basic_clap_app_gen.attrs);
     let from_clap = gen_from_clap(name, fields, &mut basic_clap_app_gen.attrs);
     let paw_impl = gen_paw_impl(&impl_generics, name, &ty_generics, &where_clause);
+    basic_clap_app_gen.attrs.push_builtin_flags();
+    write_manpage(name, &mut basic_clap_app_gen.attrs.manpage);
 
     let clap_tokens = basic_clap_app_gen.tokens;
     quote! {
@@ -1010,16 +1396,20 @@ fn impl_structopt_for_enum(
     attrs: &[Attribute],
     generics: &Generics,
 ) -> TokenStream {
-    let (impl_generics, ty_generics, where_clause) = split_structopt_generics_for_impl(&generics);
+    let (impl_generics, ty_generics, where_clause) = split_structopt_generics_for_impl(generics);
 
     let basic_clap_app_gen = gen_clap_enum(attrs);
     let clap_tokens = basic_clap_app_gen.tokens;
     let mut attrs = basic_clap_app_gen.attrs;
+    attrs.set_provenance("enum", name);
+    attrs.set_type_params(generics);
 
     let augment_clap = gen_augment_clap_enum(variants, &mut attrs);
     let from_clap = gen_from_clap_enum();
     let from_subcommand = gen_from_subcommand(name, variants, &mut attrs);
     let paw_impl = gen_paw_impl(&impl_generics, name, &ty_generics, &where_clause);
+    attrs.push_builtin_flags();
+    write_manpage(name, &mut attrs.manpage);
 
     quote! {
         #[allow(unknown_lints)]
@@ -1087,9 +1477,15 @@ fn impl_structopt(input: &DeriveInput) -> TokenStream {
             fields: syn::Fields::Named(ref fields),
             ..
         }) => impl_structopt_for_struct(struct_name, &fields.named, &input.attrs, &input.generics),
+        Struct(DataStruct {
+            fields: syn::Fields::Unnamed(ref fields),
+            ..
+        }) => {
+            impl_structopt_for_struct(struct_name, &fields.unnamed, &input.attrs, &input.generics)
+        }
         Enum(ref e) => {
             impl_structopt_for_enum(struct_name, &e.variants, &input.attrs, &input.generics)
         }
-        _ => abort_call_site!("structopt only supports non-tuple structs and enums"),
+        _ => abort_call_site!("structopt only supports structs with fields and enums"),
     }
 }
@@ -0,0 +1,600 @@
+// Copyright 2022 Manos Pitsidianakis <epilys@nessuent.net>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! DocBook XML `refentry` renderer.
+//!
+//! The header opens the `refentry` element and the footer closes it, so the three parts only
+//! form a well-formed document together.
+
+use super::roff::sentence_per_line;
+use super::{
+    bugs_text, doc_sentence, example_intro, flag_doc, std_exit_status, summary, verbatim_doc,
+    verbatim_text, Definition, Example, Flag, Keybinding, Manpage, Part, SeeAlso, Separator,
+    Settings, Subsection, TakesValue,
+};
+
+/// Escape `val` for XML character data and attribute values.
+///
+/// This is not affected by hardening: unescaped text would make the document malformed.
+fn escape(val: &str) -> String {
+    let mut ret = String::with_capacity(val.len());
+    for c in val.chars() {
+        match c {
+            '&' => ret.push_str("&amp;"),
+            '<' => ret.push_str("&lt;"),
+            '>' => ret.push_str("&gt;"),
+            '"' => ret.push_str("&quot;"),
+            c if c.is_control() && c != '\n' && c != '\t' => {}
+            c => ret.push(c),
+        }
+    }
+    ret
+}
+
+/// User text inlined into an element.
+fn inline(val: &str) -> String {
+    escape(&val.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// User text set as a literal block, as written.
+fn literal_block(val: &str) -> String {
+    format!(
+        "<literallayout>{}</literallayout>",
+        escape(&verbatim_text(val))
+    )
+}
+
+/// User text as one `para` element per paragraph.
+fn paras(page: &Manpage, val: &str) -> String {
+    let val = if page.stable {
+        sentence_per_line(val)
+    } else {
+        val.to_string()
+    };
+    val.split("\n\n")
+        .map(str::trim)
+        .filter(|para| !para.is_empty())
+        .map(|para| format!("<para>{}</para>", escape(para)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A leading comment saying where the file comes from, if `part` comes first in it.
+fn provenance(page: &Manpage, part: Part) -> String {
+    match page.provenance.as_ref().filter(|_| page.leads(part)) {
+        // `--` may not appear inside an XML comment.
+        Some(val) => format!(
+            "<!-- Generated by {} -->\n",
+            val.replace('\n', " ").replace("--", "- -")
+        ),
+        None => String::new(),
+    }
+}
+
+/// `val` turned into a valid XML `id`.
+fn id(val: &str) -> String {
+    let ret = val
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    if ret.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        ret
+    } else {
+        format!("_{}", ret)
+    }
+}
+
+/// `<replaceable>name</replaceable>`, for an option or subcommand that takes values.
+fn placeholder(page: &Manpage, args: &Option<TakesValue>, fallback: &str) -> String {
+    match args {
+        Some(value) => value.optional(format!(
+            "{}{}",
+            value
+                .placeholders(page, fallback)
+                .iter()
+                .map(|name| format!(" <replaceable>{}</replaceable>", inline(name)))
+                .collect::<String>(),
+            if value.multiple { "..." } else { "" }
+        )),
+        None => String::new(),
+    }
+}
+
+/// A flag's names and argument, joined by `separator`.
+fn flag_names(page: &Manpage, flag: &Flag, separator: &str) -> Option<String> {
+    let Flag {
+        long, short, args, ..
+    } = flag;
+    let option = |dashes: &str, name: &str| format!("<option>{}{}</option>", dashes, inline(name));
+    let mut names = match (long, short) {
+        (None, None) => return None,
+        (Some(l), Some(s)) => format!("{}{}{}", option("--", l), separator, option("-", s)),
+        (Some(l), None) => option("--", l),
+        (None, Some(s)) => option("-", s),
+    };
+    for other in flag.other_longs() {
+        names.push_str(&format!("{}{}", separator, option("--", &other)));
+    }
+    let fallback = long
+        .as_ref()
+        .or(short.as_ref())
+        .map(String::as_str)
+        .unwrap_or("ARGUMENT");
+    Some(format!(
+        "{}{}{}",
+        names,
+        placeholder(page, args, fallback),
+        if flag.trailing_ellipsis() { " ..." } else { "" }
+    ))
+}
+
+fn varlistentry(term: &str, doc: &str) -> String {
+    format!(
+        "<varlistentry>\n<term>{}</term>\n<listitem>\n{}\n</listitem>\n</varlistentry>\n",
+        term, doc
+    )
+}
+
+/// A `varlistentry` per flag, or nothing if none of them has a name.
+fn flag_list(page: &Manpage, flags: &[Flag]) -> String {
+    let mut ret = String::new();
+    for flag in page.options(flags) {
+        let names = match flag_names(page, flag, ", ") {
+            Some(names) => names,
+            None => continue,
+        };
+        let doc = flag_doc(flag).unwrap_or_default();
+        let doc = match verbatim_doc(flag) {
+            Some(verbatim) => format!("{}\n{}", literal_block(verbatim), paras(page, &doc)),
+            None => paras(page, &doc),
+        };
+        ret.push_str(&varlistentry(&names, doc.trim_end()));
+    }
+    if ret.is_empty() {
+        ret
+    } else {
+        format!("<variablelist>\n{}</variablelist>\n", ret)
+    }
+}
+
+pub fn header(page: &Manpage) -> String {
+    let name = page.name.as_str();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE refentry PUBLIC "-//OASIS//DTD DocBook XML V4.5//EN"
+  "http://www.oasis-open.org/docbook/xml/4.5/docbookx.dtd">
+{provenance}<refentry id="{id}">
+<refmeta>
+<refentrytitle>{uppercase_name}</refentrytitle>
+<manvolnum>{section}</manvolnum>
+</refmeta>
+<refnamediv>
+{names}
+<refpurpose>{description}</refpurpose>
+</refnamediv>"#,
+        provenance = provenance(page, Part::Header),
+        id = id(name),
+        uppercase_name = inline(&name.to_uppercase()),
+        section = inline(page.manual_section()),
+        names = std::iter::once(name)
+            .chain(page.invocations.iter().map(String::as_str))
+            .map(|name| format!("<refname>{}</refname>", inline(name)))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        description = inline(summary(&page.description)),
+    )
+}
+
+/// Each example's description, introducing its command line in a `screen`.
+fn examples(page: &Manpage) -> String {
+    if page.examples.is_empty() {
+        return String::new();
+    }
+    let mut ret = "<refsect1>\n<title>EXAMPLES</title>\n".to_string();
+    for Example { command, doc } in page.examples.iter() {
+        if let Some(doc) = doc {
+            ret.push_str(&paras(page, &example_intro(doc)));
+        }
+        ret.push_str(&format!(
+            "<screen>{}</screen>\n",
+            escape(&verbatim_text(command))
+        ));
+    }
+    ret.push_str("</refsect1>\n");
+    ret
+}
+
+fn definitions(page: &Manpage) -> String {
+    term_list(page, "DEFINITIONS", &page.definitions)
+}
+
+fn diagnostics(page: &Manpage) -> String {
+    term_list(page, "DIAGNOSTICS", &page.diagnostics)
+}
+
+/// The sections flags have entries in with `#[manpage(under = "...")]`, such as FILES.
+fn routed_sections(page: &Manpage) -> String {
+    page.routed_sections()
+        .iter()
+        .map(|(title, entries)| term_list(page, title, entries))
+        .collect()
+}
+
+/// A `refsect1` with a `varlistentry` per term.
+fn term_list(page: &Manpage, title: &str, entries: &[Definition]) -> String {
+    if entries.is_empty() {
+        return String::new();
+    }
+    format!(
+        "<refsect1>\n<title>{}</title>\n{}</refsect1>\n",
+        inline(title),
+        term_items(page, entries)
+    )
+}
+
+/// A `variablelist` with an entry per term.
+fn term_items(page: &Manpage, entries: &[Definition]) -> String {
+    let mut ret = "<variablelist>\n".to_string();
+    for Definition {
+        term,
+        doc,
+        overridden_by,
+    } in entries.iter()
+    {
+        let mut doc = paras(page, doc);
+        if let Some(flag) = overridden_by {
+            let note = format!(
+                "<para>Overridden by <option>{}</option>.</para>",
+                inline(&flag.display_name())
+            );
+            doc = format!("{}\n{}", doc, note).trim_start().to_string();
+        }
+        ret.push_str(&varlistentry(&inline(term), &doc));
+    }
+    ret.push_str("</variablelist>\n");
+    ret
+}
+
+/// The EXIT STATUS section: the usual exit statuses if the program has them, then the declared
+/// codes.
+fn exit_status(page: &Manpage) -> String {
+    if !page.std_exit_status && page.exit_statuses.is_empty() {
+        return String::new();
+    }
+    let mut ret = "<refsect1>\n<title>EXIT STATUS</title>\n".to_string();
+    if page.std_exit_status {
+        ret.push_str(&paras(page, &std_exit_status(page)));
+    }
+    if !page.exit_statuses.is_empty() {
+        ret.push_str(&term_items(page, &page.exit_codes()));
+    }
+    ret.push_str("</refsect1>\n");
+    ret
+}
+
+fn keybindings(page: &Manpage) -> String {
+    if page.keybindings.is_empty() {
+        return String::new();
+    }
+    let mut ret = "<refsect1>\n<title>KEY BINDINGS</title>\n<variablelist>\n".to_string();
+    for Keybinding { key, action, doc } in page.keybindings.iter() {
+        let mut term = format!("<keycap>{}</keycap>", inline(key));
+        if let Some(action) = action {
+            term.push_str(&format!(" (<command>{}</command>)", inline(action)));
+        }
+        let doc = paras(page, doc.as_deref().unwrap_or_default());
+        // A listitem may not be empty.
+        let doc = if doc.is_empty() {
+            "<para/>".to_string()
+        } else {
+            doc
+        };
+        ret.push_str(&varlistentry(&term, &doc));
+    }
+    ret.push_str("</variablelist>\n</refsect1>\n");
+    ret
+}
+
+/// The paragraph explaining which setting wins when it is given more than once.
+fn settings(page: &Manpage) -> String {
+    let Settings {
+        environment,
+        config_file,
+    } = match page.settings.as_ref() {
+        Some(settings) => settings,
+        None => return String::new(),
+    };
+    let mut ret = "<para>Settings are taken from the following sources, \
+                   in order of decreasing precedence:</para>\n<orderedlist spacing=\"compact\">\n\
+                   <listitem><para>command line options</para></listitem>\n"
+        .to_string();
+    if *environment {
+        ret.push_str("<listitem><para>environment variables</para></listitem>\n");
+    }
+    if let Some(path) = config_file {
+        ret.push_str(&format!(
+            "<listitem><para>the configuration file <filename>{}</filename></para></listitem>\n",
+            inline(path)
+        ));
+    }
+    ret.push_str("<listitem><para>built-in defaults</para></listitem>\n</orderedlist>\n");
+    ret
+}
+
+pub fn footer(page: &Manpage) -> String {
+    format!(
+        "{provenance}{definitions}{sections}{exit_status}{keybindings}{examples}{diagnostics}{see_also}{standards}{history}{authors}{caveats}{bugs}{copyright}</refentry>",
+        provenance = provenance(page, Part::Footer),
+        definitions = definitions(page),
+        sections = routed_sections(page),
+        exit_status = exit_status(page),
+        keybindings = keybindings(page),
+        examples = examples(page),
+        diagnostics = diagnostics(page),
+        see_also = see_also(page),
+        standards = standards(page),
+        history = history(page),
+        authors = authors(page),
+        caveats = caveats(page),
+        bugs = bugs(page),
+        copyright = copyright(page),
+    )
+}
+
+/// A `citerefentry` per related page.
+fn see_also(page: &Manpage) -> String {
+    if page.see_also.is_empty() {
+        return String::new();
+    }
+    let refs = page
+        .see_also
+        .iter()
+        .map(|SeeAlso { name, section }| {
+            format!(
+                "<citerefentry><refentrytitle>{}</refentrytitle><manvolnum>{}</manvolnum></citerefentry>",
+                inline(name),
+                inline(section)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",\n");
+    format!(
+        "<refsect1>\n<title>SEE ALSO</title>\n<para>\n{}\n</para>\n</refsect1>\n",
+        refs
+    )
+}
+
+fn standards(page: &Manpage) -> String {
+    match &page.standards {
+        Some(doc) => format!(
+            "<refsect1>\n<title>STANDARDS</title>\n{}\n</refsect1>\n",
+            paras(page, doc)
+        ),
+        None => String::new(),
+    }
+}
+
+fn history(page: &Manpage) -> String {
+    match &page.history {
+        Some(doc) => format!(
+            "<refsect1>\n<title>HISTORY</title>\n{}\n</refsect1>\n",
+            paras(page, doc)
+        ),
+        None => String::new(),
+    }
+}
+
+fn authors(page: &Manpage) -> String {
+    match page.authors() {
+        Some(authors) => format!(
+            "<refsect1>\n<title>AUTHORS</title>\n{}\n</refsect1>\n",
+            paras(page, authors)
+        ),
+        None => String::new(),
+    }
+}
+
+fn caveats(page: &Manpage) -> String {
+    match &page.caveats {
+        Some(doc) => format!(
+            "<refsect1>\n<title>CAVEATS</title>\n{}\n</refsect1>\n",
+            paras(page, doc)
+        ),
+        None => String::new(),
+    }
+}
+
+fn bugs(page: &Manpage) -> String {
+    match &page.bugs {
+        Some(bugs) => format!(
+            "<refsect1>\n<title>BUGS</title>\n{}\n</refsect1>\n",
+            paras(page, &bugs_text(bugs))
+        ),
+        None => String::new(),
+    }
+}
+
+fn copyright(page: &Manpage) -> String {
+    match &page.copyright {
+        Some(doc) => format!(
+            "<refsect1>\n<title>COPYRIGHT</title>\n{}\n</refsect1>\n",
+            paras(page, doc)
+        ),
+        None => String::new(),
+    }
+}
+
+/// The `choice` attribute of a synopsis `arg`.
+fn choice(flag: &Flag) -> &'static str {
+    if flag.required {
+        "req"
+    } else {
+        "opt"
+    }
+}
+
+pub fn body(page: &Manpage) -> String {
+    let mut synopsis = String::new();
+    for flag in page.options(&page.flags) {
+        if let Some(names) = flag_names(page, flag, " | ") {
+            synopsis.push_str(&format!(
+                "<arg choice=\"{}\">{}</arg>\n",
+                choice(flag),
+                names
+            ));
+        }
+    }
+    let mut operands = String::new();
+    for (name, flag) in page.operands() {
+        let operand = placeholder(page, &flag.args, name);
+        let separator = "<option>--</option>";
+        synopsis.push_str(&match page.separator(flag) {
+            Some(Separator::Required) => format!(
+                "<arg choice=\"{}\">{} {}</arg>\n",
+                choice(flag),
+                separator,
+                operand.trim_start()
+            ),
+            Some(Separator::Optional) => format!(
+                "<arg choice=\"opt\">{}</arg>\n<arg choice=\"{}\">{}</arg>\n",
+                separator,
+                choice(flag),
+                operand.trim_start()
+            ),
+            None => format!(
+                "<arg choice=\"{}\">{}</arg>\n",
+                choice(flag),
+                operand.trim_start()
+            ),
+        });
+        let doc = flag.doc.as_deref().unwrap_or_default();
+        operands.push_str(&varlistentry(
+            operand.trim_start(),
+            &paras(page, &doc_sentence(doc)),
+        ));
+    }
+    let synopsis = std::iter::once(page.name.as_str())
+        .chain(page.invocations.iter().map(String::as_str))
+        .map(|name| {
+            format!(
+                "<cmdsynopsis>\n<command>{}</command>\n{}</cmdsynopsis>\n",
+                inline(name),
+                synopsis
+            )
+        })
+        .collect::<String>();
+    let mut subcommands = String::new();
+    for cmd in page.subcommands.iter() {
+        let mut doc = paras(page, &doc_sentence(cmd.doc.as_deref().unwrap_or_default()));
+        doc.push('\n');
+        doc.push_str(&flag_list(page, &cmd.flags));
+        subcommands.push_str(&varlistentry(
+            &format!(
+                "{}{}",
+                cmd.names()
+                    .into_iter()
+                    .map(|name| format!("<command>{}</command>", inline(name)))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                placeholder(page, &cmd.args, "ARGUMENT")
+            ),
+            doc.trim_end(),
+        ));
+    }
+    let mut options = flag_list(page, &page.flags);
+    if !operands.is_empty() {
+        options.push_str(&format!("<variablelist>\n{}</variablelist>\n", operands));
+    }
+    if !subcommands.is_empty() {
+        options.push_str(&format!("<variablelist>\n{}</variablelist>\n", subcommands));
+    }
+    options.push_str(&settings(page));
+    let mut parts = vec![];
+    if !page.flags.is_empty() || !page.invocations.is_empty() {
+        parts.push(format!("<refsynopsisdiv>\n{}</refsynopsisdiv>\n", synopsis));
+    }
+    let description = page.description_text();
+    if description.is_some() || !page.subsections.is_empty() {
+        let prose = match description {
+            Some(doc) if page.verbatim => format!("{}\n", literal_block(doc)),
+            Some(doc) => format!("{}\n", paras(page, doc)),
+            None => String::new(),
+        };
+        let subsections = page
+            .subsections
+            .iter()
+            .map(|Subsection { title, doc }| {
+                format!(
+                    "<refsect2>\n<title>{}</title>\n{}\n</refsect2>\n",
+                    inline(title),
+                    paras(page, doc)
+                )
+            })
+            .collect::<String>();
+        parts.push(format!(
+            "<refsect1>\n<title>DESCRIPTION</title>\n{}{}</refsect1>\n",
+            prose, subsections
+        ));
+    }
+    if !options.is_empty() {
+        parts.push(format!(
+            "<refsect1>\n<title>OPTIONS</title>\n{}</refsect1>\n",
+            options
+        ));
+    }
+    format!("{}{}", provenance(page, Part::Body), parts.concat())
+}
+
+/// A compact `variablelist` in its own section, or nothing if `rows` is empty.
+fn compact_list(title: &str, rows: Vec<(String, String)>) -> String {
+    if rows.is_empty() {
+        return String::new();
+    }
+    let mut ret = format!(
+        "<refsect1>\n<title>{}</title>\n<variablelist spacing=\"compact\">\n",
+        title
+    );
+    for (item, doc) in rows {
+        ret.push_str(&varlistentry(&item, &format!("<para>{}</para>", doc)));
+    }
+    ret.push_str("</variablelist>\n</refsect1>\n");
+    ret
+}
+
+pub fn cheatsheet(page: &Manpage) -> String {
+    let (flags, subcommands) = page.cheatsheet_items();
+    let options = flags
+        .into_iter()
+        .filter_map(|flag| Some((flag_names(page, flag, ", ")?, inline(summary(&flag.doc)))))
+        .collect();
+    let commands = subcommands
+        .into_iter()
+        .map(|cmd| {
+            (
+                format!(
+                    "<command>{}</command>{}",
+                    inline(&cmd.name),
+                    placeholder(page, &cmd.args, "ARGUMENT")
+                ),
+                inline(summary(&cmd.doc)),
+            )
+        })
+        .collect();
+    format!(
+        "{}\n{}{}</refentry>\n",
+        header(page),
+        compact_list("OPTIONS", options),
+        compact_list("COMMANDS", commands)
+    )
+}
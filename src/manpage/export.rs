@@ -0,0 +1,390 @@
+// Copyright 2022 Manos Pitsidianakis <epilys@nessuent.net>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Structured exports of the model, for tools that want the CLI surface rather than a page.
+
+use super::{
+    json_string, ArgGroup, Definition, Example, ExitStatus, Flag, Keybinding, Manpage,
+    PossibleValue, SeeAlso, Settings, Subcommand, Subsection, TakesValue, ValueKind,
+};
+
+/// A tree of plain data every export format is written from.
+enum Value {
+    Null,
+    Bool(bool),
+    Number(u64),
+    Str(String),
+    List(Vec<Value>),
+    Map(Vec<(&'static str, Value)>),
+}
+
+impl From<&str> for Value {
+    fn from(val: &str) -> Self {
+        Value::Str(val.to_string())
+    }
+}
+
+impl From<&String> for Value {
+    fn from(val: &String) -> Self {
+        Value::Str(val.clone())
+    }
+}
+
+impl From<bool> for Value {
+    fn from(val: bool) -> Self {
+        Value::Bool(val)
+    }
+}
+
+impl From<u64> for Value {
+    fn from(val: u64) -> Self {
+        Value::Number(val)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(val: Option<T>) -> Self {
+        val.map_or(Value::Null, Into::into)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(val: Vec<T>) -> Self {
+        Value::List(val.into_iter().map(Into::into).collect())
+    }
+}
+
+impl From<&TakesValue> for Value {
+    fn from(val: &TakesValue) -> Self {
+        Value::Map(vec![
+            ("kind", val.kind.map(ValueKind::name).into()),
+            ("multiple", val.multiple.into()),
+            ("names", val.names.iter().collect::<Vec<_>>().into()),
+            ("number_of_values", val.number_of_values.into()),
+            ("optional_value", val.optional_value.into()),
+        ])
+    }
+}
+
+impl From<&Flag> for Value {
+    fn from(val: &Flag) -> Self {
+        Value::Map(vec![
+            ("long", val.long.as_ref().into()),
+            ("short", val.short.as_ref().into()),
+            ("args", val.args.as_ref().into()),
+            ("doc", val.doc.as_ref().into()),
+            ("featured", val.featured.into()),
+            ("required", val.required.into()),
+            ("positional", val.positional.as_ref().into()),
+            ("id", val.id.as_ref().into()),
+            (
+                "conflicts_with",
+                val.conflicts_with.iter().collect::<Vec<_>>().into(),
+            ),
+            ("requires", val.requires.iter().collect::<Vec<_>>().into()),
+            (
+                "required_unless",
+                val.required_unless.iter().collect::<Vec<_>>().into(),
+            ),
+            ("required_unless_all", val.required_unless_all.into()),
+            (
+                "required_if",
+                val.required_if
+                    .iter()
+                    .map(|(id, value)| Value::Map(vec![("id", id.into()), ("value", value.into())]))
+                    .collect::<Vec<_>>()
+                    .into(),
+            ),
+            ("group", val.group.as_ref().into()),
+            ("env", val.env.as_ref().into()),
+            ("default_value", val.default_value.as_ref().into()),
+            (
+                "possible_values",
+                val.possible_values.iter().collect::<Vec<_>>().into(),
+            ),
+            ("negatable", val.negatable.into()),
+            ("global", val.global.into()),
+            (
+                "visible_aliases",
+                val.visible_aliases.iter().collect::<Vec<_>>().into(),
+            ),
+            ("aliases", val.aliases.iter().collect::<Vec<_>>().into()),
+            ("repeatable", val.repeatable.into()),
+            ("last", val.last.into()),
+            ("section", val.section.as_ref().into()),
+            ("heading", val.heading.as_ref().into()),
+        ])
+    }
+}
+
+impl From<&Subcommand> for Value {
+    fn from(val: &Subcommand) -> Self {
+        Value::Map(vec![
+            ("name", (&val.name).into()),
+            ("args", val.args.as_ref().into()),
+            ("flags", val.flags.iter().collect::<Vec<_>>().into()),
+            ("doc", val.doc.as_ref().into()),
+            ("featured", val.featured.into()),
+            (
+                "visible_aliases",
+                val.visible_aliases.iter().collect::<Vec<_>>().into(),
+            ),
+            ("aliases", val.aliases.iter().collect::<Vec<_>>().into()),
+            ("own_page", val.own_page.into()),
+        ])
+    }
+}
+
+impl From<&ArgGroup> for Value {
+    fn from(val: &ArgGroup) -> Self {
+        Value::Map(vec![
+            ("name", (&val.name).into()),
+            ("args", val.args.iter().collect::<Vec<_>>().into()),
+            ("required", val.required.into()),
+            ("multiple", val.multiple.into()),
+        ])
+    }
+}
+
+impl From<&Keybinding> for Value {
+    fn from(val: &Keybinding) -> Self {
+        Value::Map(vec![
+            ("key", (&val.key).into()),
+            ("action", val.action.as_ref().into()),
+            ("doc", val.doc.as_ref().into()),
+        ])
+    }
+}
+
+impl From<&Example> for Value {
+    fn from(val: &Example) -> Self {
+        Value::Map(vec![
+            ("command", (&val.command).into()),
+            ("doc", val.doc.as_ref().into()),
+        ])
+    }
+}
+
+impl From<&ExitStatus> for Value {
+    fn from(val: &ExitStatus) -> Self {
+        Value::Map(vec![
+            ("code", (&val.code).into()),
+            ("doc", (&val.doc).into()),
+        ])
+    }
+}
+
+impl From<&PossibleValue> for Value {
+    fn from(val: &PossibleValue) -> Self {
+        Value::Map(vec![
+            ("name", (&val.name).into()),
+            ("doc", val.doc.as_ref().into()),
+        ])
+    }
+}
+
+impl From<&Definition> for Value {
+    fn from(val: &Definition) -> Self {
+        Value::Map(vec![
+            ("term", (&val.term).into()),
+            ("doc", (&val.doc).into()),
+            (
+                "overridden_by",
+                val.overridden_by
+                    .as_ref()
+                    .map(|flag| Value::from(flag.display_name().as_str()))
+                    .into(),
+            ),
+        ])
+    }
+}
+
+impl From<&Subsection> for Value {
+    fn from(val: &Subsection) -> Self {
+        Value::Map(vec![
+            ("title", (&val.title).into()),
+            ("doc", (&val.doc).into()),
+        ])
+    }
+}
+
+impl From<&SeeAlso> for Value {
+    fn from(val: &SeeAlso) -> Self {
+        Value::Map(vec![
+            ("name", (&val.name).into()),
+            ("section", (&val.section).into()),
+        ])
+    }
+}
+
+impl From<&Settings> for Value {
+    fn from(val: &Settings) -> Self {
+        Value::Map(vec![
+            ("environment", val.environment.into()),
+            ("config_file", val.config_file.as_ref().into()),
+        ])
+    }
+}
+
+impl From<&Manpage> for Value {
+    fn from(val: &Manpage) -> Self {
+        Value::Map(vec![
+            ("name", (&val.name).into()),
+            ("description", val.description.as_ref().into()),
+            ("long_description", val.long_description.as_ref().into()),
+            ("author", val.author.as_ref().into()),
+            ("version", val.version.as_ref().into()),
+            (
+                "invocations",
+                val.invocations.iter().collect::<Vec<_>>().into(),
+            ),
+            ("flags", val.flags.iter().collect::<Vec<_>>().into()),
+            ("groups", val.groups.iter().collect::<Vec<_>>().into()),
+            (
+                "subcommands",
+                val.subcommands.iter().collect::<Vec<_>>().into(),
+            ),
+            (
+                "keybindings",
+                val.keybindings.iter().collect::<Vec<_>>().into(),
+            ),
+            ("examples", val.examples.iter().collect::<Vec<_>>().into()),
+            (
+                "exit_statuses",
+                val.exit_statuses.iter().collect::<Vec<_>>().into(),
+            ),
+            ("std_exit_status", val.std_exit_status.into()),
+            (
+                "diagnostics",
+                val.diagnostics.iter().collect::<Vec<_>>().into(),
+            ),
+            ("env_vars", val.env_vars.iter().collect::<Vec<_>>().into()),
+            ("files", val.files.iter().collect::<Vec<_>>().into()),
+            ("standards", val.standards.as_ref().into()),
+            ("history", val.history.as_ref().into()),
+            ("bugs", val.bugs.as_ref().into()),
+            ("caveats", val.caveats.as_ref().into()),
+            ("copyright", val.copyright.as_ref().into()),
+            (
+                "definitions",
+                val.definitions.iter().collect::<Vec<_>>().into(),
+            ),
+            (
+                "subsections",
+                val.subsections.iter().collect::<Vec<_>>().into(),
+            ),
+            ("see_also", val.see_also.iter().collect::<Vec<_>>().into()),
+            ("settings", val.settings.as_ref().into()),
+            ("trailing_var_arg", val.trailing_var_arg.into()),
+        ])
+    }
+}
+
+#[cfg(feature = "json")]
+fn write_json(out: &mut String, val: &Value, depth: usize) {
+    let indent = |depth: usize| "  ".repeat(depth);
+    match val {
+        Value::Null => out.push_str("null"),
+        Value::Bool(val) => out.push_str(if *val { "true" } else { "false" }),
+        Value::Number(val) => out.push_str(&val.to_string()),
+        Value::Str(val) => out.push_str(&json_string(val)),
+        Value::List(items) if items.is_empty() => out.push_str("[]"),
+        Value::List(items) => {
+            out.push_str("[\n");
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(",\n");
+                }
+                out.push_str(&indent(depth + 1));
+                write_json(out, item, depth + 1);
+            }
+            out.push('\n');
+            out.push_str(&indent(depth));
+            out.push(']');
+        }
+        Value::Map(entries) => {
+            out.push_str("{\n");
+            for (i, (key, item)) in entries.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(",\n");
+                }
+                out.push_str(&format!("{}{}: ", indent(depth + 1), json_string(key)));
+                write_json(out, item, depth + 1);
+            }
+            out.push('\n');
+            out.push_str(&indent(depth));
+            out.push('}');
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+pub fn json(page: &Manpage) -> String {
+    let mut ret = String::new();
+    write_json(&mut ret, &page.into(), 0);
+    ret.push('\n');
+    ret
+}
+
+#[cfg(feature = "yaml")]
+/// Values written on the line of their key or list dash.
+fn yaml_scalar(val: &Value) -> Option<String> {
+    match val {
+        Value::Null => Some("null".to_string()),
+        Value::Bool(val) => Some(val.to_string()),
+        Value::Number(val) => Some(val.to_string()),
+        // A JSON string is also a valid YAML double-quoted scalar.
+        Value::Str(val) => Some(json_string(val)),
+        Value::List(items) if items.is_empty() => Some("[]".to_string()),
+        Value::Map(entries) if entries.is_empty() => Some("{}".to_string()),
+        Value::List(_) | Value::Map(_) => None,
+    }
+}
+
+#[cfg(feature = "yaml")]
+fn write_yaml(out: &mut String, val: &Value, depth: usize) {
+    let indent = "  ".repeat(depth);
+    match val {
+        Value::List(items) => {
+            for item in items {
+                match (yaml_scalar(item), item) {
+                    (Some(scalar), _) => out.push_str(&format!("{}- {}\n", indent, scalar)),
+                    (None, Value::Map(_)) => {
+                        // The first entry of a map shares the line of its dash.
+                        let mut map = String::new();
+                        write_yaml(&mut map, item, depth + 1);
+                        out.push_str(&format!("{}- {}", indent, &map[indent.len() + 2..]));
+                    }
+                    (None, _) => {
+                        out.push_str(&format!("{}-\n", indent));
+                        write_yaml(out, item, depth + 1);
+                    }
+                }
+            }
+        }
+        Value::Map(entries) => {
+            for (key, item) in entries {
+                match yaml_scalar(item) {
+                    Some(scalar) => out.push_str(&format!("{}{}: {}\n", indent, key, scalar)),
+                    None => {
+                        out.push_str(&format!("{}{}:\n", indent, key));
+                        write_yaml(out, item, depth + 1);
+                    }
+                }
+            }
+        }
+        scalar => out.push_str(&format!("{}{}\n", indent, yaml_scalar(scalar).unwrap())),
+    }
+}
+
+#[cfg(feature = "yaml")]
+pub fn yaml(page: &Manpage) -> String {
+    let mut ret = String::new();
+    write_yaml(&mut ret, &page.into(), 0);
+    ret
+}
@@ -0,0 +1,90 @@
+// Copyright 2022 Manos Pitsidianakis <epilys@nessuent.net>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Checks for common mistakes in generated mdoc, so broken pages are caught without mandoc.
+
+use super::roff;
+
+/// The mdoc macros that only take effect at the start of a line.
+fn is_line_macro(word: &str) -> bool {
+    matches!(
+        word,
+        "Bd" | "Bk"
+            | "Bl"
+            | "Dd"
+            | "Dt"
+            | "Ed"
+            | "Ek"
+            | "El"
+            | "It"
+            | "Nd"
+            | "Os"
+            | "Pp"
+            | "Sh"
+            | "Ss"
+    )
+}
+
+/// Lint a rendered mdoc page, returning one `line N: message` warning per problem found.
+///
+/// This checks that `.Bl`, `.Bd` and `.Bk` blocks are closed in order, that `.It` only appears in a
+/// list, that `.Nd` is not empty, and that no macro ended up in a text line, where it would be
+/// printed instead of taking effect.
+pub fn mdoc(output: &str) -> Vec<String> {
+    let mut ret = vec![];
+    let mut blocks: Vec<(&str, usize)> = vec![];
+    for (idx, line) in output.lines().enumerate() {
+        let lineno = idx + 1;
+        let mut warn = |msg: String| ret.push(format!("line {}: {}", lineno, msg));
+        if line.starts_with(".\\\"") {
+            continue;
+        }
+        let rest = match line.strip_prefix('.') {
+            Some(rest) => rest,
+            None => {
+                // Words escaped with `\&` are meant to be printed, as `text` escapes them.
+                if let Some(word) = line.split_whitespace().find(|word| {
+                    word.strip_prefix('.').map_or(false, |name| {
+                        roff::is_callable_macro(name) || is_line_macro(name)
+                    })
+                }) {
+                    warn(format!("stray macro {:?} in text", word));
+                }
+                continue;
+            }
+        };
+        let mut words = rest.split_whitespace();
+        match words.next().unwrap_or_default() {
+            name @ ("Bl" | "Bd" | "Bk") => blocks.push((name, lineno)),
+            name @ ("El" | "Ed" | "Ek") => {
+                let opener = match name {
+                    "El" => "Bl",
+                    "Ed" => "Bd",
+                    _ => "Bk",
+                };
+                match blocks.pop() {
+                    Some((open, _)) if open == opener => {}
+                    Some((open, open_lineno)) => warn(format!(
+                        ".{} closes .{} opened on line {}",
+                        name, open, open_lineno
+                    )),
+                    None => warn(format!(".{} without .{}", name, opener)),
+                }
+            }
+            "It" if !blocks.iter().any(|(open, _)| *open == "Bl") => {
+                warn(".It outside of a .Bl list".to_string())
+            }
+            "Nd" if words.next().is_none() => warn("empty .Nd".to_string()),
+            _ => {}
+        }
+    }
+    for (open, lineno) in blocks {
+        ret.push(format!("line {}: unterminated .{}", lineno, open));
+    }
+    ret
+}
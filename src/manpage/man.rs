@@ -0,0 +1,650 @@
+// Copyright 2022 Manos Pitsidianakis <epilys@nessuent.net>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! man(7) renderer.
+
+use super::roff::{self, provenance, text};
+use super::{
+    bracketed, bugs_text, doc_sentence, example_intro, flag_doc, paragraphs, separated,
+    std_exit_status, summary, verbatim_doc, Definition, Example, Flag, Keybinding, Manpage, Part,
+    SeeAlso, Settings, Subcommand, Subsection, TakesValue, EXTERNAL_SUBCOMMANDS,
+};
+
+/// User text folded onto one line and escaped, leaving its hyphens alone.
+fn escape(page: &Manpage, val: &str) -> String {
+    let val = val.split_whitespace().collect::<Vec<_>>().join(" ");
+    if page.hardened {
+        roff::harden_text(&val)
+    } else {
+        roff::escape_text(&val)
+    }
+}
+
+/// User text inlined into a line the renderer builds, such as a `.TP` tag.
+fn inline(page: &Manpage, val: &str) -> String {
+    roff::glyphs(page, roff::hyphens(page, escape(page, val)))
+}
+
+/// User text as a quoted macro argument.
+fn quoted(page: &Manpage, val: &str) -> String {
+    format!("\"{}\"", inline(page, val).replace('"', "\\(dq"))
+}
+
+fn bold(val: &str) -> String {
+    format!("\\fB{}\\fR", val)
+}
+
+/// An option name, with its dashes spelled as minus signs.
+fn option(page: &Manpage, dashes: &str, name: &str) -> String {
+    let name = format!("{}{}", dashes, escape(page, name)).replace('-', "\\-");
+    bold(&roff::glyphs(page, name))
+}
+
+/// ` \fIname\fR` or ` \fIname\fR ...`, for an option or subcommand that takes values.
+fn placeholder(page: &Manpage, args: &Option<TakesValue>, fallback: &str) -> String {
+    match args {
+        Some(value) => value.optional(format!(
+            "{}{}",
+            value
+                .placeholders(page, fallback)
+                .iter()
+                .map(|name| format!(" \\fI{}\\fR", inline(page, name)))
+                .collect::<String>(),
+            if value.multiple { " ..." } else { "" }
+        )),
+        None => String::new(),
+    }
+}
+
+/// A synopsis item with its spaces unpaddable, so that no option is split from its argument
+/// when the synopsis wraps.
+fn unbreakable(val: &str) -> String {
+    val.replace(' ', "\\ ")
+}
+
+/// A subcommand's name and visible aliases, in bold and separated by commas.
+fn command_names(page: &Manpage, cmd: &Subcommand) -> String {
+    cmd.names()
+        .into_iter()
+        .map(|name| bold(&inline(page, name)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// User text set as a literal block, as written.
+fn literal_block(page: &Manpage, val: &str) -> String {
+    format!(".nf\n{}\n.fi\n", roff::literal(page, val))
+}
+
+/// The sentence in a subcommand's entry referring to the global flags, which it takes too.
+fn globals_note(page: &Manpage) -> String {
+    let globals = page.globals();
+    if globals.is_empty() {
+        return String::new();
+    }
+    format!(
+        "Also takes the global option{}\n{}.\n",
+        if globals.len() == 1 { "" } else { "s" },
+        globals
+            .into_iter()
+            .filter_map(|flag| match (&flag.long, &flag.short) {
+                (Some(long), _) => Some(option(page, "--", long)),
+                (None, Some(short)) => Some(option(page, "-", short)),
+                (None, None) => None,
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+/// A flag's names and argument, for example `\fB\-\-output\fR | \fB\-o\fR \fIoutput\fR`.
+fn flag_names(page: &Manpage, flag: &Flag) -> Option<String> {
+    let Flag {
+        long, short, args, ..
+    } = flag;
+    let mut names = match (long, short) {
+        (None, None) => return None,
+        (Some(l), Some(s)) => format!("{} | {}", option(page, "--", l), option(page, "-", s)),
+        (Some(l), None) => option(page, "--", l),
+        (None, Some(s)) => option(page, "-", s),
+    };
+    for other in flag.other_longs() {
+        names.push_str(&format!(" | {}", option(page, "--", &other)));
+    }
+    let fallback = long
+        .as_ref()
+        .or(short.as_ref())
+        .map(String::as_str)
+        .unwrap_or("ARGUMENT");
+    Some(format!(
+        "{}{}{}",
+        names,
+        placeholder(page, args, fallback),
+        if flag.trailing_ellipsis() { " ..." } else { "" }
+    ))
+}
+
+pub fn header(page: &Manpage) -> String {
+    format!(
+        "{provenance}.TH {uppercase_name} {section}{date}\n.SH NAME\n{names} \\- {description}",
+        provenance = provenance(page, Part::Header),
+        // `.TH` takes the date and then the source, so a source needs a date before it.
+        date = match (page.date.as_deref(), page.source().as_deref()) {
+            (Some(date), Some(os)) => format!(" {} {}", quoted(page, date), quoted(page, os)),
+            (None, Some(os)) => format!(" \"\" {}", quoted(page, os)),
+            (Some(date), None) => format!(" {}", quoted(page, date)),
+            (None, None) => String::new(),
+        },
+        uppercase_name = quoted(page, &page.name.to_uppercase()),
+        section = inline(page, page.manual_section()),
+        names = std::iter::once(page.name.as_str())
+            .chain(page.invocations.iter().map(String::as_str))
+            .map(|name| inline(page, name))
+            .collect::<Vec<_>>()
+            .join(", "),
+        description = inline(page, page.name_description()),
+    )
+    // An empty description would leave the line with trailing whitespace.
+    .trim_end()
+    .to_string()
+}
+
+/// Each example's description, introducing its command line in an indented block.
+fn examples(page: &Manpage) -> String {
+    if page.examples.is_empty() {
+        return String::new();
+    }
+    let entries = page
+        .examples
+        .iter()
+        .map(|Example { command, doc }| {
+            let mut ret = String::new();
+            if let Some(doc) = doc {
+                ret.push_str(&format!("{}\n.PP\n", text(page, &example_intro(doc))));
+            }
+            ret.push_str(&format!(".RS 4\n{}.RE\n", literal_block(page, command)));
+            ret
+        })
+        .collect::<Vec<_>>();
+    format!(".SH EXAMPLES\n{}", entries.join(".PP\n"))
+}
+
+fn definitions(page: &Manpage) -> String {
+    term_list(page, "DEFINITIONS", &page.definitions)
+}
+
+fn diagnostics(page: &Manpage) -> String {
+    term_list(page, "DIAGNOSTICS", &page.diagnostics)
+}
+
+/// The sections flags have entries in with `#[manpage(under = "...")]`, such as FILES.
+fn routed_sections(page: &Manpage) -> String {
+    page.routed_sections()
+        .iter()
+        .map(|(title, entries)| term_list(page, title, entries))
+        .collect()
+}
+
+/// A section of tagged paragraphs, one per term.
+fn term_list(page: &Manpage, title: &str, entries: &[Definition]) -> String {
+    if entries.is_empty() {
+        return String::new();
+    }
+    format!(".SH {}\n{}", inline(page, title), term_items(page, entries))
+}
+
+/// A tagged paragraph per term.
+fn term_items(page: &Manpage, entries: &[Definition]) -> String {
+    let mut ret = String::new();
+    for Definition {
+        term,
+        doc,
+        overridden_by,
+    } in entries.iter()
+    {
+        ret.push_str(&format!(".TP\n{}\n", bold(&inline(page, term))));
+        if !doc.is_empty() {
+            ret.push_str(&format!("{}\n", text(page, doc)));
+        }
+        if let Some(flag) = overridden_by {
+            ret.push_str(&format!(
+                "Overridden by {}.\n",
+                option(page, "", &flag.display_name())
+            ));
+        }
+    }
+    ret
+}
+
+/// The EXIT STATUS section: the usual exit statuses if the program has them, then the declared
+/// codes.
+fn exit_status(page: &Manpage) -> String {
+    if !page.std_exit_status && page.exit_statuses.is_empty() {
+        return String::new();
+    }
+    let mut ret = ".SH \"EXIT STATUS\"\n".to_string();
+    if page.std_exit_status {
+        ret.push_str(&format!("{}\n", text(page, &std_exit_status(page))));
+    }
+    ret.push_str(&term_items(page, &page.exit_codes()));
+    ret
+}
+
+/// The COMMANDS section of a page whose subcommands have their own pages, referring to each that
+/// has one.
+fn commands(page: &Manpage) -> String {
+    if !page.subcommand_pages || (page.subcommands.is_empty() && !page.external_subcommands) {
+        return String::new();
+    }
+    let mut ret = ".SH COMMANDS\n".to_string();
+    for cmd in page.subcommands.iter() {
+        ret.push_str(&format!(
+            ".TP\n{}{}\n",
+            command_names(page, cmd),
+            placeholder(page, &cmd.args, "ARGUMENT")
+        ));
+        let doc = summary(&cmd.doc);
+        if !doc.is_empty() {
+            ret.push_str(&format!("{}\n", text(page, &doc_sentence(doc))));
+        }
+        ret.push_str(&globals_note(page));
+        if cmd.own_page {
+            ret.push_str(&format!(
+                "See\n.BR {} ({}).\n",
+                inline(page, &page.subcommand_page(cmd)),
+                inline(page, page.manual_section())
+            ));
+        }
+    }
+    if page.external_subcommands {
+        if !page.subcommands.is_empty() {
+            ret.push_str(".PP\n");
+        }
+        ret.push_str(&format!("{}\n", EXTERNAL_SUBCOMMANDS));
+    }
+    ret
+}
+
+/// An `.SS` subsection per declared subsection, with `.PP` between its paragraphs.
+fn subsections(page: &Manpage) -> String {
+    let mut ret = String::new();
+    for Subsection { title, doc } in page.subsections.iter() {
+        let paras = paragraphs(doc)
+            .map(|para| text(page, para))
+            .collect::<Vec<_>>();
+        ret.push_str(&format!(
+            ".SS {}\n{}\n",
+            quoted(page, title),
+            paras.join("\n.PP\n")
+        ));
+    }
+    ret
+}
+
+fn keybindings(page: &Manpage) -> String {
+    if page.keybindings.is_empty() {
+        return String::new();
+    }
+    let mut ret = ".SH \"KEY BINDINGS\"\n".to_string();
+    for Keybinding { key, action, doc } in page.keybindings.iter() {
+        ret.push_str(&format!(".TP\n{}", bold(&inline(page, key))));
+        if let Some(action) = action {
+            ret.push_str(&format!(" ({})", bold(&inline(page, action))));
+        }
+        ret.push('\n');
+        if let Some(doc) = doc {
+            ret.push_str(&text(page, doc));
+            ret.push('\n');
+        }
+    }
+    ret
+}
+
+/// The paragraph explaining which setting wins when it is given more than once.
+fn settings(page: &Manpage) -> String {
+    let Settings {
+        environment,
+        config_file,
+    } = match page.settings.as_ref() {
+        Some(settings) => settings,
+        None => return String::new(),
+    };
+    let mut sources = vec!["command line options".to_string()];
+    if *environment {
+        sources.push("environment variables".to_string());
+    }
+    if let Some(path) = config_file {
+        sources.push(format!(
+            "the configuration file\n\\fI{}\\fR",
+            inline(page, path)
+        ));
+    }
+    sources.push("built-in defaults".to_string());
+    let mut ret = ".PP\nSettings are taken from the following sources, \
+                   in order of decreasing precedence:\n"
+        .to_string();
+    for (i, source) in sources.iter().enumerate() {
+        ret.push_str(&format!(".IP {}. 4\n{}\n", i + 1, source));
+    }
+    ret
+}
+
+pub fn footer(page: &Manpage) -> String {
+    format!(
+        "{provenance}{commands}{definitions}{sections}{exit_status}{keybindings}{examples}{diagnostics}{see_also}{standards}{history}{authors}{caveats}{bugs}{copyright}",
+        provenance = provenance(page, Part::Footer),
+        commands = commands(page),
+        definitions = definitions(page),
+        sections = routed_sections(page),
+        exit_status = exit_status(page),
+        keybindings = keybindings(page),
+        examples = examples(page),
+        diagnostics = diagnostics(page),
+        see_also = see_also(page),
+        standards = standards(page),
+        history = history(page),
+        authors = authors(page),
+        caveats = caveats(page),
+        bugs = bugs(page),
+        copyright = copyright(page),
+    )
+    .trim_end()
+    .to_string()
+}
+
+/// One `.BR name (section)` line per related page.
+fn see_also(page: &Manpage) -> String {
+    if page.see_also.is_empty() {
+        return String::new();
+    }
+    let mut ret = ".SH \"SEE ALSO\"\n".to_string();
+    for (idx, SeeAlso { name, section }) in page.see_also.iter().enumerate() {
+        ret.push_str(&format!(
+            ".BR {} ({}){}\n",
+            inline(page, name),
+            inline(page, section),
+            if idx + 1 < page.see_also.len() {
+                ","
+            } else {
+                ""
+            }
+        ));
+    }
+    ret
+}
+
+/// The STANDARDS section, with `.PP` between its paragraphs.
+fn standards(page: &Manpage) -> String {
+    let doc = match &page.standards {
+        Some(doc) => doc,
+        None => return String::new(),
+    };
+    let paras = paragraphs(doc)
+        .map(|para| text(page, para))
+        .collect::<Vec<_>>();
+    format!(".SH STANDARDS\n{}\n", paras.join("\n.PP\n"))
+}
+
+/// The HISTORY section, with `.PP` between its paragraphs.
+fn history(page: &Manpage) -> String {
+    let doc = match &page.history {
+        Some(doc) => doc,
+        None => return String::new(),
+    };
+    let paras = paragraphs(doc)
+        .map(|para| text(page, para))
+        .collect::<Vec<_>>();
+    format!(".SH HISTORY\n{}\n", paras.join("\n.PP\n"))
+}
+
+fn authors(page: &Manpage) -> String {
+    match page.authors() {
+        Some(authors) => format!(".SH AUTHORS\n{}\n", text(page, authors)),
+        None => String::new(),
+    }
+}
+
+/// The CAVEATS section, with `.PP` between its paragraphs.
+fn caveats(page: &Manpage) -> String {
+    let doc = match &page.caveats {
+        Some(doc) => doc,
+        None => return String::new(),
+    };
+    let paras = paragraphs(doc)
+        .map(|para| text(page, para))
+        .collect::<Vec<_>>();
+    format!(".SH CAVEATS\n{}\n", paras.join("\n.PP\n"))
+}
+
+fn bugs(page: &Manpage) -> String {
+    match &page.bugs {
+        Some(bugs) => format!(".SH BUGS\n{}\n", text(page, &bugs_text(bugs))),
+        None => String::new(),
+    }
+}
+
+/// The COPYRIGHT section, with `.PP` between its paragraphs.
+fn copyright(page: &Manpage) -> String {
+    let doc = match &page.copyright {
+        Some(doc) => doc,
+        None => return String::new(),
+    };
+    let paras = paragraphs(doc)
+        .map(|para| text(page, para))
+        .collect::<Vec<_>>();
+    format!(".SH COPYRIGHT\n{}\n", paras.join("\n.PP\n"))
+}
+
+/// A tagged paragraph per named flag in `flags`.
+fn option_table(page: &Manpage, flags: &[&Flag]) -> String {
+    let mut ret = String::new();
+    for flag in flags {
+        let names = match flag_names(page, flag) {
+            Some(names) => names,
+            None => continue,
+        };
+        ret.push_str(&format!(".TP\n{}\n", names));
+        if let Some(doc) = verbatim_doc(flag) {
+            ret.push_str(&literal_block(page, doc));
+        }
+        if let Some(doc) = flag_doc(flag) {
+            ret.push_str(&format!("{}\n", text(page, &doc)));
+        }
+    }
+    ret
+}
+
+pub fn body(page: &Manpage) -> String {
+    let mut synopsis = String::new();
+    for flag in page.options(&page.flags) {
+        if let Some(names) = flag_names(page, flag) {
+            synopsis.push_str(&format!("{}\n", unbreakable(&bracketed(flag, &names))));
+        }
+    }
+    let (flags, headed) = page.headed_options(&page.flags);
+    let flag_table = option_table(page, &flags);
+    // Options with a `help_heading` come in a subsection per heading.
+    let headed = headed
+        .iter()
+        .map(|(heading, flags)| (heading, option_table(page, flags)))
+        .filter(|(_, table)| !table.is_empty())
+        .map(|(heading, table)| format!(".SS {}\n{}", quoted(page, heading), table))
+        .collect::<String>();
+    let mut subcommands = String::new();
+    for cmd in page.subcommands.iter() {
+        subcommands.push_str(&format!(
+            ".TP\n{}{}\n",
+            command_names(page, cmd),
+            placeholder(page, &cmd.args, "ARGUMENT")
+        ));
+        if let Some(doc) = &cmd.doc {
+            subcommands.push_str(&format!("{}\n", text(page, &doc_sentence(doc))));
+        }
+        subcommands.push_str(&globals_note(page));
+        let mut flags = String::new();
+        for flag in page.options(&cmd.flags) {
+            let names = match flag_names(page, flag) {
+                Some(names) => names,
+                None => continue,
+            };
+            flags.push_str(&format!(".TP\n{}\n", names));
+            if let Some(doc) = verbatim_doc(flag) {
+                flags.push_str(&literal_block(page, doc));
+            }
+            if let Some(doc) = flag_doc(flag) {
+                flags.push_str(&format!("{}\n", text(page, &doc)));
+            }
+        }
+        if !flags.is_empty() {
+            subcommands.push_str(&format!(".RS\n{}.RE\n", flags));
+        }
+    }
+    let mut operand_table = String::new();
+    for (name, flag) in page.operands() {
+        let operand = placeholder(page, &flag.args, name);
+        synopsis.push_str(&format!(
+            "{}\n",
+            unbreakable(&separated(
+                page,
+                flag,
+                &option(page, "--", ""),
+                operand.trim_start()
+            ))
+        ));
+        operand_table.push_str(&format!(".TP\n{}\n", operand.trim_start()));
+        if let Some(doc) = flag.doc.as_ref() {
+            operand_table.push_str(&format!("{}\n", text(page, &doc_sentence(doc))));
+        }
+    }
+    // The page of a subcommand shows the program followed by the subcommand.
+    let name = match page.command() {
+        Some((parent, cmd)) => format!(
+            "{} {}",
+            bold(&inline(page, parent)),
+            bold(&inline(page, cmd))
+        ),
+        None => bold(&inline(page, &page.name)),
+    };
+    let synopsis = std::iter::once(name)
+        .chain(
+            page.invocations
+                .iter()
+                .map(|name| bold(&inline(page, name))),
+        )
+        .map(|name| format!("{}\n{}", name, synopsis))
+        .collect::<Vec<_>>()
+        .join(".br\n");
+    let mut parts = vec![];
+    if !page.flags.is_empty() || !page.invocations.is_empty() || page.parent.is_some() {
+        parts.push(synopsis.trim());
+    }
+    let description = match page.description_text() {
+        Some(doc) if page.verbatim => literal_block(page, doc),
+        Some(doc) => {
+            let paras = paragraphs(doc)
+                .map(|para| text(page, para))
+                .collect::<Vec<_>>()
+                .join("\n.PP\n");
+            // A paragraph break only separates the description from the synopsis before it, on
+            // pages where no heading does.
+            if parts.is_empty() || page.parent.is_some() {
+                paras
+            } else {
+                format!(".PP\n{}", paras)
+            }
+        }
+        None => String::new(),
+    };
+    if !description.is_empty() {
+        parts.push(description.trim_end());
+    }
+    if !flag_table.is_empty() {
+        parts.push(flag_table.trim());
+    }
+    if !operand_table.is_empty() {
+        parts.push(operand_table.trim());
+    }
+    if !page.subcommands.is_empty() && !page.subcommand_pages {
+        parts.push(subcommands.trim());
+    }
+    let external = format!(".PP\n{}", EXTERNAL_SUBCOMMANDS);
+    if page.external_subcommands && !page.subcommand_pages {
+        parts.push(if parts.is_empty() {
+            EXTERNAL_SUBCOMMANDS
+        } else {
+            &external
+        });
+    }
+    let settings = settings(page);
+    if !settings.is_empty() {
+        parts.push(settings.trim());
+    }
+    if !headed.is_empty() {
+        parts.push(headed.trim());
+    }
+    let subsections = subsections(page);
+    if !subsections.is_empty() {
+        parts.push(subsections.trim());
+    }
+    if parts.is_empty() {
+        return provenance(page, Part::Body);
+    }
+    // The page of a subcommand is written whole, so its body has headings of its own.
+    if page.parent.is_some() {
+        let mut ret = format!(
+            "{}.SH SYNOPSIS\n{}\n",
+            provenance(page, Part::Body),
+            parts[0]
+        );
+        if parts.len() > 1 {
+            ret.push_str(&format!(".SH DESCRIPTION\n{}\n", parts[1..].join("\n")));
+        }
+        return ret;
+    }
+    format!("{}{}\n", provenance(page, Part::Body), parts.join("\n"))
+}
+
+/// A compact tagged list, or nothing if `rows` is empty.
+fn compact_list(title: &str, rows: Vec<(String, String)>) -> String {
+    if rows.is_empty() {
+        return String::new();
+    }
+    let mut ret = format!(".SH {}\n.PD 0\n", title);
+    for (item, doc) in rows {
+        ret.push_str(&format!(".TP\n{}\n{}\n", item, doc));
+    }
+    ret.push_str(".PD\n");
+    ret
+}
+
+pub fn cheatsheet(page: &Manpage) -> String {
+    let (flags, subcommands) = page.cheatsheet_items();
+    let options = flags
+        .into_iter()
+        .filter_map(|flag| Some((flag_names(page, flag)?, inline(page, summary(&flag.doc)))))
+        .collect();
+    let commands = subcommands
+        .into_iter()
+        .map(|cmd| {
+            (
+                format!(
+                    "{}{}",
+                    bold(&inline(page, &cmd.name)),
+                    placeholder(page, &cmd.args, "ARGUMENT")
+                ),
+                inline(page, summary(&cmd.doc)),
+            )
+        })
+        .collect();
+    format!(
+        "{}\n{}{}",
+        header(page),
+        compact_list("OPTIONS", options),
+        compact_list("COMMANDS", commands)
+    )
+}
@@ -0,0 +1,619 @@
+// Copyright 2022 Manos Pitsidianakis <epilys@nessuent.net>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! CommonMark renderer, producing a chapter ready for an mdBook.
+//!
+//! Every option and subcommand gets an explicit anchor, so other chapters can link to
+//! `#option-verbose` or `#command-build` without depending on how headings are slugified.
+
+use super::roff::sentence_per_line;
+use super::{
+    bracketed, bugs_text, doc_sentence, example_intro, flag_doc, json_string, sentence_case,
+    separated, std_exit_status, summary, verbatim_doc, verbatim_text, Definition, Example, Flag,
+    FrontMatter, FrontMatterStyle, Keybinding, Manpage, Part, Settings, Subsection, TakesValue,
+};
+
+/// Escape the characters that start inline markup or block constructs.
+fn escape(val: &str) -> String {
+    let mut ret = String::with_capacity(val.len());
+    for c in val.chars().filter(|c| !c.is_control() || *c == '\n') {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '!'
+        ) {
+            ret.push('\\');
+        }
+        ret.push(c);
+    }
+    ret
+}
+
+/// User text emitted as one or more paragraph lines.
+fn text(page: &Manpage, val: &str) -> String {
+    let val = if page.stable {
+        sentence_per_line(val)
+    } else {
+        val.trim().to_string()
+    };
+    if page.hardened {
+        escape(&val)
+    } else {
+        val
+    }
+}
+
+/// User text inlined into a line the renderer builds.
+fn inline(page: &Manpage, val: &str) -> String {
+    let val = val.split_whitespace().collect::<Vec<_>>().join(" ");
+    if page.hardened {
+        escape(&val)
+    } else {
+        val
+    }
+}
+
+/// User text set as a fenced code block, as written.
+fn literal_block(val: &str) -> String {
+    // Fenced code is literal, so only the fence itself has to be kept out.
+    format!("```text\n{}\n```", verbatim_text(val).replace("```", ""))
+}
+
+/// Text set as a code span, which cannot be escaped.
+fn code(val: &str) -> String {
+    format!(
+        "`{}`",
+        val.split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .replace('`', "")
+    )
+}
+
+/// Text for a table cell, where a pipe would end the cell whatever the hardening.
+fn cell(val: String) -> String {
+    val.replace('|', "\\|")
+}
+
+/// `val` reduced to the characters allowed in an anchor.
+fn slug(val: &str) -> String {
+    val.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>()
+        .trim_matches('-')
+        .to_string()
+}
+
+fn anchor(id: &str) -> String {
+    format!("<a id=\"{}\"></a>\n", id)
+}
+
+/// A leading comment saying where the file comes from, if `part` comes first in it.
+fn provenance(page: &Manpage, part: Part) -> String {
+    match page.provenance.as_ref().filter(|_| page.leads(part)) {
+        // `--` may not appear inside an HTML comment.
+        Some(val) => format!(
+            "<!-- Generated by {} -->\n\n",
+            val.replace('\n', " ").replace("--", "- -")
+        ),
+        None => String::new(),
+    }
+}
+
+/// Whether `val` is an ISO 8601 date or date-time, which YAML and TOML both read unquoted.
+fn is_date(val: &str) -> bool {
+    let bytes = val.as_bytes();
+    bytes.len() >= 10
+        && bytes[..10].iter().enumerate().all(|(i, b)| {
+            if i == 4 || i == 7 {
+                *b == b'-'
+            } else {
+                b.is_ascii_digit()
+            }
+        })
+        && bytes[10..]
+            .iter()
+            .all(|b| b.is_ascii_digit() || b":.+-TZ".contains(b))
+}
+
+/// The front matter block static site generators read the page metadata from.
+fn front_matter(page: &Manpage) -> String {
+    let FrontMatter {
+        style,
+        title,
+        date,
+        section,
+    } = match page.front_matter.as_ref() {
+        Some(front_matter) => front_matter,
+        None => return String::new(),
+    };
+    let (fence, assign) = match style {
+        FrontMatterStyle::Yaml => ("---", ":"),
+        FrontMatterStyle::Toml => ("+++", " ="),
+    };
+    let title = title.as_deref().unwrap_or(page.name.as_str());
+    let mut ret = format!("{}\ntitle{} {}\n", fence, assign, json_string(title));
+    if let Some(date) = date {
+        let date = if is_date(date) {
+            date.to_string()
+        } else {
+            json_string(date)
+        };
+        ret.push_str(&format!("date{} {}\n", assign, date));
+    }
+    if let Some(section) = section {
+        ret.push_str(&format!("section{} {}\n", assign, json_string(section)));
+    }
+    ret.push_str(fence);
+    ret.push_str("\n\n");
+    ret
+}
+
+/// ` <name>` or ` <name>...`, for an option or subcommand that takes values.
+fn placeholder(page: &Manpage, args: &Option<TakesValue>, fallback: &str) -> String {
+    match args {
+        Some(value) => value.optional(format!(
+            "{}{}",
+            value
+                .placeholders(page, fallback)
+                .iter()
+                .map(|name| format!(" <{}>", name))
+                .collect::<String>(),
+            if value.multiple { "..." } else { "" }
+        )),
+        None => String::new(),
+    }
+}
+
+/// Every spelling of a flag with its argument, for example `--output <output>`.
+fn spellings(page: &Manpage, flag: &Flag) -> Vec<String> {
+    let Flag {
+        long, short, args, ..
+    } = flag;
+    let fallback = long
+        .as_ref()
+        .or(short.as_ref())
+        .map(String::as_str)
+        .unwrap_or("ARGUMENT");
+    let placeholder = format!(
+        "{}{}",
+        placeholder(page, args, fallback),
+        if flag.trailing_ellipsis() { " ..." } else { "" }
+    );
+    let mut names = match (long, short) {
+        (None, None) => vec![],
+        (Some(l), Some(s)) => vec![format!("--{}", l), format!("-{}", s)],
+        (Some(l), None) => vec![format!("--{}", l)],
+        (None, Some(s)) => vec![format!("-{}", s)],
+    };
+    names.extend(
+        flag.other_longs()
+            .iter()
+            .map(|other| format!("--{}", other)),
+    );
+    names
+        .iter()
+        .map(|name| format!("{}{}", name, placeholder))
+        .collect()
+}
+
+/// The anchor id of a flag, `option-long`, under `prefix` for subcommand flags.
+fn flag_id(prefix: &str, flag: &Flag) -> Option<String> {
+    let name = flag.long.as_ref().or(flag.short.as_ref())?;
+    Some(format!("{}option-{}", prefix, slug(name)))
+}
+
+/// A heading and paragraph documenting `flag`.
+fn flag_section(page: &Manpage, level: &str, prefix: &str, flag: &Flag) -> Option<String> {
+    let spellings = spellings(page, flag);
+    if spellings.is_empty() {
+        return None;
+    }
+    let mut ret = format!(
+        "{}{} {}\n",
+        anchor(&flag_id(prefix, flag)?),
+        level,
+        spellings
+            .iter()
+            .map(|spelling| code(spelling))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    if let Some(doc) = verbatim_doc(flag) {
+        ret.push_str(&format!("\n{}\n", literal_block(doc)));
+    }
+    if let Some(doc) = flag_doc(flag) {
+        ret.push_str(&format!("\n{}\n", text(page, &doc)));
+    }
+    Some(ret)
+}
+
+pub fn header(page: &Manpage) -> String {
+    let mut ret = format!(
+        "{}{}# {}\n\n{}\n",
+        front_matter(page),
+        provenance(page, Part::Header),
+        inline(page, &page.name),
+        inline(
+            page,
+            &doc_sentence(page.description.as_deref().unwrap_or_default())
+        ),
+    );
+    if !page.invocations.is_empty() {
+        ret.push_str(&format!(
+            "\nAlso invoked as {}.\n",
+            page.invocations
+                .iter()
+                .map(|name| code(name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    ret
+}
+
+/// Each example's description, introducing its command line in a code block.
+fn examples(page: &Manpage) -> String {
+    if page.examples.is_empty() {
+        return String::new();
+    }
+    let mut ret = "## Examples\n\n".to_string();
+    for Example { command, doc } in page.examples.iter() {
+        if let Some(doc) = doc {
+            ret.push_str(&format!("{}\n\n", text(page, &example_intro(doc))));
+        }
+        ret.push_str(&format!("{}\n\n", literal_block(command)));
+    }
+    ret
+}
+
+fn definitions(page: &Manpage) -> String {
+    term_list(page, "Definitions", &page.definitions)
+}
+
+fn diagnostics(page: &Manpage) -> String {
+    term_list(page, "Diagnostics", &page.diagnostics)
+}
+
+/// The sections flags have entries in with `#[manpage(under = "...")]`, such as FILES.
+fn routed_sections(page: &Manpage) -> String {
+    page.routed_sections()
+        .iter()
+        .map(|(title, entries)| term_list(page, &sentence_case(title), entries))
+        .collect()
+}
+
+/// A section with a list item per term.
+fn term_list(page: &Manpage, title: &str, entries: &[Definition]) -> String {
+    if entries.is_empty() {
+        return String::new();
+    }
+    format!(
+        "## {}\n\n{}",
+        inline(page, title),
+        term_items(page, entries)
+    )
+}
+
+/// A list item per term.
+fn term_items(page: &Manpage, entries: &[Definition]) -> String {
+    let mut ret = String::new();
+    for Definition {
+        term,
+        doc,
+        overridden_by,
+    } in entries.iter()
+    {
+        let mut doc = text(page, doc);
+        if let Some(flag) = overridden_by {
+            let note = format!("Overridden by {}.", code(&flag.display_name()));
+            doc = format!("{}\n{}", doc, note).trim_start().to_string();
+        }
+        // Continuation lines are indented to stay inside the list item.
+        let doc = doc.replace('\n', "\n  ").replace("\n  \n", "\n\n");
+        ret.push_str(&format!("- **{}**: {}\n", inline(page, term), doc));
+    }
+    ret.push('\n');
+    ret
+}
+
+/// The exit status section: the usual exit statuses if the program has them, then the declared
+/// codes.
+fn exit_status(page: &Manpage) -> String {
+    if !page.std_exit_status && page.exit_statuses.is_empty() {
+        return String::new();
+    }
+    let mut ret = "## Exit status\n\n".to_string();
+    if page.std_exit_status {
+        ret.push_str(&format!("{}\n\n", text(page, &std_exit_status(page))));
+    }
+    if !page.exit_statuses.is_empty() {
+        ret.push_str(&term_items(page, &page.exit_codes()));
+    }
+    ret
+}
+
+fn keybindings(page: &Manpage) -> String {
+    if page.keybindings.is_empty() {
+        return String::new();
+    }
+    let mut ret =
+        "## Key bindings\n\n| Key | Action | Description |\n| --- | --- | --- |\n".to_string();
+    for Keybinding { key, action, doc } in page.keybindings.iter() {
+        ret.push_str(&format!(
+            "| {} | {} | {} |\n",
+            cell(code(key)),
+            cell(action.as_deref().map(code).unwrap_or_default()),
+            cell(inline(page, doc.as_deref().unwrap_or_default())),
+        ));
+    }
+    ret.push('\n');
+    ret
+}
+
+/// The paragraph explaining which setting wins when it is given more than once.
+fn settings(page: &Manpage) -> String {
+    let Settings {
+        environment,
+        config_file,
+    } = match page.settings.as_ref() {
+        Some(settings) => settings,
+        None => return String::new(),
+    };
+    let mut sources = vec!["command line options".to_string()];
+    if *environment {
+        sources.push("environment variables".to_string());
+    }
+    if let Some(path) = config_file {
+        sources.push(format!("the configuration file {}", code(path)));
+    }
+    sources.push("built-in defaults".to_string());
+    let mut ret = "Settings are taken from the following sources, \
+                   in order of decreasing precedence:\n\n"
+        .to_string();
+    for (i, source) in sources.iter().enumerate() {
+        ret.push_str(&format!("{}. {}\n", i + 1, source));
+    }
+    ret
+}
+
+pub fn footer(page: &Manpage) -> String {
+    format!(
+        "{provenance}{definitions}{sections}{exit_status}{keybindings}{examples}{diagnostics}{see_also}{standards}{history}{closing}",
+        provenance = provenance(page, Part::Footer),
+        definitions = definitions(page),
+        sections = routed_sections(page),
+        exit_status = exit_status(page),
+        keybindings = keybindings(page),
+        examples = examples(page),
+        diagnostics = diagnostics(page),
+        see_also = see_also(page),
+        standards = standards(page),
+        history = history(page),
+        closing = closing(page),
+    )
+}
+
+fn see_also(page: &Manpage) -> String {
+    if page.see_also.is_empty() {
+        return String::new();
+    }
+    format!("## See also\n\n{}\n\n", text(page, &page.see_also_text()))
+}
+
+fn standards(page: &Manpage) -> String {
+    match &page.standards {
+        Some(doc) => format!("## Standards\n\n{}\n\n", text(page, doc)),
+        None => String::new(),
+    }
+}
+
+fn history(page: &Manpage) -> String {
+    match &page.history {
+        Some(doc) => format!("## History\n\n{}\n\n", text(page, doc)),
+        None => String::new(),
+    }
+}
+
+fn authors(page: &Manpage) -> String {
+    match page.authors() {
+        Some(authors) => format!("## Authors\n\n{}", text(page, authors)),
+        None => String::new(),
+    }
+}
+
+fn caveats(page: &Manpage) -> String {
+    match &page.caveats {
+        Some(doc) => format!("## Caveats\n\n{}", text(page, doc)),
+        None => String::new(),
+    }
+}
+
+fn bugs(page: &Manpage) -> String {
+    match &page.bugs {
+        Some(bugs) => format!("## Bugs\n\n{}", text(page, &bugs_text(bugs))),
+        None => String::new(),
+    }
+}
+
+fn copyright(page: &Manpage) -> String {
+    match &page.copyright {
+        Some(doc) => format!("## Copyright\n\n{}", text(page, doc)),
+        None => String::new(),
+    }
+}
+
+/// The authors, caveats, bugs and copyright sections, which end the page without a blank line.
+fn closing(page: &Manpage) -> String {
+    vec![authors(page), caveats(page), bugs(page), copyright(page)]
+        .into_iter()
+        .filter(|section| !section.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+pub fn body(page: &Manpage) -> String {
+    let mut synopsis = String::new();
+    let mut options = vec![];
+    for flag in page.options(&page.flags) {
+        if let Some(section) = flag_section(page, "###", "", flag) {
+            synopsis.push_str(&format!(
+                " {}",
+                bracketed(flag, &spellings(page, flag).join(" | "))
+            ));
+            options.push(section);
+        }
+    }
+    let mut operands = vec![];
+    for (name, flag) in page.operands() {
+        let operand = placeholder(page, &flag.args, name);
+        synopsis.push_str(&format!(
+            " {}",
+            separated(page, flag, "--", operand.trim_start())
+        ));
+        let mut section = format!(
+            "{}### {}\n",
+            anchor(&format!("argument-{}", slug(name))),
+            code(operand.trim_start())
+        );
+        if let Some(doc) = flag.doc.as_ref() {
+            section.push_str(&format!("\n{}\n", text(page, &doc_sentence(doc))));
+        }
+        operands.push(section);
+    }
+    let mut subcommands = vec![];
+    for cmd in page.subcommands.iter() {
+        let id = format!("command-{}", slug(&cmd.name));
+        let mut section = format!(
+            "{}### {}\n",
+            anchor(&id),
+            cmd.names()
+                .into_iter()
+                .map(|name| code(&format!(
+                    "{}{}",
+                    name,
+                    placeholder(page, &cmd.args, "ARGUMENT")
+                )))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        if let Some(doc) = &cmd.doc {
+            section.push_str(&format!("\n{}\n", text(page, &doc_sentence(doc))));
+        }
+        let prefix = format!("{}-", id);
+        for flag in page.options(&cmd.flags) {
+            if let Some(option) = flag_section(page, "####", &prefix, flag) {
+                section.push('\n');
+                section.push_str(&option);
+            }
+        }
+        subcommands.push(section);
+    }
+    let mut parts = vec![];
+    if !page.flags.is_empty() || !page.invocations.is_empty() {
+        let synopsis = std::iter::once(page.name.as_str())
+            .chain(page.invocations.iter().map(String::as_str))
+            // Fenced code is literal, so only the fence itself has to be kept out.
+            .map(|name| format!("{}{}\n", name, synopsis).replace("```", ""))
+            .collect::<String>();
+        parts.push(format!("## Synopsis\n\n```text\n{}```\n", synopsis));
+    }
+    let description = page.description_text();
+    if description.is_some() || !page.subsections.is_empty() {
+        parts.push("## Description\n".to_string());
+    }
+    match description {
+        Some(doc) if page.verbatim => parts.push(format!("{}\n", literal_block(doc))),
+        Some(doc) => parts.push(format!("{}\n", text(page, doc))),
+        None => {}
+    }
+    for Subsection { title, doc } in page.subsections.iter() {
+        parts.push(format!(
+            "### {}\n\n{}\n",
+            inline(page, title),
+            text(page, doc)
+        ));
+    }
+    let settings = settings(page);
+    if !options.is_empty() || !settings.is_empty() {
+        parts.push("## Options\n".to_string());
+        parts.extend(options);
+        if !settings.is_empty() {
+            parts.push(settings);
+        }
+    }
+    if !operands.is_empty() {
+        parts.push("## Arguments\n".to_string());
+        parts.extend(operands);
+    }
+    if !subcommands.is_empty() {
+        parts.push("## Commands\n".to_string());
+        parts.extend(subcommands);
+    }
+    if parts.is_empty() {
+        return provenance(page, Part::Body);
+    }
+    format!("{}{}\n", provenance(page, Part::Body), parts.join("\n"))
+}
+
+/// A two-column table under a heading, or nothing if `rows` is empty.
+fn table(title: &str, column: &str, rows: Vec<(String, String)>) -> String {
+    if rows.is_empty() {
+        return String::new();
+    }
+    let mut ret = format!(
+        "\n## {}\n\n| {} | Description |\n| --- | --- |\n",
+        title, column
+    );
+    for (item, doc) in rows {
+        ret.push_str(&format!("| {} | {} |\n", cell(item), cell(doc)));
+    }
+    ret
+}
+
+pub fn cheatsheet(page: &Manpage) -> String {
+    let (flags, subcommands) = page.cheatsheet_items();
+    let options = flags
+        .into_iter()
+        .filter(|flag| !spellings(page, flag).is_empty())
+        .map(|flag| {
+            (
+                spellings(page, flag)
+                    .iter()
+                    .map(|spelling| code(spelling))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                inline(page, summary(&flag.doc)),
+            )
+        })
+        .collect();
+    let commands = subcommands
+        .into_iter()
+        .map(|cmd| {
+            (
+                code(&format!(
+                    "{}{}",
+                    cmd.name,
+                    placeholder(page, &cmd.args, "ARGUMENT")
+                )),
+                inline(page, summary(&cmd.doc)),
+            )
+        })
+        .collect();
+    format!(
+        "{}{}{}",
+        header(page),
+        table("Options", "Option", options),
+        table("Commands", "Command", commands)
+    )
+}
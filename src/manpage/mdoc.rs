@@ -0,0 +1,1056 @@
+// Copyright 2022 Manos Pitsidianakis <epilys@nessuent.net>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! mdoc(7) renderer.
+
+use super::roff::{self, provenance, text};
+use super::{
+    doc_sentence, example_intro, flag_doc, paragraphs, split_authors, verbatim_doc, Definition,
+    Example, Flag, Keybinding, ListStyle, Manpage, Part, PossibleValue, SeeAlso, Separator,
+    Settings, Subcommand, Subsection, TakesValue, ValueKind, EXTERNAL_SUBCOMMANDS,
+};
+
+/// User text emitted as macro arguments.
+fn arg(page: &Manpage, val: &str) -> String {
+    let val = if page.hardened {
+        roff::harden_arg(val)
+    } else {
+        roff::escape_arg(val)
+    };
+    roff::glyphs(page, roff::hyphens(page, val))
+}
+
+pub fn header(page: &Manpage) -> String {
+    format!(
+        r#"{provenance}.Dd {date}
+.Dt {uppercase_name} {section}
+.Os{os}
+.Sh NAME
+.Nm {name}{invocations}
+.Nd {description}"#,
+        provenance = provenance(page, Part::Header),
+        date = match page.date.as_deref() {
+            // The date is the rest of the line, so it must not be wrapped or split into sentences.
+            Some(date) => arg(page, date),
+            None => "$Mdocdate$".to_string(),
+        },
+        uppercase_name = arg(page, &page.name.to_uppercase()),
+        section = arg(page, page.manual_section()),
+        os = match page.source() {
+            Some(os) => format!(" {}", arg(page, &os)),
+            None => String::new(),
+        },
+        name = arg(page, page.name.as_str()),
+        invocations = page
+            .invocations
+            .iter()
+            .map(|name| format!(" ,\n.Nm {}", arg(page, name)))
+            .collect::<String>(),
+        description = arg(page, page.name_description()),
+    )
+    // An empty description would leave the line with trailing whitespace.
+    .trim_end()
+    .to_string()
+}
+
+fn keybindings(page: &Manpage) -> String {
+    if page.keybindings.is_empty() {
+        return String::new();
+    }
+    let mut ret = ".Sh KEY BINDINGS\n.Bl -tag -width Ds\n".to_string();
+    for Keybinding { key, action, doc } in page.keybindings.iter() {
+        let key = arg(page, key);
+        if roff::is_delimiter(&key) {
+            ret.push_str(&format!(".It Cm \\&{}", key));
+        } else {
+            ret.push_str(&format!(".It Cm {}", key));
+        }
+        if let Some(action) = action {
+            ret.push_str(&format!(" Pq Ic {}", arg(page, action)));
+        }
+        ret.push('\n');
+        if let Some(doc) = doc {
+            ret.push_str(&text(page, doc));
+            ret.push('\n');
+        }
+    }
+    ret.push_str(".El\n");
+    ret
+}
+
+/// Each example's description, introducing its command line in a display.
+fn examples(page: &Manpage) -> String {
+    if page.examples.is_empty() {
+        return String::new();
+    }
+    let entries = page
+        .examples
+        .iter()
+        .map(|Example { command, doc }| {
+            let mut ret = String::new();
+            if let Some(doc) = doc {
+                ret.push_str(&format!("{}\n.Pp\n", text(page, &example_intro(doc))));
+            }
+            if command.contains('\n') {
+                ret.push_str(&format!(
+                    ".Bd -literal -offset indent\n{}\n.Ed\n",
+                    roff::literal(page, command)
+                ));
+            } else {
+                ret.push_str(&format!(".Dl {}\n", arg(page, command)));
+            }
+            ret
+        })
+        .collect::<Vec<_>>();
+    format!(".Sh EXAMPLES\n{}", entries.join(".Pp\n"))
+}
+
+fn definitions(page: &Manpage) -> String {
+    term_list(page, "DEFINITIONS", &page.definitions)
+}
+
+/// The DIAGNOSTICS section, as a `-diag` list of the messages.
+fn diagnostics(page: &Manpage) -> String {
+    if page.diagnostics.is_empty() {
+        return String::new();
+    }
+    let mut ret = ".Sh DIAGNOSTICS\n.Bl -diag\n".to_string();
+    for Definition { term, doc, .. } in page.diagnostics.iter() {
+        ret.push_str(&format!(".It {}\n{}\n", arg(page, term), text(page, doc)));
+    }
+    ret.push_str(".El\n");
+    ret
+}
+
+/// The sections flags have entries in with `#[manpage(under = "...")]`, such as FILES.
+fn routed_sections(page: &Manpage) -> String {
+    page.routed_sections()
+        .iter()
+        .map(|(title, entries)| match title.as_str() {
+            "ENVIRONMENT" => marked_list(page, title, entries, "Ev"),
+            "FILES" => marked_list(page, title, entries, "Pa"),
+            _ => term_list(page, title, entries),
+        })
+        .collect()
+}
+
+/// A section of tagged paragraphs whose terms are set with `mark`, such as `Ev` for the
+/// variables of ENVIRONMENT and `Pa` for the paths of FILES.
+fn marked_list(page: &Manpage, title: &str, entries: &[Definition], mark: &str) -> String {
+    let mut ret = format!(".Sh {}\n.Bl -tag -width Ds\n", arg(page, title));
+    for Definition {
+        term,
+        doc,
+        overridden_by,
+    } in entries.iter()
+    {
+        ret.push_str(&format!(".It {} {}\n", mark, arg(page, term)));
+        if !doc.is_empty() {
+            ret.push_str(&format!("{}\n", text(page, doc)));
+        }
+        if let Some(flag) = overridden_by {
+            ret.push_str(&format!("Overridden by\n.{} .\n", reference(page, flag)));
+        }
+    }
+    ret.push_str(".El\n");
+    ret
+}
+
+/// A section of tagged paragraphs, one per term.
+fn term_list(page: &Manpage, title: &str, entries: &[Definition]) -> String {
+    if entries.is_empty() {
+        return String::new();
+    }
+    format!(".Sh {}\n{}", arg(page, title), term_items(page, entries))
+}
+
+/// A tagged list with an item per term.
+fn term_items(page: &Manpage, entries: &[Definition]) -> String {
+    let mut ret = ".Bl -tag -width Ds\n".to_string();
+    for Definition { term, doc, .. } in entries.iter() {
+        ret.push_str(&format!(".It {}\n{}\n", arg(page, term), text(page, doc)));
+    }
+    ret.push_str(".El\n");
+    ret
+}
+
+/// The EXIT STATUS section: `.Ex -std` if the program exits as usual, then the declared codes.
+fn exit_status(page: &Manpage) -> String {
+    if !page.std_exit_status && page.exit_statuses.is_empty() {
+        return String::new();
+    }
+    let mut ret = ".Sh EXIT STATUS\n".to_string();
+    if page.std_exit_status {
+        ret.push_str(".Ex -std\n");
+    }
+    if !page.exit_statuses.is_empty() {
+        ret.push_str(&term_items(page, &page.exit_codes()));
+    }
+    ret
+}
+
+/// The COMMANDS section of a page whose subcommands have their own pages, referring to each that
+/// has one.
+fn commands(page: &Manpage) -> String {
+    if !page.subcommand_pages || (page.subcommands.is_empty() && !page.external_subcommands) {
+        return String::new();
+    }
+    let mut ret = ".Sh COMMANDS\n".to_string();
+    if page.subcommands.is_empty() {
+        ret.push_str(&format!("{}\n", EXTERNAL_SUBCOMMANDS));
+        return ret;
+    }
+    ret.push_str(&format!(
+        ".Bl -tag -width Ds{}\n",
+        list_style(
+            page.subcommands_list
+                .clone()
+                .unwrap_or_else(ListStyle::subcommands)
+        )
+    ));
+    for cmd in page.subcommands.iter() {
+        ret.push_str(&format!(
+            ".It {}{}\n",
+            command_names(page, cmd),
+            placeholder(page, &cmd.args, "ARGUMENT")
+        ));
+        let doc = super::summary(&cmd.doc);
+        if !doc.is_empty() {
+            ret.push_str(&format!("{}\n", text(page, &doc_sentence(doc))));
+        }
+        ret.push_str(&command_aliases(page, cmd));
+        ret.push_str(&globals_note(page));
+        if cmd.own_page {
+            ret.push_str(&format!(
+                "See\n.Xr {} {} .\n",
+                arg(page, &page.subcommand_page(cmd)),
+                arg(page, page.manual_section())
+            ));
+        }
+    }
+    ret.push_str(".El\n");
+    if page.external_subcommands {
+        ret.push_str(&format!(".Pp\n{}\n", EXTERNAL_SUBCOMMANDS));
+    }
+    ret
+}
+
+/// An `.Ss` subsection per declared subsection, with `.Pp` between its paragraphs.
+fn subsections(page: &Manpage) -> String {
+    let mut ret = String::new();
+    for Subsection { title, doc } in page.subsections.iter() {
+        let paras = paragraphs(doc)
+            .map(|para| text(page, para))
+            .collect::<Vec<_>>();
+        ret.push_str(&format!(
+            ".Ss {}\n{}\n",
+            arg(page, title),
+            paras.join("\n.Pp\n")
+        ));
+    }
+    ret
+}
+
+/// The paragraph explaining which setting wins when it is given more than once.
+fn settings(page: &Manpage) -> String {
+    let Settings {
+        environment,
+        config_file,
+    } = match page.settings.as_ref() {
+        Some(settings) => settings,
+        None => return String::new(),
+    };
+    let mut ret = ".Pp\nSettings are taken from the following sources, \
+                   in order of decreasing precedence:\n.Bl -enum -compact\n.It\ncommand line options\n"
+        .to_string();
+    if *environment {
+        ret.push_str(".It\nenvironment variables\n");
+    }
+    if let Some(path) = config_file {
+        ret.push_str(&format!(
+            ".It\nthe configuration file\n.Pa {}\n",
+            arg(page, path)
+        ));
+    }
+    ret.push_str(".It\nbuilt-in defaults\n.El\n");
+    ret
+}
+
+pub fn footer(page: &Manpage) -> String {
+    format!(
+        "{provenance}{commands}{definitions}{sections}{exit_status}{keybindings}{examples}{diagnostics}{see_also}{standards}{history}{authors}{caveats}{bugs}{copyright}",
+        provenance = provenance(page, Part::Footer),
+        commands = commands(page),
+        definitions = definitions(page),
+        sections = routed_sections(page),
+        exit_status = exit_status(page),
+        keybindings = keybindings(page),
+        examples = examples(page),
+        diagnostics = diagnostics(page),
+        see_also = see_also(page),
+        standards = standards(page),
+        history = history(page),
+        authors = authors(page),
+        caveats = caveats(page),
+        bugs = bugs(page),
+        copyright = copyright(page),
+    )
+    .trim_end()
+    .to_string()
+}
+
+/// One `.Xr name section` line per related page.
+fn see_also(page: &Manpage) -> String {
+    if page.see_also.is_empty() {
+        return String::new();
+    }
+    let mut ret = ".Sh SEE ALSO\n".to_string();
+    for (idx, SeeAlso { name, section }) in page.see_also.iter().enumerate() {
+        ret.push_str(&format!(".Xr {} {}", arg(page, name), arg(page, section)));
+        if idx + 1 < page.see_also.len() {
+            ret.push_str(" ,");
+        }
+        ret.push('\n');
+    }
+    ret
+}
+
+/// The STANDARDS section, with `.Pp` between its paragraphs.
+fn standards(page: &Manpage) -> String {
+    let doc = match &page.standards {
+        Some(doc) => doc,
+        None => return String::new(),
+    };
+    let paras = paragraphs(doc)
+        .map(|para| text(page, para))
+        .collect::<Vec<_>>();
+    format!(".Sh STANDARDS\n{}\n", paras.join("\n.Pp\n"))
+}
+
+/// The HISTORY section, with `.Pp` between its paragraphs.
+fn history(page: &Manpage) -> String {
+    let doc = match &page.history {
+        Some(doc) => doc,
+        None => return String::new(),
+    };
+    let paras = paragraphs(doc)
+        .map(|para| text(page, para))
+        .collect::<Vec<_>>();
+    format!(".Sh HISTORY\n{}\n", paras.join("\n.Pp\n"))
+}
+
+/// One `.An Name Aq Mt address` line per author, with `Lk` in place of `Mt` for web addresses.
+fn authors(page: &Manpage) -> String {
+    let authors = match page.authors() {
+        Some(authors) => split_authors(authors),
+        None => return String::new(),
+    };
+    let mut ret = ".Sh AUTHORS\n".to_string();
+    for (idx, (name, address)) in authors.iter().enumerate() {
+        let address = address.map(|address| match address.strip_prefix("mailto:") {
+            Some(email) => format!("Mt {}", arg(page, email)),
+            None if address.contains("://") => format!("Lk {}", arg(page, address)),
+            None => format!("Mt {}", arg(page, address)),
+        });
+        ret.push_str(&match (name.is_empty(), address) {
+            (false, Some(address)) => format!(".An {} Aq {}", arg(page, name), address),
+            (false, None) => format!(".An {}", arg(page, name)),
+            (true, Some(address)) => format!(".{}", address),
+            (true, None) => continue,
+        });
+        if idx + 1 < authors.len() {
+            ret.push_str(" ,");
+        }
+        ret.push('\n');
+    }
+    ret
+}
+
+/// The CAVEATS section, with `.Pp` between its paragraphs.
+fn caveats(page: &Manpage) -> String {
+    let doc = match &page.caveats {
+        Some(doc) => doc,
+        None => return String::new(),
+    };
+    let paras = paragraphs(doc)
+        .map(|para| text(page, para))
+        .collect::<Vec<_>>();
+    format!(".Sh CAVEATS\n{}\n", paras.join("\n.Pp\n"))
+}
+
+/// Where to report bugs, with `Mt` for email addresses and `Lk` otherwise.
+fn bugs(page: &Manpage) -> String {
+    let bugs = match &page.bugs {
+        Some(bugs) => bugs,
+        None => return String::new(),
+    };
+    let address = match bugs.strip_prefix("mailto:") {
+        Some(email) => format!("Mt {}", arg(page, email)),
+        None if !bugs.contains("://") && bugs.contains('@') => format!("Mt {}", arg(page, bugs)),
+        None => format!("Lk {}", arg(page, bugs)),
+    };
+    format!(".Sh BUGS\nReport bugs to\n.{} .\n", address)
+}
+
+/// The COPYRIGHT section, with `.Pp` between its paragraphs.
+fn copyright(page: &Manpage) -> String {
+    let doc = match &page.copyright {
+        Some(doc) => doc,
+        None => return String::new(),
+    };
+    let paras = paragraphs(doc)
+        .map(|para| text(page, para))
+        .collect::<Vec<_>>();
+    format!(".Sh COPYRIGHT\n{}\n", paras.join("\n.Pp\n"))
+}
+
+/// ` Ar name` or ` Ar name ...`, for an option or subcommand that takes values, with `Pa` in
+/// place of `Ar` for paths.
+fn placeholder(page: &Manpage, args: &Option<TakesValue>, fallback: &str) -> String {
+    match args {
+        Some(value) => {
+            let mac = match value.kind {
+                Some(
+                    ValueKind::Path
+                    | ValueKind::File
+                    | ValueKind::Directory
+                    | ValueKind::Executable,
+                ) => "Pa",
+                Some(_) | None => "Ar",
+            };
+            format!(
+                "{}{}{}",
+                if value.optional_value { " Op" } else { "" },
+                value
+                    .placeholders(page, fallback)
+                    .iter()
+                    .map(|name| format!(" {} {}", mac, arg(page, name)))
+                    .collect::<String>(),
+                if value.multiple { " ..." } else { "" }
+            )
+        }
+        None => String::new(),
+    }
+}
+
+/// The SYNOPSIS form invoking `cmd`, after the program's own `synopsis`. The flags of a
+/// subcommand with its own page are left to that page. An optional subcommand is bracketed
+/// along with its flags and operands.
+fn subcommand_form(page: &Manpage, synopsis: &str, cmd: &Subcommand) -> String {
+    let mut ret = format!(
+        "{}{}.Cm {}\n",
+        synopsis,
+        if page.optional_subcommand {
+            ".Oo\n"
+        } else {
+            ""
+        },
+        arg(page, &cmd.name)
+    );
+    let flags = if page.subcommand_pages {
+        &[][..]
+    } else {
+        &cmd.flags[..]
+    };
+    for flag in page.options(flags) {
+        match flag_macros(page, flag) {
+            Some(macros) if flag.required => ret.push_str(&format!(".{}\n", macros)),
+            Some(macros) => ret.push_str(&format!(".Op {}\n", macros)),
+            None => {}
+        }
+    }
+    let args = placeholder(page, &cmd.args, "ARGUMENT");
+    if !args.is_empty() {
+        ret.push_str(&format!(".{}\n", args.trim_start()));
+    }
+    if page.optional_subcommand {
+        ret.push_str(".Oc\n");
+    }
+    ret
+}
+
+/// A synopsis form wrapped in `.Bk -words`, so that no option is split from its argument when
+/// the synopsis wraps.
+fn keep(form: &str) -> String {
+    if form.is_empty() {
+        return String::new();
+    }
+    format!(".Bk -words\n{}.Ek\n", form)
+}
+
+/// The macros referring to `flag` from another one's entry: its long name, its short name or
+/// the placeholder of an operand.
+fn reference(page: &Manpage, flag: &Flag) -> String {
+    match (&flag.long, &flag.short, &flag.positional) {
+        (Some(long), _, _) => format!("Fl {}", arg(page, &format!("-{}", long))),
+        (None, Some(short), _) => format!("Fl {}", arg(page, short)),
+        (None, None, name) => placeholder(page, &flag.args, name.as_deref().unwrap_or("ARGUMENT"))
+            .trim_start()
+            .to_string(),
+    }
+}
+
+/// The macros naming a subcommand and its visible aliases, without the leading dot, for example
+/// `Ic checkout , Ic co`.
+fn command_names(page: &Manpage, cmd: &Subcommand) -> String {
+    cmd.names()
+        .into_iter()
+        .map(|name| format!("Ic {}", arg(page, name)))
+        .collect::<Vec<_>>()
+        .join(" , ")
+}
+
+/// The sentence in a subcommand's entry naming its hidden aliases.
+fn command_aliases(page: &Manpage, cmd: &Subcommand) -> String {
+    if cmd.aliases.is_empty() {
+        return String::new();
+    }
+    format!(
+        "Also known as\n.Ic {} .\n",
+        cmd.aliases
+            .iter()
+            .map(|alias| arg(page, alias))
+            .collect::<Vec<_>>()
+            .join(" , Ic ")
+    )
+}
+
+/// User text set as a literal block, as written.
+fn literal_block(page: &Manpage, val: &str) -> String {
+    format!(".Bd -literal\n{}\n.Ed\n", roff::literal(page, val))
+}
+
+/// One paragraph per required group, saying how many of its flags must be given.
+fn group_notes(page: &Manpage) -> String {
+    let mut ret = String::new();
+    for (group, members) in page.required_groups() {
+        ret.push_str(&format!(
+            ".Pp\n{} of\n.{}\nmust be given.\n",
+            if group.multiple {
+                "At least one"
+            } else {
+                "Exactly one"
+            },
+            members
+                .into_iter()
+                .map(|flag| reference(page, flag))
+                .collect::<Vec<_>>()
+                .join(" , ")
+        ));
+    }
+    ret
+}
+
+/// The sentence in a subcommand's entry referring to the global flags, which it takes too.
+fn globals_note(page: &Manpage) -> String {
+    let globals = page.globals();
+    if globals.is_empty() {
+        return String::new();
+    }
+    format!(
+        "Also takes the global option{}\n.{} .\n",
+        if globals.len() == 1 { "" } else { "s" },
+        globals
+            .into_iter()
+            .map(|flag| reference(page, flag))
+            .collect::<Vec<_>>()
+            .join(" , ")
+    )
+}
+
+/// The hidden aliases of a flag, the flags among `flags` it cannot be combined with or requires,
+/// the values it is restricted to and where its value comes from besides the command line: its
+/// literal default, set with `Ql`, and its environment variable, set with `Ev`.
+fn value_notes(page: &Manpage, flags: &[Flag], flag: &Flag) -> String {
+    let mut ret = String::new();
+    let references = |referred: Vec<&Flag>| {
+        referred
+            .into_iter()
+            .map(|flag| reference(page, flag))
+            .collect::<Vec<_>>()
+            .join(" , ")
+    };
+    if !flag.aliases.is_empty() {
+        ret.push_str(&format!(
+            "Also known as\n.Fl {} .\n",
+            flag.aliases
+                .iter()
+                .map(|alias| arg(page, &format!("-{}", alias)))
+                .collect::<Vec<_>>()
+                .join(" , Fl ")
+        ));
+    }
+    let conflicts = flag.conflicts(flags);
+    if !conflicts.is_empty() {
+        ret.push_str(&format!(
+            "Cannot be combined with\n.{} .\n",
+            references(conflicts)
+        ));
+    }
+    let requirements = flag.requirements(flags);
+    if !requirements.is_empty() {
+        ret.push_str(&format!("Requires\n.{} .\n", references(requirements)));
+    }
+    let exemptions = flag.exemptions(flags);
+    match exemptions.len() {
+        0 => {}
+        1 => ret.push_str(&format!(
+            "Required unless\n.{}\nis given.\n",
+            references(exemptions)
+        )),
+        _ => ret.push_str(&format!(
+            "Required unless {} of\n.{}\n{} given.\n",
+            if flag.required_unless_all {
+                "all"
+            } else {
+                "one"
+            },
+            references(exemptions),
+            if flag.required_unless_all {
+                "are"
+            } else {
+                "is"
+            }
+        )),
+    }
+    match page.separator(flag) {
+        Some(Separator::Required) => ret.push_str(&format!(
+            "Only given after\n.Fl {} ,\nso that its values may start with a hyphen.\n",
+            arg(page, "-")
+        )),
+        Some(Separator::Optional) => ret.push_str(
+            "Takes every argument from its first value on, even those starting with a hyphen.\n",
+        ),
+        None => {}
+    }
+    let conditions = flag.conditions(flags);
+    if !conditions.is_empty() {
+        ret.push_str(&format!(
+            "Required when\n{} .\n",
+            conditions
+                .into_iter()
+                .map(|(other, value)| format!(
+                    ".{}\nis\n.Ql {}",
+                    reference(page, other),
+                    arg(page, value)
+                ))
+                .collect::<Vec<_>>()
+                .join(" ,\nor when\n")
+        ));
+    }
+    if flag.possible_values.iter().any(|value| value.doc.is_some()) {
+        ret.push_str("Can be one of:\n.Bl -tag -width Ds -compact\n");
+        for PossibleValue { name, doc } in flag.possible_values.iter() {
+            ret.push_str(&format!(".It Ql {}\n", arg(page, name)));
+            if let Some(doc) = doc {
+                ret.push_str(&format!("{}\n", text(page, &doc_sentence(doc))));
+            }
+        }
+        ret.push_str(".El\n");
+    } else if !flag.possible_values.is_empty() {
+        ret.push_str(&format!(
+            "Can be one of\n.Ql {} .\n",
+            flag.possible_values
+                .iter()
+                .map(|value| arg(page, &value.name))
+                .collect::<Vec<_>>()
+                .join(" , Ql ")
+        ));
+    }
+    if let Some(default) = flag.default_value.as_ref() {
+        ret.push_str(&format!("Defaults to\n.Ql {} .\n", arg(page, default)));
+    }
+    if let Some(env) = flag.env.as_ref() {
+        ret.push_str(&format!(
+            "May also be set with the\n.Ev {}\nenvironment variable.\n",
+            arg(page, env)
+        ));
+    }
+    ret
+}
+
+/// The macros naming a flag and its argument, without the leading dot, for example
+/// `Fl o , Fl -output Ar output`.
+fn flag_macros(page: &Manpage, flag: &Flag) -> Option<String> {
+    let Flag {
+        long, short, args, ..
+    } = flag;
+    let mut names = match (long, short) {
+        (None, None) => return None,
+        (Some(l), Some(s)) => format!("Fl {} , Fl {}", arg(page, s), arg(page, &format!("-{}", l))),
+        (Some(l), None) => format!("Fl {}", arg(page, &format!("-{}", l))),
+        (None, Some(s)) => format!("Fl {}", arg(page, s)),
+    };
+    for other in flag.other_longs() {
+        names.push_str(&format!(" , Fl {}", arg(page, &format!("-{}", other))));
+    }
+    let fallback = long
+        .as_ref()
+        .or(short.as_ref())
+        .map(String::as_str)
+        .unwrap_or("ARGUMENT");
+    Some(format!(
+        "{}{}{}",
+        names,
+        placeholder(page, args, fallback),
+        if flag.trailing_ellipsis() { " ..." } else { "" }
+    ))
+}
+
+/// A layout argument of a `.Bl` line, such as a width or an offset.
+///
+/// These are never user markup, so they are always hardened. Unquoted, a macro name such as
+/// `Ds` stands for its own width.
+fn layout_arg(val: &str) -> String {
+    let val = roff::harden_text(&val.replace('"', ""))
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if val.contains(' ') {
+        format!("\"{}\"", val)
+    } else {
+        val
+    }
+}
+
+/// The `-width` argument of the OPTIONS list: the page's own, or the longest of `flags`.
+fn tag_width<'a>(page: &Manpage, flags: impl Iterator<Item = &'a String>) -> String {
+    match page.tag_width.as_ref() {
+        Some(width) => layout_arg(width),
+        None => {
+            // Measured by the characters the glyphs stand for, and escaped again as a layout
+            // argument.
+            let widest = flags
+                .map(|macros| roff::unglyph(&sample(macros)))
+                .max_by_key(|sample| sample.chars().count())
+                .unwrap_or_else(|| "Ds".to_string());
+            roff::glyphs(page, layout_arg(&widest))
+        }
+    }
+}
+
+/// The `-compact` and `-offset` arguments of a `.Bl` line.
+fn list_style(style: ListStyle) -> String {
+    let mut ret = String::new();
+    if style.compact {
+        ret.push_str(" -compact");
+    }
+    match style.offset.as_deref().map(layout_arg) {
+        Some(offset) if !offset.is_empty() => ret.push_str(&format!(" -offset {}", offset)),
+        _ => {}
+    }
+    ret
+}
+
+/// Each of `flags` that has a name, paired with its macros.
+fn named<'a>(page: &Manpage, flags: Vec<&'a Flag>) -> Vec<(String, &'a Flag)> {
+    flags
+        .into_iter()
+        .filter_map(|flag| Some((flag_macros(page, flag)?, flag)))
+        .collect()
+}
+
+/// A tagged list of `flags`, paired with their macros.
+fn option_table(page: &Manpage, flags: &[(String, &Flag)]) -> String {
+    let mut ret = format!(
+        ".Bl -tag -width {}{}\n",
+        tag_width(page, flags.iter().map(|(macros, _)| macros)),
+        list_style(page.options_list.clone().unwrap_or_else(ListStyle::options))
+    );
+    for (macros, flag) in flags.iter() {
+        ret.push_str(&format!(".It {}\n", macros));
+        if let Some(doc) = verbatim_doc(flag) {
+            ret.push_str(&literal_block(page, doc));
+        }
+        if let Some(doc) = flag_doc(flag) {
+            ret.push_str(&format!("{}\n", text(page, &doc)));
+        }
+        ret.push_str(&value_notes(page, &page.flags, flag));
+    }
+    ret.push_str(".El\n");
+    ret
+}
+
+pub fn body(page: &Manpage) -> String {
+    let mut synopsis = String::new();
+    let (flags, headed) = page.headed_options(&page.flags);
+    let flags = named(page, flags);
+    for alternation in page.alternations() {
+        let macros = alternation
+            .iter()
+            .filter_map(|flag| flag_macros(page, flag))
+            .collect::<Vec<_>>();
+        if macros.is_empty() {
+            continue;
+        }
+        let macros = macros.join(" | ");
+        if page.required_alternation(&alternation) {
+            synopsis.push_str(&format!(".{}\n", macros));
+        } else {
+            synopsis.push_str(&format!(".Op {}\n", macros));
+        }
+    }
+    let flag_table = option_table(page, &flags);
+    // Options with a `help_heading` come in a subsection per heading.
+    let headed = headed
+        .into_iter()
+        .map(|(heading, flags)| (heading, named(page, flags)))
+        .filter(|(_, flags)| !flags.is_empty())
+        .map(|(heading, flags)| {
+            format!(".Ss {}\n{}", arg(page, heading), option_table(page, &flags))
+        })
+        .collect::<String>();
+    let mut subcommands = format!(
+        ".Bl -tag -width Ds{}\n",
+        list_style(
+            page.subcommands_list
+                .clone()
+                .unwrap_or_else(ListStyle::subcommands)
+        )
+    );
+    for cmd in page.subcommands.iter() {
+        subcommands.push_str(&format!(
+            ".It {}{}\n",
+            command_names(page, cmd),
+            placeholder(page, &cmd.args, "ARGUMENT")
+        ));
+        for flag in page.options(&cmd.flags) {
+            let macros = match flag_macros(page, flag) {
+                Some(macros) => macros,
+                None => continue,
+            };
+            subcommands.push_str(&format!(".{}\n", macros));
+            if let Some(doc) = verbatim_doc(flag) {
+                subcommands.push_str(&literal_block(page, doc));
+            }
+            if let Some(doc) = flag_doc(flag) {
+                subcommands.push_str(&format!("{}\n", text(page, &doc)));
+            }
+            subcommands.push_str(&value_notes(page, &cmd.flags, flag));
+        }
+        if let Some(doc) = &cmd.doc {
+            subcommands.push_str(&format!("{}\n", text(page, &doc_sentence(doc))));
+        }
+        subcommands.push_str(&command_aliases(page, cmd));
+        subcommands.push_str(&globals_note(page));
+    }
+    subcommands.push_str(".El\n");
+    let operands = page.operands();
+    let mut operand_table = format!(
+        ".Bl -tag -width Ds{}\n",
+        list_style(page.options_list.clone().unwrap_or_else(ListStyle::options))
+    );
+    for (name, flag) in operands.iter() {
+        let operand = placeholder(page, &flag.args, name);
+        // `Fl -` renders as the `--` ending the options.
+        let separator = format!("Fl {}", arg(page, "-"));
+        synopsis.push_str(&match page.separator(flag) {
+            Some(Separator::Required) if flag.required => format!(".{}{}\n", separator, operand),
+            Some(Separator::Required) => format!(".Op {}{}\n", separator, operand),
+            Some(Separator::Optional) if flag.required => {
+                format!(".Op {}\n.{}\n", separator, operand.trim_start())
+            }
+            Some(Separator::Optional) => format!(".Op {}\n.Op{}\n", separator, operand),
+            None if flag.required => format!(".{}\n", operand.trim_start()),
+            None => format!(".Op{}\n", operand),
+        });
+        operand_table.push_str(&format!(".It{}\n", operand));
+        if let Some(doc) = flag.doc.as_ref() {
+            operand_table.push_str(&format!("{}\n", text(page, &doc_sentence(doc))));
+        }
+        operand_table.push_str(&value_notes(page, &page.flags, flag));
+    }
+    operand_table.push_str(".El\n");
+    // One usage form per subcommand, each after the flags the program itself takes.
+    let forms = if page.subcommands.is_empty() {
+        vec![synopsis]
+    } else {
+        page.subcommands
+            .iter()
+            .map(|cmd| subcommand_form(page, &synopsis, cmd))
+            .collect()
+    };
+    // The page of a subcommand shows the program followed by the subcommand.
+    let name = match page.command() {
+        Some((parent, cmd)) => format!(".Nm {}\n.Cm {}\n", arg(page, parent), arg(page, cmd)),
+        None => ".Nm\n".to_string(),
+    };
+    let synopsis = std::iter::once(name)
+        .chain(
+            page.invocations
+                .iter()
+                .map(|name| format!(".Nm {}\n", arg(page, name))),
+        )
+        .flat_map(|nm| {
+            forms
+                .iter()
+                .map(move |form| format!("{}{}", nm, keep(form)))
+        })
+        .collect::<String>();
+    let mut parts = vec![];
+    if !page.flags.is_empty()
+        || !page.invocations.is_empty()
+        || !page.subcommands.is_empty()
+        || page.parent.is_some()
+    {
+        parts.push(synopsis.trim());
+    }
+    let description = match page.description_text() {
+        Some(doc) if page.verbatim => literal_block(page, doc),
+        Some(doc) => {
+            let paras = paragraphs(doc)
+                .map(|para| text(page, para))
+                .collect::<Vec<_>>()
+                .join("\n.Pp\n");
+            // A paragraph break only separates the description from the synopsis before it, on
+            // pages where no heading does.
+            if parts.is_empty() || page.parent.is_some() {
+                paras
+            } else {
+                format!(".Pp\n{}", paras)
+            }
+        }
+        None => String::new(),
+    };
+    if !description.is_empty() {
+        parts.push(description.trim_end());
+    }
+    if !flags.is_empty() {
+        parts.push(flag_table.trim());
+    }
+    if !operands.is_empty() {
+        parts.push(operand_table.trim());
+    }
+    let group_notes = group_notes(page);
+    if !group_notes.is_empty() {
+        parts.push(group_notes.trim());
+    }
+    if !page.subcommands.is_empty() && !page.subcommand_pages {
+        parts.push(subcommands.trim());
+    }
+    let external = format!(".Pp\n{}", EXTERNAL_SUBCOMMANDS);
+    if page.external_subcommands && !page.subcommand_pages {
+        // A paragraph break only separates the note from what comes before it.
+        parts.push(if parts.is_empty() {
+            EXTERNAL_SUBCOMMANDS
+        } else {
+            &external
+        });
+    }
+    let settings = settings(page);
+    if !settings.is_empty() {
+        parts.push(settings.trim());
+    }
+    if !headed.is_empty() {
+        parts.push(headed.trim());
+    }
+    let subsections = subsections(page);
+    if !subsections.is_empty() {
+        parts.push(subsections.trim());
+    }
+    if parts.is_empty() {
+        return provenance(page, Part::Body);
+    }
+    // The page of a subcommand is written whole, so its body has headings of its own.
+    if page.parent.is_some() {
+        let mut ret = format!(
+            "{}.Sh SYNOPSIS\n{}\n",
+            provenance(page, Part::Body),
+            parts[0]
+        );
+        if parts.len() > 1 {
+            ret.push_str(&format!(".Sh DESCRIPTION\n{}\n", parts[1..].join("\n")));
+        }
+        return ret;
+    }
+    format!("{}{}\n", provenance(page, Part::Body), parts.join("\n"))
+}
+
+/// Roughly the text `macros` render to, for example `-o, --output output` for
+/// `Fl o , Fl -output Ar output`.
+fn sample(macros: &str) -> String {
+    let mut ret = String::new();
+    let mut flag = false;
+    for word in macros.split(' ') {
+        match word {
+            "Fl" => flag = true,
+            "Ar" | "Pa" | "Ic" => {}
+            "," => ret.push(','),
+            word => {
+                if !ret.is_empty() {
+                    ret.push(' ');
+                }
+                if flag {
+                    ret.push('-');
+                    flag = false;
+                }
+                ret.push_str(&word.replace("\\-", "-"));
+            }
+        }
+    }
+    ret
+}
+
+/// A compact two-column list, or nothing if `rows` is empty.
+fn column_list(title: &str, rows: Vec<(String, String)>) -> String {
+    if rows.is_empty() {
+        return String::new();
+    }
+    // `.Bl -column` sizes its first column after the widest sample text given to it.
+    let widest = rows
+        .iter()
+        .map(|(item, _)| sample(item))
+        .max_by_key(|sample| roff::unglyph(sample).chars().count())
+        .unwrap_or_default();
+    let mut ret = format!(
+        ".Sh {}\n.Bl -column \"{}\" -compact\n",
+        title,
+        widest.replace('"', "")
+    );
+    for (item, doc) in rows {
+        ret.push_str(&format!(".It {} Ta {}\n", item, doc));
+    }
+    ret.push_str(".El\n");
+    ret
+}
+
+/// The first line of a doc string, fit for a table cell.
+fn summary(page: &Manpage, doc: &Option<String>) -> String {
+    arg(page, super::summary(doc))
+}
+
+pub fn cheatsheet(page: &Manpage) -> String {
+    let (flags, subcommands) = page.cheatsheet_items();
+    let options = flags
+        .into_iter()
+        .filter_map(|flag| Some((flag_macros(page, flag)?, summary(page, &flag.doc))))
+        .collect();
+    let commands = subcommands
+        .into_iter()
+        .map(
+            |Subcommand {
+                 name, args, doc, ..
+             }| {
+                (
+                    format!(
+                        "Ic {}{}",
+                        arg(page, name),
+                        placeholder(page, args, "ARGUMENT")
+                    ),
+                    summary(page, doc),
+                )
+            },
+        )
+        .collect();
+    format!(
+        "{}\n{}{}",
+        header(page),
+        column_list("OPTIONS", options),
+        column_list("COMMANDS", commands)
+    )
+}
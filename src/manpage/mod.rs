@@ -0,0 +1,1983 @@
+// Copyright 2022 Manos Pitsidianakis <epilys@nessuent.net>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The manpage model and its renderers.
+//!
+//! Rendering is pure: it turns a [`Manpage`] into strings and never touches the filesystem or
+//! the process environment, so this module (minus [`output`]) builds for targets such as
+//! `wasm32-unknown-unknown`. Everything that reads `CARGO_PKG_*` variables happens in the derive
+//! before the model is built, and writing the rendered strings to disk lives in [`output`].
+//!
+//! Only the mdoc renderer is always built. Every other backend is behind the cargo feature of the
+//! same name (`man`, `rst`, `docbook`, `texinfo`, `text`, `markdown`, `json` and `yaml`), and
+//! gzip output is behind `gzip`.
+
+#[cfg(feature = "docbook")]
+mod docbook;
+#[cfg(any(feature = "json", feature = "yaml"))]
+mod export;
+pub mod lint;
+#[cfg(feature = "man")]
+mod man;
+#[cfg(feature = "markdown")]
+mod markdown;
+mod mdoc;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod output;
+pub mod roff;
+#[cfg(feature = "rst")]
+mod rst;
+#[cfg(feature = "texinfo")]
+mod texinfo;
+#[cfg(feature = "text")]
+mod text;
+
+use std::path::PathBuf;
+
+/// The format pages are rendered in, chosen with `#[manpage(format = "...")]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Semantic mdoc(7) macros, the default.
+    Mdoc,
+    /// Classic man(7) macros, for tooling that expects `.TH` and `.SH`.
+    #[cfg(feature = "man")]
+    Man,
+    /// reStructuredText with Sphinx `option` directives, for documentation builds.
+    #[cfg(feature = "rst")]
+    Rst,
+    /// A DocBook XML `refentry`, for documentation built from DocBook.
+    #[cfg(feature = "docbook")]
+    Docbook,
+    /// A Texinfo node with `@deffn` option definitions, for GNU-style manuals.
+    #[cfg(feature = "texinfo")]
+    Texinfo,
+    /// Formatted plain text, as a pager would show the page.
+    #[cfg(feature = "text")]
+    Text,
+    /// CommonMark with an anchor per option and subcommand, as a chapter of an mdBook.
+    #[cfg(feature = "markdown")]
+    Markdown,
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Format::Mdoc
+    }
+}
+
+impl Format {
+    /// The name of every format, enabled or not, as given to the `format` attribute.
+    pub const NAMES: &'static [&'static str] = &[
+        "mdoc", "man", "rst", "docbook", "texinfo", "text", "markdown",
+    ];
+
+    /// The format called `name`, if it is known and its feature is enabled.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "mdoc" => Format::Mdoc,
+            #[cfg(feature = "man")]
+            "man" => Format::Man,
+            #[cfg(feature = "rst")]
+            "rst" => Format::Rst,
+            #[cfg(feature = "docbook")]
+            "docbook" => Format::Docbook,
+            #[cfg(feature = "texinfo")]
+            "texinfo" => Format::Texinfo,
+            #[cfg(feature = "text")]
+            "text" => Format::Text,
+            #[cfg(feature = "markdown")]
+            "markdown" => Format::Markdown,
+            _ => return None,
+        })
+    }
+
+    /// Whether the output is roff, which hardened pages are checked against.
+    pub fn is_roff(self) -> bool {
+        #[cfg(feature = "man")]
+        if self == Format::Man {
+            return true;
+        }
+        self == Format::Mdoc
+    }
+}
+
+/// What a value stands for, so that renderers can mark it up as such.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    /// A file system path, such as a `PathBuf` field or one parsed with `from_os_str`.
+    Path,
+    /// A number, such as a `u32` field.
+    Number,
+    /// A file, as with `value_hint = ValueHint::FilePath`.
+    File,
+    /// A directory, as with `value_hint = ValueHint::DirPath`.
+    Directory,
+    /// A program to run, as with `value_hint = ValueHint::ExecutablePath`.
+    Executable,
+    /// A host name or address, as with `value_hint = ValueHint::Hostname`.
+    Hostname,
+    /// A URL, as with `value_hint = ValueHint::Url`.
+    Url,
+    /// A command, with or without its arguments, as with `value_hint = ValueHint::CommandName`.
+    Command,
+    /// A user name, as with `value_hint = ValueHint::Username`.
+    Username,
+    /// An email address, as with `value_hint = ValueHint::EmailAddress`.
+    Email,
+}
+
+impl ValueKind {
+    #[cfg(any(feature = "json", feature = "yaml"))]
+    pub fn name(self) -> &'static str {
+        match self {
+            ValueKind::Path => "path",
+            ValueKind::Number => "number",
+            ValueKind::File => "file",
+            ValueKind::Directory => "directory",
+            ValueKind::Executable => "executable",
+            ValueKind::Hostname => "hostname",
+            ValueKind::Url => "url",
+            ValueKind::Command => "command",
+            ValueKind::Username => "username",
+            ValueKind::Email => "email",
+        }
+    }
+
+    /// The placeholder the value is named by when it has no value name. The kinds inferred from
+    /// the field's type keep the argument's own name.
+    fn placeholder(self) -> Option<&'static str> {
+        match self {
+            ValueKind::Path | ValueKind::Number => None,
+            ValueKind::File => Some("file"),
+            ValueKind::Directory => Some("directory"),
+            ValueKind::Executable => Some("program"),
+            ValueKind::Hostname => Some("host"),
+            ValueKind::Url => Some("url"),
+            ValueKind::Command => Some("command"),
+            ValueKind::Username => Some("user"),
+            ValueKind::Email => Some("email"),
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct TakesValue {
+    pub kind: Option<ValueKind>,
+    pub multiple: bool,
+    /// The `value_name`, or `value_names`, given to clap, shown as is in place of the argument's
+    /// own name.
+    pub names: Vec<String>,
+    /// The `number_of_values` given to clap, when each occurrence takes more than one value.
+    pub number_of_values: Option<u64>,
+    /// Whether the value may be left out, as for an `Option<Option<T>>` field.
+    pub optional_value: bool,
+}
+
+impl TakesValue {
+    /// The placeholders naming the values taken at once, where `fallback` is the name of the
+    /// argument they belong to. Without value names, the name its kind suggests or else the
+    /// argument's name stands for each value.
+    fn placeholders(&self, page: &Manpage, fallback: &str) -> Vec<String> {
+        if !self.names.is_empty() {
+            return self.names.clone();
+        }
+        let fallback = self
+            .kind
+            .and_then(ValueKind::placeholder)
+            .unwrap_or(fallback);
+        let name = match page.value_names {
+            ValueNames::Uppercase => fallback.to_uppercase().replace('-', "_"),
+            ValueNames::Verbatim => fallback.to_string(),
+        };
+        vec![name; self.number_of_values.unwrap_or(1).max(1) as usize]
+    }
+
+    /// The rendered placeholders of the value, in brackets when it may be left out.
+    #[cfg(any(
+        feature = "man",
+        feature = "rst",
+        feature = "docbook",
+        feature = "texinfo",
+        feature = "text",
+        feature = "markdown"
+    ))]
+    fn optional(&self, values: String) -> String {
+        if self.optional_value {
+            format!(" [{}]", values.trim_start())
+        } else {
+            values
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct Flag {
+    long: Option<String>,
+    short: Option<String>,
+    args: Option<TakesValue>,
+    doc: Option<String>,
+    featured: bool,
+    /// Whether the argument must be given, so the synopsis shows it without brackets.
+    required: bool,
+    /// The value name of a positional argument, which has neither a long nor a short name.
+    positional: Option<String>,
+    /// The clap name of the argument, which `conflicts_with` refers to.
+    id: Option<String>,
+    /// The names of the arguments this one cannot be given together with.
+    conflicts_with: Vec<String>,
+    /// The names of the arguments this one can only be given together with.
+    requires: Vec<String>,
+    /// The names of the arguments that make this one optional when given, as with
+    /// `required_unless`.
+    required_unless: Vec<String>,
+    /// Whether only all of `required_unless` together make this argument optional, as with
+    /// `required_unless_all`, rather than any one of them.
+    required_unless_all: bool,
+    /// The arguments, and their values, that make this one required, as with `required_if`.
+    required_if: Vec<(String, String)>,
+    /// The argument group this one is in, whose members exclude each other.
+    group: Option<String>,
+    /// The environment variable the value is also read from.
+    env: Option<String>,
+    /// The default value, when it is given literally.
+    default_value: Option<String>,
+    /// The values the argument is restricted to, when they are given literally or documented.
+    possible_values: Vec<PossibleValue>,
+    /// Whether the values are matched regardless of case, as with `case_insensitive`.
+    case_insensitive: bool,
+    /// Whether the flag also has a `--no-` form turning it off.
+    negatable: bool,
+    /// Whether the flag is `global`, so subcommands take it too.
+    global: bool,
+    /// Other long names of the flag, listed with its own.
+    visible_aliases: Vec<String>,
+    /// Other long names of the flag that only its description mentions.
+    aliases: Vec<String>,
+    /// Whether the doc is set as written, as with `verbatim_doc_comment`, rather than reflowed.
+    verbatim: bool,
+    /// Whether the flag may be given more than once: it is a `Vec` option, is set `multiple` or
+    /// counts its occurrences, as with `parse(from_occurrences)`.
+    repeatable: bool,
+    /// Whether the operand is only given after `--`, as with `last`.
+    last: bool,
+    /// The section, such as FILES or ENVIRONMENT, the flag also has an entry in.
+    section: Option<String>,
+    /// The heading the flag is listed under, with the others given the same `help_heading`.
+    heading: Option<String>,
+}
+
+impl Flag {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn long(&mut self, val: String) -> &mut Self {
+        self.long = Some(val);
+        self
+    }
+
+    pub fn short(&mut self, val: String) -> &mut Self {
+        self.short = Some(val);
+        self
+    }
+
+    pub fn doc(&mut self, val: String) -> &mut Self {
+        self.doc = Some(val);
+        self
+    }
+
+    pub fn args(&mut self, val: TakesValue) -> &mut Self {
+        self.args = Some(val);
+        self
+    }
+
+    pub fn featured(&mut self, val: bool) -> &mut Self {
+        self.featured = val;
+        self
+    }
+
+    pub fn required(&mut self, val: bool) -> &mut Self {
+        self.required = val;
+        self
+    }
+
+    pub fn positional(&mut self, val: String) -> &mut Self {
+        self.positional = Some(val);
+        self
+    }
+
+    pub fn id(&mut self, val: String) -> &mut Self {
+        self.id = Some(val);
+        self
+    }
+
+    pub fn push_conflict(&mut self, val: String) -> &mut Self {
+        self.conflicts_with.push(val);
+        self
+    }
+
+    pub fn push_requirement(&mut self, val: String) -> &mut Self {
+        self.requires.push(val);
+        self
+    }
+
+    pub fn push_required_unless(&mut self, val: String) -> &mut Self {
+        self.required_unless.push(val);
+        self
+    }
+
+    pub fn required_unless_all(&mut self, val: bool) -> &mut Self {
+        self.required_unless_all = val;
+        self
+    }
+
+    /// Require the argument when the argument named `id` is given `value`.
+    pub fn push_required_if(&mut self, id: String, value: String) -> &mut Self {
+        self.required_if.push((id, value));
+        self
+    }
+
+    /// Set the `value_name` of the flag's argument, if it takes one.
+    pub fn value_name(&mut self, val: String) -> &mut Self {
+        if let Some(args) = self.args.as_mut() {
+            args.names = vec![val];
+        }
+        self
+    }
+
+    /// Add one of the `value_names` of the flag's argument, if it takes one.
+    pub fn push_value_name(&mut self, val: String) -> &mut Self {
+        if let Some(args) = self.args.as_mut() {
+            args.names.push(val);
+        }
+        self
+    }
+
+    /// Set the `number_of_values` of the flag's argument, if it takes one.
+    pub fn number_of_values(&mut self, val: u64) -> &mut Self {
+        if let Some(args) = self.args.as_mut() {
+            args.number_of_values = Some(val);
+        }
+        self
+    }
+
+    pub fn env(&mut self, val: String) -> &mut Self {
+        self.env = Some(val);
+        self
+    }
+
+    pub fn default_value(&mut self, val: String) -> &mut Self {
+        self.default_value = Some(val);
+        self
+    }
+
+    pub fn push_possible_value(&mut self, val: String) -> &mut Self {
+        self.possible_values.push(PossibleValue::new(val, None));
+        self
+    }
+
+    pub fn case_insensitive(&mut self, val: bool) -> &mut Self {
+        self.case_insensitive = val;
+        self
+    }
+
+    /// Whether `name` stands for the listed value `value`.
+    fn is_value(&self, value: &PossibleValue, name: &str) -> bool {
+        value.name == name || (self.case_insensitive && value.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Whether `name` can be documented as one of the values of the argument: any name when they
+    /// are not listed, as with an `arg_enum!`, and one of them otherwise.
+    pub fn accepts_value(&self, name: &str) -> bool {
+        self.possible_values.is_empty()
+            || self
+                .possible_values
+                .iter()
+                .any(|value| self.is_value(value, name))
+    }
+
+    /// Document one of the values of the argument, adding it to them if it is not listed yet.
+    ///
+    /// A listed value keeps its own spelling.
+    pub fn document_value(&mut self, val: PossibleValue) -> &mut Self {
+        match self
+            .possible_values
+            .iter()
+            .position(|value| self.is_value(value, &val.name))
+        {
+            Some(i) => self.possible_values[i].doc = val.doc,
+            None => self.possible_values.push(val),
+        }
+        self
+    }
+
+    pub fn repeatable(&mut self, val: bool) -> &mut Self {
+        self.repeatable = val;
+        self
+    }
+
+    pub fn last(&mut self, val: bool) -> &mut Self {
+        self.last = val;
+        self
+    }
+
+    pub fn verbatim(&mut self, val: bool) -> &mut Self {
+        self.verbatim = val;
+        self
+    }
+
+    pub fn negatable(&mut self, val: bool) -> &mut Self {
+        self.negatable = val;
+        self
+    }
+
+    pub fn global(&mut self, val: bool) -> &mut Self {
+        self.global = val;
+        self
+    }
+
+    pub fn push_visible_alias(&mut self, val: String) -> &mut Self {
+        self.visible_aliases.push(val);
+        self
+    }
+
+    pub fn push_alias(&mut self, val: String) -> &mut Self {
+        self.aliases.push(val);
+        self
+    }
+
+    /// Whether the flag's names and argument are followed by an ellipsis, as when it may be
+    /// repeated and its argument placeholder does not already end with one.
+    fn trailing_ellipsis(&self) -> bool {
+        self.repeatable && !self.args.as_ref().map_or(false, |args| args.multiple)
+    }
+
+    /// The long names listed after the flag's own: its visible aliases and its `--no-` form.
+    fn other_longs(&self) -> Vec<String> {
+        let mut ret = self.visible_aliases.clone();
+        match &self.long {
+            Some(long) if self.negatable => ret.push(format!("no-{}", long)),
+            _ => {}
+        }
+        ret
+    }
+
+    pub fn group(&mut self, val: String) -> &mut Self {
+        self.group = Some(val);
+        self
+    }
+
+    pub fn section(&mut self, val: String) -> &mut Self {
+        self.section = Some(val);
+        self
+    }
+
+    pub fn heading(&mut self, val: String) -> &mut Self {
+        self.heading = Some(val);
+        self
+    }
+
+    /// How other sections name the flag: by its long or short name, or as an operand.
+    fn display_name(&self) -> String {
+        match (&self.long, &self.short, &self.positional) {
+            (Some(long), _, _) => format!("--{}", long),
+            (None, Some(short), _) => format!("-{}", short),
+            (None, None, Some(name)) => name.clone(),
+            (None, None, None) => String::new(),
+        }
+    }
+
+    /// Whether `self` and `other` cannot be given together.
+    fn excludes(&self, other: &Flag) -> bool {
+        let conflicts = |a: &Flag, b: &Flag| {
+            b.id.as_ref()
+                .map_or(false, |id| a.conflicts_with.contains(id))
+        };
+        (self.group.is_some() && self.group == other.group)
+            || conflicts(self, other)
+            || conflicts(other, self)
+    }
+
+    /// The flags among `flags` that cannot be given together with `self`.
+    fn conflicts<'a>(&self, flags: &'a [Flag]) -> Vec<&'a Flag> {
+        flags
+            .iter()
+            .filter(|other| !std::ptr::eq(*other, self) && self.excludes(other))
+            .collect()
+    }
+
+    /// The flags among `flags` that `self` can only be given together with.
+    fn requirements<'a>(&self, flags: &'a [Flag]) -> Vec<&'a Flag> {
+        flags
+            .iter()
+            .filter(|other| {
+                other
+                    .id
+                    .as_ref()
+                    .map_or(false, |id| self.requires.contains(id))
+            })
+            .collect()
+    }
+
+    /// The flags among `flags` whose presence makes `self` optional.
+    fn exemptions<'a>(&self, flags: &'a [Flag]) -> Vec<&'a Flag> {
+        flags
+            .iter()
+            .filter(|other| {
+                other
+                    .id
+                    .as_ref()
+                    .map_or(false, |id| self.required_unless.contains(id))
+            })
+            .collect()
+    }
+
+    /// The flags among `flags`, each with the value that makes `self` required.
+    fn conditions<'a>(&'a self, flags: &'a [Flag]) -> Vec<(&'a Flag, &'a str)> {
+        self.required_if
+            .iter()
+            .filter_map(|(id, value)| {
+                flags
+                    .iter()
+                    .find(|other| other.id.as_ref() == Some(id))
+                    .map(|other| (other, value.as_str()))
+            })
+            .collect()
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct Subcommand {
+    name: String,
+    args: Option<TakesValue>,
+    flags: Vec<Flag>,
+    doc: Option<String>,
+    featured: bool,
+    /// Other names of the subcommand, listed with its own.
+    visible_aliases: Vec<String>,
+    /// Other names of the subcommand that only its description mentions.
+    aliases: Vec<String>,
+    /// Whether the flags of the subcommand are known, so that it can have a page of its own.
+    own_page: bool,
+}
+
+impl Subcommand {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            ..Self::default()
+        }
+    }
+
+    pub fn doc(&mut self, val: String) -> &mut Self {
+        self.doc = Some(val);
+        self
+    }
+
+    pub fn flags(&mut self, val: Vec<Flag>) -> &mut Self {
+        self.flags = val;
+        self
+    }
+
+    pub fn featured(&mut self, val: bool) -> &mut Self {
+        self.featured = val;
+        self
+    }
+
+    pub fn push_visible_alias(&mut self, val: String) -> &mut Self {
+        self.visible_aliases.push(val);
+        self
+    }
+
+    pub fn push_alias(&mut self, val: String) -> &mut Self {
+        self.aliases.push(val);
+        self
+    }
+
+    pub fn own_page(&mut self, val: bool) -> &mut Self {
+        self.own_page = val;
+        self
+    }
+
+    /// The name of the subcommand followed by its visible aliases.
+    fn names(&self) -> Vec<&str> {
+        std::iter::once(&self.name)
+            .chain(self.visible_aliases.iter())
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+/// An interactive key binding, documented in the KEY BINDINGS section.
+#[derive(Default, Debug, Clone)]
+pub struct Keybinding {
+    key: String,
+    action: Option<String>,
+    doc: Option<String>,
+}
+
+impl Keybinding {
+    pub fn new(key: String) -> Self {
+        Self {
+            key,
+            ..Self::default()
+        }
+    }
+
+    pub fn action(&mut self, val: String) -> &mut Self {
+        self.action = Some(val);
+        self
+    }
+
+    pub fn doc(&mut self, val: String) -> &mut Self {
+        self.doc = Some(val);
+        self
+    }
+}
+
+/// A term used in the page, explained in the DEFINITIONS section.
+#[derive(Default, Debug, Clone)]
+pub struct Definition {
+    term: String,
+    doc: String,
+    /// The flag that takes precedence over the variable, for the variables flags read.
+    overridden_by: Option<Flag>,
+}
+
+impl Definition {
+    pub fn new(term: String, doc: String) -> Self {
+        Self {
+            term,
+            doc,
+            ..Self::default()
+        }
+    }
+
+    pub fn overridden_by(&mut self, val: Flag) -> &mut Self {
+        self.overridden_by = Some(val);
+        self
+    }
+}
+
+/// An exit code of the program, with what it means, listed in the EXIT STATUS section.
+#[derive(Default, Debug, Clone)]
+pub struct ExitStatus {
+    code: String,
+    doc: String,
+}
+
+impl ExitStatus {
+    pub fn new(code: String, doc: String) -> Self {
+        Self { code, doc }
+    }
+}
+
+/// A command line shown in the EXAMPLES section, with what it does.
+#[derive(Default, Debug, Clone)]
+pub struct Example {
+    command: String,
+    doc: Option<String>,
+}
+
+impl Example {
+    pub fn new(command: String) -> Self {
+        Self {
+            command,
+            ..Self::default()
+        }
+    }
+
+    pub fn doc(&mut self, val: String) -> &mut Self {
+        self.doc = Some(val);
+        self
+    }
+}
+
+/// A value an argument accepts, with what it means if that is documented.
+#[derive(Default, Debug, Clone)]
+pub struct PossibleValue {
+    name: String,
+    doc: Option<String>,
+}
+
+impl PossibleValue {
+    pub fn new(name: String, doc: Option<String>) -> Self {
+        Self { name, doc }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// A titled part of the description, rendered as an mdoc `.Ss` subsection.
+#[derive(Default, Debug, Clone)]
+pub struct Subsection {
+    title: String,
+    doc: String,
+}
+
+impl Subsection {
+    pub fn new(title: String, doc: String) -> Self {
+        Self { title, doc }
+    }
+}
+
+/// A clap `ArgGroup`, whose arguments exclude each other unless it allows `multiple`.
+#[derive(Default, Debug, Clone)]
+pub struct ArgGroup {
+    name: String,
+    /// The clap names of the arguments in the group, besides those naming it as their `group`.
+    args: Vec<String>,
+    required: bool,
+    multiple: bool,
+}
+
+impl ArgGroup {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            ..Self::default()
+        }
+    }
+
+    pub fn push_arg(&mut self, val: String) -> &mut Self {
+        if !self.args.contains(&val) {
+            self.args.push(val);
+        }
+        self
+    }
+
+    pub fn required(&mut self, val: bool) -> &mut Self {
+        self.required = val;
+        self
+    }
+
+    pub fn multiple(&mut self, val: bool) -> &mut Self {
+        self.multiple = val;
+        self
+    }
+
+    /// Whether `flag` is in the group.
+    fn contains(&self, flag: &Flag) -> bool {
+        flag.group.as_ref() == Some(&self.name)
+            || flag.id.as_ref().map_or(false, |id| self.args.contains(id))
+    }
+
+    /// The flags among `flags` in the group.
+    fn members<'a>(&self, flags: &'a [Flag]) -> Vec<&'a Flag> {
+        flags.iter().filter(|flag| self.contains(flag)).collect()
+    }
+}
+
+/// A related page, listed in the SEE ALSO section.
+#[derive(Default, Debug, Clone)]
+pub struct SeeAlso {
+    name: String,
+    section: String,
+}
+
+impl SeeAlso {
+    pub fn new(name: String, section: String) -> Self {
+        Self { name, section }
+    }
+}
+
+/// The sources settings are read from besides the command line, in order of precedence.
+#[derive(Default, Debug, Clone)]
+pub struct Settings {
+    pub environment: bool,
+    pub config_file: Option<String>,
+}
+
+/// The syntax front matter is written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrontMatterStyle {
+    /// Between `---` lines, as Hugo and Jekyll read it.
+    Yaml,
+    /// Between `+++` lines, as Zola and Hugo read it.
+    Toml,
+}
+
+impl Default for FrontMatterStyle {
+    fn default() -> Self {
+        FrontMatterStyle::Yaml
+    }
+}
+
+/// Metadata prepended to Markdown output for static site generators.
+#[derive(Default, Debug, Clone)]
+pub struct FrontMatter {
+    pub style: FrontMatterStyle,
+    /// Defaults to the page name.
+    pub title: Option<String>,
+    pub date: Option<String>,
+    pub section: Option<String>,
+}
+
+/// How placeholders for argument values are spelled when no `value_name` is given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueNames {
+    /// `--output OUTPUT`, as clap spells them since version 3.
+    Uppercase,
+    /// `--output output`, the argument's name as clap 2 shows it in `--help`.
+    Verbatim,
+}
+
+impl Default for ValueNames {
+    fn default() -> Self {
+        ValueNames::Uppercase
+    }
+}
+
+/// The order options are listed in, in the OPTIONS list and the synopsis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionOrder {
+    /// The order the fields are declared in.
+    Declaration,
+    /// Alphabetical by the short name, or the long one if there is none, ignoring case and
+    /// listing `-a` before `-A`, as some style guides require.
+    Alphabetical,
+}
+
+impl Default for OptionOrder {
+    fn default() -> Self {
+        OptionOrder::Declaration
+    }
+}
+
+/// How an mdoc list is laid out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListStyle {
+    /// The `-offset` of the list, such as `indent` or `Ds`, or none to keep it flush.
+    pub offset: Option<String>,
+    /// Whether items are not separated by blank lines.
+    pub compact: bool,
+}
+
+impl ListStyle {
+    /// The layout of the OPTIONS list unless the page sets its own.
+    pub fn options() -> Self {
+        ListStyle {
+            offset: Some("indent".to_string()),
+            compact: false,
+        }
+    }
+
+    /// The layout of the subcommand list unless the page sets its own.
+    pub fn subcommands() -> Self {
+        ListStyle {
+            offset: Some("indent".to_string()),
+            compact: true,
+        }
+    }
+}
+
+/// A part of a page, which is written to a file of its own or along with the others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Part {
+    Header,
+    Body,
+    Footer,
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct Manpage {
+    pub name: String,
+    pub description: Option<String>,
+    pub long_description: Option<String>,
+    pub author: Option<String>,
+    /// The version of the program, from structopt's `version` unless `#[manpage(version)]`
+    /// overrides it.
+    pub version: Option<String>,
+    pub path: Option<PathBuf>,
+    pub header_path: Option<PathBuf>,
+    pub footer_path: Option<PathBuf>,
+    pub cheatsheet_path: Option<PathBuf>,
+    pub json_path: Option<PathBuf>,
+    pub yaml_path: Option<PathBuf>,
+    pub flags: Vec<Flag>,
+    pub subcommands: Vec<Subcommand>,
+    pub keybindings: Vec<Keybinding>,
+    pub examples: Vec<Example>,
+    pub exit_statuses: Vec<ExitStatus>,
+    /// Messages the program prints, with the conditions they report, listed in the DIAGNOSTICS
+    /// section.
+    pub diagnostics: Vec<Definition>,
+    /// Environment variables the program reads besides those of its options, listed in the
+    /// ENVIRONMENT section after them.
+    pub env_vars: Vec<Definition>,
+    /// Files the program reads or writes besides the defaults of its options, such as caches and
+    /// sockets, listed in the FILES section after them.
+    pub files: Vec<Definition>,
+    /// The standards the program conforms to, for the STANDARDS section, in paragraphs separated
+    /// by blank lines.
+    pub standards: Option<String>,
+    /// When the program first appeared and how it changed since, for the HISTORY section, in
+    /// paragraphs separated by blank lines.
+    pub history: Option<String>,
+    /// Where to report bugs, for the BUGS section: a web address, or an email address optionally
+    /// prefixed with `mailto:`.
+    pub bugs: Option<String>,
+    /// Known limitations of the program, for the CAVEATS section, in paragraphs separated by blank
+    /// lines.
+    pub caveats: Option<String>,
+    /// The copyright or license notice closing the page, in paragraphs separated by blank lines.
+    pub copyright: Option<String>,
+    /// Whether the EXIT STATUS section says the program exits 0 on success and >0 on errors, as
+    /// mdoc's `.Ex -std` does, before any exit statuses declared.
+    pub std_exit_status: bool,
+    /// The argument groups, those that flags name in their `group` and those defined by
+    /// `group = ArgGroup::with_name(...)` attributes.
+    pub groups: Vec<ArgGroup>,
+    pub definitions: Vec<Definition>,
+    pub see_also: Vec<SeeAlso>,
+    /// Subsections closing the body, or making up the DESCRIPTION section in formats whose body
+    /// has headings of its own.
+    pub subsections: Vec<Subsection>,
+    /// Whether this page's flag or subcommand is listed in its parent's cheat sheet.
+    pub featured: bool,
+    /// Whether this page's flag also has a `--no-` form.
+    pub negatable: bool,
+    /// Whether the long description, or the doc of this page's flag, is set as written, as with
+    /// `verbatim_doc_comment`.
+    pub verbatim: bool,
+    /// The documented values of this page's flag.
+    pub values: Vec<PossibleValue>,
+    pub settings: Option<Settings>,
+    pub format: Format,
+    /// The width of the tag column of the mdoc OPTIONS list, instead of the longest flag.
+    pub tag_width: Option<String>,
+    /// Defaults to [`ListStyle::options`].
+    pub options_list: Option<ListStyle>,
+    /// Defaults to [`ListStyle::subcommands`].
+    pub subcommands_list: Option<ListStyle>,
+    pub value_names: ValueNames,
+    pub sort_options: OptionOrder,
+    /// The date of the page, such as `May 1, 2022`. Without one mdoc pages carry the
+    /// `$Mdocdate$` keyword for version control to expand.
+    pub date: Option<String>,
+    /// What the page documents a part of, such as `MyProject 2.1`. Without it the program and
+    /// its version are named, or, when the version is unknown, mdoc pages name the system they
+    /// are displayed on.
+    pub os: Option<String>,
+    /// The manual section, such as `8` for daemons or `5` for file formats. Defaults to `1`.
+    pub section: Option<String>,
+    /// On a field, the section of the page its flag also has an entry in, such as `FILES`.
+    pub under: Option<String>,
+    pub front_matter: Option<FrontMatter>,
+    /// Other names the program is commonly invoked as, such as wrappers and symlinks.
+    pub invocations: Vec<String>,
+    /// Pass all user text through [`roff::harden_text`] and [`roff::harden_arg`].
+    pub hardened: bool,
+    /// Lay out prose one sentence per line so regenerated pages diff minimally. roff output is
+    /// always laid out this way.
+    pub stable: bool,
+    /// Spell every hyphen in roff output as a minus sign, `\-`, as Debian's lintian expects.
+    pub escape_hyphens: bool,
+    /// Leave non-ASCII characters in roff output as they are, for toolchains that read UTF-8,
+    /// instead of spelling them as glyph escapes such as `\(em`.
+    pub utf8: bool,
+    /// List subcommands in a COMMANDS section of cross references to their own pages, named
+    /// `name-subcommand`, instead of documenting their flags here. Only roff formats do this, and
+    /// only subcommands whose flags are known have a page.
+    pub subcommand_pages: bool,
+    /// On the page of a subcommand, the program it is a subcommand of, which the synopsis starts
+    /// with.
+    pub parent: Option<String>,
+    /// Whether commands other than the subcommands are accepted, as with
+    /// `external_subcommand`. Only roff formats mention this.
+    pub external_subcommands: bool,
+    /// Whether the program also runs without a subcommand, as with an `Option` subcommand field.
+    /// Only mdoc synopses show this.
+    pub optional_subcommand: bool,
+    /// Whether the last operand takes every argument from its first value on, even those
+    /// starting with a hyphen, as with `AppSettings::TrailingVarArg`.
+    pub trailing_var_arg: bool,
+    /// Write rendered pages gzipped, to a path with `.gz` appended. Needs the `gzip` feature,
+    /// without which the derive refuses `#[manpage(compress)]`.
+    pub compress: bool,
+    /// What generated the page, emitted as a leading comment by the part of the page that comes
+    /// first in what is written.
+    pub provenance: Option<String>,
+    /// Kept in declaration order, with an owner's later flag replacing its earlier one, so that
+    /// the same input always produces the same model.
+    short_flags: Vec<(Option<String>, String)>,
+    long_flags: Vec<(Option<String>, String)>,
+}
+
+/// The sections every page writes itself, which flags can't have entries in. ENVIRONMENT and
+/// FILES are not among them: the entries of flags come before the program's own there.
+pub const OWN_SECTIONS: [&str; 17] = [
+    "NAME",
+    "SYNOPSIS",
+    "DESCRIPTION",
+    "OPTIONS",
+    "COMMANDS",
+    "DEFINITIONS",
+    "EXIT STATUS",
+    "KEY BINDINGS",
+    "EXAMPLES",
+    "DIAGNOSTICS",
+    "SEE ALSO",
+    "STANDARDS",
+    "HISTORY",
+    "AUTHORS",
+    "CAVEATS",
+    "BUGS",
+    "COPYRIGHT",
+];
+
+impl Manpage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn name(&mut self, val: String) -> &mut Self {
+        self.name = val;
+        self
+    }
+
+    pub fn path(&mut self, val: PathBuf) -> &mut Self {
+        self.path = Some(val);
+        self
+    }
+
+    pub fn header_path(&mut self, val: PathBuf) -> &mut Self {
+        self.header_path = Some(val);
+        self
+    }
+
+    pub fn footer_path(&mut self, val: PathBuf) -> &mut Self {
+        self.footer_path = Some(val);
+        self
+    }
+
+    pub fn cheatsheet_path(&mut self, val: PathBuf) -> &mut Self {
+        self.cheatsheet_path = Some(val);
+        self
+    }
+
+    pub fn json_path(&mut self, val: PathBuf) -> &mut Self {
+        self.json_path = Some(val);
+        self
+    }
+
+    pub fn yaml_path(&mut self, val: PathBuf) -> &mut Self {
+        self.yaml_path = Some(val);
+        self
+    }
+
+    pub fn featured(&mut self, val: bool) -> &mut Self {
+        self.featured = val;
+        self
+    }
+
+    pub fn negatable(&mut self, val: bool) -> &mut Self {
+        self.negatable = val;
+        self
+    }
+
+    pub fn verbatim(&mut self, val: bool) -> &mut Self {
+        self.verbatim = val;
+        self
+    }
+
+    pub fn format(&mut self, val: Format) -> &mut Self {
+        self.format = val;
+        self
+    }
+
+    pub fn tag_width(&mut self, val: Option<String>) -> &mut Self {
+        self.tag_width = val;
+        self
+    }
+
+    pub fn options_list(&mut self, val: ListStyle) -> &mut Self {
+        self.options_list = Some(val);
+        self
+    }
+
+    pub fn subcommands_list(&mut self, val: ListStyle) -> &mut Self {
+        self.subcommands_list = Some(val);
+        self
+    }
+
+    pub fn os(&mut self, val: Option<String>) -> &mut Self {
+        self.os = val;
+        self
+    }
+
+    pub fn section(&mut self, val: Option<String>) -> &mut Self {
+        self.section = val;
+        self
+    }
+
+    pub fn under(&mut self, val: Option<String>) -> &mut Self {
+        self.under = val;
+        self
+    }
+
+    pub fn date(&mut self, val: Option<String>) -> &mut Self {
+        self.date = val;
+        self
+    }
+
+    pub fn value_names(&mut self, val: ValueNames) -> &mut Self {
+        self.value_names = val;
+        self
+    }
+
+    pub fn sort_options(&mut self, val: OptionOrder) -> &mut Self {
+        self.sort_options = val;
+        self
+    }
+
+    pub fn hardened(&mut self, val: bool) -> &mut Self {
+        self.hardened = val;
+        self
+    }
+
+    pub fn stable(&mut self, val: bool) -> &mut Self {
+        self.stable = val;
+        self
+    }
+
+    pub fn escape_hyphens(&mut self, val: bool) -> &mut Self {
+        self.escape_hyphens = val;
+        self
+    }
+
+    pub fn utf8(&mut self, val: bool) -> &mut Self {
+        self.utf8 = val;
+        self
+    }
+
+    pub fn subcommand_pages(&mut self, val: bool) -> &mut Self {
+        self.subcommand_pages = val;
+        self
+    }
+
+    pub fn external_subcommands(&mut self, val: bool) -> &mut Self {
+        self.external_subcommands = val;
+        self
+    }
+
+    pub fn std_exit_status(&mut self, val: bool) -> &mut Self {
+        self.std_exit_status = val;
+        self
+    }
+
+    pub fn optional_subcommand(&mut self, val: bool) -> &mut Self {
+        self.optional_subcommand = val;
+        self
+    }
+
+    pub fn trailing_var_arg(&mut self, val: bool) -> &mut Self {
+        self.trailing_var_arg = val;
+        self
+    }
+
+    /// Add `flag` to the group `name`, defining it if no attribute did, and to the groups that
+    /// list it among their `args`. Only the groups without `multiple` make their flags exclude
+    /// each other.
+    pub fn join_groups(&mut self, flag: &mut Flag, name: Option<String>) {
+        if let Some(name) = name {
+            if !self.groups.iter().any(|group| group.name == name) {
+                self.groups.push(ArgGroup::new(name.clone()));
+            }
+            for group in self.groups.iter_mut().filter(|group| group.name == name) {
+                if let Some(id) = flag.id.clone() {
+                    group.push_arg(id);
+                }
+            }
+        }
+        for group in self.groups.iter().filter(|group| !group.multiple) {
+            if group.contains(flag) {
+                flag.group(group.name.clone());
+            }
+        }
+    }
+
+    pub fn compress(&mut self, val: bool) -> &mut Self {
+        self.compress = val;
+        self
+    }
+
+    pub fn provenance(&mut self, val: Option<String>) -> &mut Self {
+        self.provenance = val;
+        self
+    }
+
+    pub fn description(&mut self, val: Option<String>) -> &mut Self {
+        self.description = val;
+        self
+    }
+
+    pub fn author(&mut self, val: Option<String>) -> &mut Self {
+        self.author = val;
+        self
+    }
+
+    pub fn bugs(&mut self, val: Option<String>) -> &mut Self {
+        self.bugs = val;
+        self
+    }
+
+    pub fn history(&mut self, val: Option<String>) -> &mut Self {
+        self.history = val;
+        self
+    }
+
+    pub fn caveats(&mut self, val: Option<String>) -> &mut Self {
+        self.caveats = val;
+        self
+    }
+
+    pub fn copyright(&mut self, val: Option<String>) -> &mut Self {
+        self.copyright = val;
+        self
+    }
+
+    pub fn version(&mut self, val: Option<String>) -> &mut Self {
+        self.version = val;
+        self
+    }
+
+    pub fn long_description(&mut self, val: Option<String>) -> &mut Self {
+        self.long_description = val;
+        self
+    }
+
+    pub fn push_invocation(&mut self, val: String) -> &mut Self {
+        self.invocations.push(val);
+        self
+    }
+
+    pub fn push_short_flag(&mut self, owner: Option<String>, ident: String) -> &mut Self {
+        insert_ordered(&mut self.short_flags, owner, ident);
+        self
+    }
+
+    pub fn push_long_flag(&mut self, owner: Option<String>, ident: String) -> &mut Self {
+        insert_ordered(&mut self.long_flags, owner, ident);
+        self
+    }
+
+    pub fn push_subcommand(&mut self, mut cmd: Self) {
+        cmd.path = None;
+        let name = std::mem::take(&mut cmd.name);
+        let description = cmd.description.take();
+        let long_description = cmd.long_description.take();
+        let flags = std::mem::take(&mut cmd.flags);
+
+        let mut val = Subcommand::new(name);
+        if let Some(v) = description {
+            val.doc(v);
+        }
+        if let Some(v) = long_description {
+            val.doc(v);
+        }
+        val.flags(flags).featured(cmd.featured);
+        self.subcommands.push(val);
+    }
+
+    /// The long description, set as prose at the start of the description, unless it only repeats
+    /// the one-line description in the NAME section.
+    fn description_text(&self) -> Option<&str> {
+        let long = self.long_description.as_deref()?.trim();
+        if long.is_empty() || long.trim_end_matches('.') == self.name_description() {
+            return None;
+        }
+        Some(long)
+    }
+
+    /// The one-line description in the NAME section, from which `whatis` and `apropos` index the
+    /// page: the first line of the description, or of the long description if there is none, with
+    /// no closing full stop.
+    fn name_description(&self) -> &str {
+        let description = summary(&self.description);
+        if description.is_empty() {
+            summary(&self.long_description)
+        } else {
+            description
+        }
+    }
+
+    /// Whether `part` comes first in what is written of the page, and so carries the provenance
+    /// comment: the header, or, when it has no path while the body or the footer has one, the
+    /// first of those.
+    fn leads(&self, part: Part) -> bool {
+        let first = if self.header_path.is_none() && self.path.is_some() {
+            Part::Body
+        } else if self.header_path.is_none() && self.footer_path.is_some() {
+            Part::Footer
+        } else {
+            Part::Header
+        };
+        part == first
+    }
+
+    /// Whether the NAME section has a description to show.
+    pub fn has_description(&self) -> bool {
+        !self.name_description().is_empty()
+    }
+
+    /// The manual section the page belongs in.
+    fn manual_section(&self) -> &str {
+        self.section.as_deref().unwrap_or("1")
+    }
+
+    /// What the page documents a part of: the `os` set, or the program at its version.
+    fn source(&self) -> Option<String> {
+        self.os.clone().or_else(|| {
+            self.version
+                .as_ref()
+                .map(|version| format!("{} {}", self.name, version))
+        })
+    }
+
+    /// The name of the page of a subcommand, when subcommands have their own pages.
+    fn subcommand_page(&self, cmd: &Subcommand) -> String {
+        format!("{}-{}", self.name, cmd.name)
+    }
+
+    /// The pages of the subcommands, when they have their own: each documents the flags of its
+    /// subcommand and the global flags, and refers back to this page.
+    fn own_pages(&self) -> Vec<Manpage> {
+        if !self.subcommand_pages || !self.format.is_roff() {
+            return vec![];
+        }
+        self.subcommands
+            .iter()
+            .filter(|cmd| cmd.own_page)
+            .map(|cmd| Manpage {
+                name: self.subcommand_page(cmd),
+                parent: Some(self.name.clone()),
+                long_description: cmd.doc.clone(),
+                author: self.author.clone(),
+                version: self.version.clone(),
+                flags: cmd.flags.iter().chain(self.globals()).cloned().collect(),
+                see_also: vec![SeeAlso::new(
+                    self.name.clone(),
+                    self.manual_section().to_string(),
+                )],
+                format: self.format,
+                tag_width: self.tag_width.clone(),
+                options_list: self.options_list.clone(),
+                value_names: self.value_names,
+                sort_options: self.sort_options,
+                date: self.date.clone(),
+                os: self.os.clone(),
+                section: self.section.clone(),
+                hardened: self.hardened,
+                stable: self.stable,
+                escape_hyphens: self.escape_hyphens,
+                utf8: self.utf8,
+                compress: self.compress,
+                provenance: self.provenance.clone(),
+                ..Manpage::default()
+            })
+            .collect()
+    }
+
+    /// The program and the subcommand that the synopsis of a subcommand's page starts with.
+    fn command(&self) -> Option<(&str, &str)> {
+        let parent = self.parent.as_deref()?;
+        Some((parent, self.name.strip_prefix(parent)?.strip_prefix('-')?))
+    }
+
+    /// The related pages as `name(section)`, separated by commas, for formats without markup
+    /// for references.
+    #[cfg(any(
+        feature = "rst",
+        feature = "markdown",
+        feature = "texinfo",
+        feature = "text"
+    ))]
+    fn see_also_text(&self) -> String {
+        self.see_also
+            .iter()
+            .map(|SeeAlso { name, section }| format!("{}({})", name, section))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// The author, unless none is given.
+    fn authors(&self) -> Option<&str> {
+        self.author
+            .as_deref()
+            .map(str::trim)
+            .filter(|author| !author.is_empty())
+    }
+
+    /// `flags` in the order [`Manpage::sort_options`] lists options in, with the positional
+    /// arguments last in the order they are given in.
+    fn options<'a>(&self, flags: &'a [Flag]) -> Vec<&'a Flag> {
+        let (mut ret, positional): (Vec<&Flag>, Vec<&Flag>) =
+            flags.iter().partition(|flag| flag.positional.is_none());
+        if self.sort_options == OptionOrder::Alphabetical {
+            ret.sort_by_cached_key(|flag| {
+                let name = flag
+                    .short
+                    .as_deref()
+                    .or(flag.long.as_deref())
+                    .unwrap_or_default();
+                (
+                    name.to_lowercase(),
+                    name.starts_with(|c: char| c.is_uppercase()),
+                )
+            });
+        }
+        ret.extend(positional);
+        ret
+    }
+
+    /// `flags` in the order [`Manpage::options`] lists them, split into those without a
+    /// `help_heading` and those under each heading, in the order the headings first come in.
+    fn headed_options<'a>(
+        &self,
+        flags: &'a [Flag],
+    ) -> (Vec<&'a Flag>, Vec<(&'a str, Vec<&'a Flag>)>) {
+        let mut plain = vec![];
+        let mut headed: Vec<(&str, Vec<&Flag>)> = vec![];
+        for flag in self.options(flags) {
+            let heading = match flag.heading.as_deref() {
+                Some(heading) => heading,
+                None => {
+                    plain.push(flag);
+                    continue;
+                }
+            };
+            match headed.iter_mut().find(|(other, _)| *other == heading) {
+                Some((_, flags)) => flags.push(flag),
+                None => headed.push((heading, vec![flag])),
+            }
+        }
+        (plain, headed)
+    }
+
+    /// The named flags, each joining the first alternation listed before it whose flags it all
+    /// excludes, so that every flag of an alternation excludes every other one.
+    fn alternations(&self) -> Vec<Vec<&Flag>> {
+        let mut ret: Vec<Vec<&Flag>> = vec![];
+        for flag in self.options(&self.flags) {
+            if flag.positional.is_some() {
+                continue;
+            }
+            match ret
+                .iter_mut()
+                .find(|alternation| alternation.iter().all(|other| flag.excludes(other)))
+            {
+                Some(alternation) => alternation.push(flag),
+                None => ret.push(vec![flag]),
+            }
+        }
+        ret
+    }
+
+    /// Whether one of the flags of `alternation` must be given, as they all are required or they
+    /// all belong to the same required group.
+    fn required_alternation(&self, alternation: &[&Flag]) -> bool {
+        alternation.iter().all(|flag| flag.required)
+            || self
+                .groups
+                .iter()
+                .filter(|group| group.required && !group.multiple)
+                .any(|group| alternation.iter().all(|flag| group.contains(flag)))
+    }
+
+    /// The required groups of more than one flag, each with its flags.
+    fn required_groups(&self) -> Vec<(&ArgGroup, Vec<&Flag>)> {
+        self.groups
+            .iter()
+            .filter(|group| group.required)
+            .map(|group| (group, group.members(&self.flags)))
+            .filter(|(_, members)| members.len() > 1)
+            .collect()
+    }
+
+    /// How the `--` ending the options comes before the operand `flag`, if it does.
+    fn separator(&self, flag: &Flag) -> Option<Separator> {
+        if flag.last {
+            return Some(Separator::Required);
+        }
+        let trailing = self.trailing_var_arg
+            && flag.args.as_ref().map_or(false, |args| args.multiple)
+            && self
+                .operands()
+                .last()
+                .map_or(false, |(_, last)| std::ptr::eq(*last, flag));
+        if trailing {
+            Some(Separator::Optional)
+        } else {
+            None
+        }
+    }
+
+    /// The named flags subcommands take too, which their entries refer to.
+    fn globals(&self) -> Vec<&Flag> {
+        self.options(&self.flags)
+            .into_iter()
+            .filter(|flag| flag.global && (flag.long.is_some() || flag.short.is_some()))
+            .collect()
+    }
+
+    /// The positional arguments, with their value names, in the order they are given in.
+    fn operands(&self) -> Vec<(&str, &Flag)> {
+        self.flags
+            .iter()
+            .filter_map(|flag| Some((flag.positional.as_deref()?, flag)))
+            .collect()
+    }
+
+    /// The declared exit statuses as terms of a list, with the code as the term.
+    fn exit_codes(&self) -> Vec<Definition> {
+        self.exit_statuses
+            .iter()
+            .map(|ExitStatus { code, doc }| Definition::new(code.clone(), doc.clone()))
+            .collect()
+    }
+
+    /// Add the `-h, --help` and `-V, --version` flags clap adds to every app, as far as `help`
+    /// and `version` say it does. A flag declared with the same long name takes its place, and
+    /// one with the same short name leaves it with only its long one, as in clap.
+    pub fn push_builtin_flags(&mut self, help: bool, version: bool) {
+        let builtins = [
+            (help, "help", "h", "Print help information."),
+            (version, "version", "V", "Print version information."),
+        ];
+        for (enabled, long, short, doc) in builtins {
+            if !enabled || self.flags.iter().any(|f| f.long.as_deref() == Some(long)) {
+                continue;
+            }
+            let mut flag = Flag::new();
+            flag.long(long.to_string())
+                .id(long.to_string())
+                .doc(doc.to_string());
+            if !self.flags.iter().any(|f| f.short.as_deref() == Some(short)) {
+                flag.short(short.to_string());
+            }
+            self.flags.push(flag);
+        }
+    }
+
+    /// The entries flags have in other sections, ENVIRONMENT and FILES first, followed by the
+    /// others in the order they are first named. A FILES entry is the flag's literal default,
+    /// when it has one; other entries are the flag itself. Every variable a flag reads has an
+    /// ENVIRONMENT entry, followed by the other variables of the program, and the other files of
+    /// the program follow the FILES entries of flags.
+    fn routed_sections(&self) -> Vec<(String, Vec<Definition>)> {
+        let mut ret: Vec<(String, Vec<Definition>)> = vec![];
+        let mut push = |title: String, entry: Definition| match ret
+            .iter_mut()
+            .find(|(other, _)| *other == title)
+        {
+            Some((_, entries)) => entries.push(entry),
+            None => ret.push((title, vec![entry])),
+        };
+        for flag in self.flags.iter() {
+            let doc = flag.doc.as_deref().map(doc_sentence).unwrap_or_default();
+            let section = flag.section.as_deref().map(str::to_uppercase);
+            // Every variable an option reads is listed under ENVIRONMENT, whether or not the
+            // option is routed there.
+            if let Some(env) = &flag.env {
+                let mut entry = Definition::new(env.clone(), doc.clone());
+                entry.overridden_by(flag.clone());
+                push("ENVIRONMENT".to_string(), entry);
+            }
+            let title = match section {
+                Some(title) if title == "ENVIRONMENT" && flag.env.is_some() => continue,
+                Some(title) => title,
+                None => continue,
+            };
+            let term = match title.as_str() {
+                "FILES" => flag.default_value.clone(),
+                _ => None,
+            };
+            push(
+                title,
+                Definition::new(term.unwrap_or_else(|| flag.display_name()), doc),
+            );
+        }
+        for var in self.env_vars.iter() {
+            push("ENVIRONMENT".to_string(), var.clone());
+        }
+        for file in self.files.iter() {
+            push("FILES".to_string(), file.clone());
+        }
+        const ORDER: [&str; 2] = ["ENVIRONMENT", "FILES"];
+        ret.sort_by_key(|(title, _)| {
+            ORDER
+                .iter()
+                .position(|other| other == title)
+                .unwrap_or(ORDER.len())
+        });
+        ret
+    }
+
+    /// The flags and subcommands listed in the cheat sheet: the featured ones, or all of them
+    /// if none is featured.
+    fn cheatsheet_items(&self) -> (Vec<&Flag>, Vec<&Subcommand>) {
+        let any_featured =
+            self.flags.iter().any(|f| f.featured) || self.subcommands.iter().any(|c| c.featured);
+        (
+            self.options(&self.flags)
+                .into_iter()
+                .filter(|flag| flag.featured || !any_featured)
+                .collect(),
+            self.subcommands
+                .iter()
+                .filter(|cmd| cmd.featured || !any_featured)
+                .collect(),
+        )
+    }
+
+    /// Render the page body: the synopsis, the options list and the subcommands list.
+    pub fn render(&self) -> String {
+        match self.format {
+            Format::Mdoc => mdoc::body(self),
+            #[cfg(feature = "man")]
+            Format::Man => man::body(self),
+            #[cfg(feature = "rst")]
+            Format::Rst => rst::body(self),
+            #[cfg(feature = "docbook")]
+            Format::Docbook => docbook::body(self),
+            #[cfg(feature = "texinfo")]
+            Format::Texinfo => texinfo::body(self),
+            #[cfg(feature = "text")]
+            Format::Text => text::body(self),
+            #[cfg(feature = "markdown")]
+            Format::Markdown => markdown::body(self),
+        }
+    }
+
+    /// Render the page prologue and the NAME section.
+    pub fn render_header(&self) -> String {
+        match self.format {
+            Format::Mdoc => mdoc::header(self),
+            #[cfg(feature = "man")]
+            Format::Man => man::header(self),
+            #[cfg(feature = "rst")]
+            Format::Rst => rst::header(self),
+            #[cfg(feature = "docbook")]
+            Format::Docbook => docbook::header(self),
+            #[cfg(feature = "texinfo")]
+            Format::Texinfo => texinfo::header(self),
+            #[cfg(feature = "text")]
+            Format::Text => text::header(self),
+            #[cfg(feature = "markdown")]
+            Format::Markdown => markdown::header(self),
+        }
+    }
+
+    /// Render the trailing sections of the page.
+    pub fn render_footer(&self) -> String {
+        match self.format {
+            Format::Mdoc => mdoc::footer(self),
+            #[cfg(feature = "man")]
+            Format::Man => man::footer(self),
+            #[cfg(feature = "rst")]
+            Format::Rst => rst::footer(self),
+            #[cfg(feature = "docbook")]
+            Format::Docbook => docbook::footer(self),
+            #[cfg(feature = "texinfo")]
+            Format::Texinfo => texinfo::footer(self),
+            #[cfg(feature = "text")]
+            Format::Text => text::footer(self),
+            #[cfg(feature = "markdown")]
+            Format::Markdown => markdown::footer(self),
+        }
+    }
+
+    /// Render the whole page, for pages written without a header and footer of their own, such
+    /// as those of subcommands.
+    pub fn render_page(&self) -> String {
+        let footer = self.render_footer();
+        // The header and footer end without a line break, and the footer may be empty.
+        if footer.is_empty() {
+            format!("{}\n{}", self.render_header(), self.render())
+        } else {
+            format!("{}\n{}{}\n", self.render_header(), self.render(), footer)
+        }
+    }
+
+    /// Dump the whole model as JSON, for tools that generate their own documentation.
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> String {
+        export::json(self)
+    }
+
+    /// Dump the whole model as YAML.
+    #[cfg(feature = "yaml")]
+    pub fn to_yaml(&self) -> String {
+        export::yaml(self)
+    }
+
+    /// Render a one-page quick reference of the featured flags and subcommands.
+    pub fn render_cheatsheet(&self) -> String {
+        match self.format {
+            Format::Mdoc => mdoc::cheatsheet(self),
+            #[cfg(feature = "man")]
+            Format::Man => man::cheatsheet(self),
+            #[cfg(feature = "rst")]
+            Format::Rst => rst::cheatsheet(self),
+            #[cfg(feature = "docbook")]
+            Format::Docbook => docbook::cheatsheet(self),
+            #[cfg(feature = "texinfo")]
+            Format::Texinfo => texinfo::cheatsheet(self),
+            #[cfg(feature = "text")]
+            Format::Text => text::cheatsheet(self),
+            #[cfg(feature = "markdown")]
+            Format::Markdown => markdown::cheatsheet(self),
+        }
+    }
+}
+
+/// Set the entry of `owner` to `ident`, in place if it has one and at the end otherwise.
+fn insert_ordered(
+    entries: &mut Vec<(Option<String>, String)>,
+    owner: Option<String>,
+    ident: String,
+) {
+    match entries.iter_mut().find(|(key, _)| *key == owner) {
+        Some((_, val)) => *val = ident,
+        None => entries.push((owner, ident)),
+    }
+}
+
+/// `val` as a JSON string, which is also a valid YAML or TOML string.
+#[cfg(any(feature = "json", feature = "yaml", feature = "markdown"))]
+fn json_string(val: &str) -> String {
+    let mut ret = String::with_capacity(val.len() + 2);
+    ret.push('"');
+    for c in val.chars() {
+        match c {
+            '"' => ret.push_str("\\\""),
+            '\\' => ret.push_str("\\\\"),
+            '\n' => ret.push_str("\\n"),
+            '\r' => ret.push_str("\\r"),
+            '\t' => ret.push_str("\\t"),
+            c if c.is_control() => ret.push_str(&format!("\\u{:04x}", c as u32)),
+            c => ret.push(c),
+        }
+    }
+    ret.push('"');
+    ret
+}
+
+/// `val` in brackets unless `flag` is required, the way synopses mark optional arguments.
+#[cfg(any(
+    feature = "man",
+    feature = "rst",
+    feature = "texinfo",
+    feature = "text",
+    feature = "markdown"
+))]
+fn bracketed(flag: &Flag, val: &str) -> String {
+    if flag.required {
+        val.to_string()
+    } else {
+        format!("[{}]", val)
+    }
+}
+
+/// `operand` in the synopsis, bracketed unless `flag` is required, after the `--` that comes
+/// before it, spelled as `separator`, if [`Manpage::separator`] says one does.
+#[cfg(any(
+    feature = "man",
+    feature = "rst",
+    feature = "texinfo",
+    feature = "text",
+    feature = "markdown"
+))]
+fn separated(page: &Manpage, flag: &Flag, separator: &str, operand: &str) -> String {
+    match page.separator(flag) {
+        Some(Separator::Required) => bracketed(flag, &format!("{} {}", separator, operand)),
+        Some(Separator::Optional) => format!("[{}] {}", separator, bracketed(flag, operand)),
+        None => bracketed(flag, operand),
+    }
+}
+
+/// A section title such as `FILES` as the formats with sentence-case headings spell it.
+#[cfg(any(feature = "rst", feature = "texinfo", feature = "markdown"))]
+fn sentence_case(title: &str) -> String {
+    let lower = title.to_lowercase();
+    let mut chars = lower.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// The sentence of the BUGS section, for the formats without markup for addresses.
+#[cfg(any(
+    feature = "man",
+    feature = "rst",
+    feature = "docbook",
+    feature = "texinfo",
+    feature = "text",
+    feature = "markdown"
+))]
+fn bugs_text(bugs: &str) -> String {
+    format!(
+        "Report bugs to {}.",
+        bugs.strip_prefix("mailto:").unwrap_or(bugs)
+    )
+}
+
+/// The sentence mdoc's `.Ex -std` renders to, for the formats without it.
+#[cfg(any(
+    feature = "man",
+    feature = "rst",
+    feature = "docbook",
+    feature = "texinfo",
+    feature = "text",
+    feature = "markdown"
+))]
+fn std_exit_status(page: &Manpage) -> String {
+    format!(
+        "The {} utility exits 0 on success, and >0 if an error occurs.",
+        page.name
+    )
+}
+
+/// The description of an example as the sentence introducing its command line, which ends in
+/// a colon.
+fn example_intro(doc: &str) -> String {
+    format!("{}:", doc.trim().trim_end_matches(&['.', ':'][..]))
+}
+
+/// A doc string as one finished sentence: surrounding whitespace goes and a full stop is added
+/// unless the text already ends in closing punctuation. The prose itself is never altered.
+fn doc_sentence(doc: &str) -> String {
+    let doc = doc.trim();
+    if doc.is_empty() || doc.ends_with(&['.', '!', '?', ':', '\u{2026}'][..]) {
+        doc.to_string()
+    } else {
+        format!("{}.", doc)
+    }
+}
+
+/// How the `--` that ends the options comes before an operand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Separator {
+    /// A `last` operand is only given after it.
+    Required,
+    /// The trailing operand of a `TrailingVarArg` program may be given after it, though it takes
+    /// arguments starting with a hyphen without it too.
+    Optional,
+}
+
+/// The note on a page with `external_subcommand`, after the subcommands it lists.
+const EXTERNAL_SUBCOMMANDS: &str =
+    "Any other command is passed on to an external program, with its arguments.";
+
+/// The documentation of `flag` as full sentences, noting when it may be repeated. Verbatim
+/// documentation is left to [`verbatim_doc`], leaving only the note.
+fn flag_doc(flag: &Flag) -> Option<String> {
+    let doc = flag
+        .doc
+        .as_deref()
+        .filter(|_| !flag.verbatim)
+        .map(doc_sentence);
+    if !flag.repeatable {
+        return doc;
+    }
+    Some(match doc {
+        Some(doc) if !doc.is_empty() => format!("{}\nMay be given multiple times.", doc),
+        _ => "May be given multiple times.".to_string(),
+    })
+}
+
+/// The documentation of `flag` as written, for a literal block, if it is verbatim.
+fn verbatim_doc(flag: &Flag) -> Option<&str> {
+    flag.doc.as_deref().filter(|_| flag.verbatim)
+}
+
+/// `val` as written, for a literal block, without the blank lines around it, trailing whitespace
+/// or control characters other than tabs.
+#[cfg(any(
+    feature = "rst",
+    feature = "docbook",
+    feature = "texinfo",
+    feature = "text",
+    feature = "markdown"
+))]
+fn verbatim_text(val: &str) -> String {
+    val.trim_matches('\n')
+        .trim_end()
+        .split('\n')
+        .map(|line| {
+            line.chars()
+                .filter(|c| !c.is_control() || *c == '\t')
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The first line of a doc string, for one-line listings, without a closing full stop.
+fn summary(doc: &Option<String>) -> &str {
+    let doc = doc.as_deref().unwrap_or_default();
+    let line = doc.lines().next().unwrap_or_default().trim();
+    match line.strip_suffix('.') {
+        Some(rest) if !rest.ends_with('.') => rest,
+        _ => line,
+    }
+}
+
+/// The paragraphs of a doc string, separated by blank lines.
+fn paragraphs(doc: &str) -> impl Iterator<Item = &str> {
+    doc.split("\n\n")
+        .map(str::trim)
+        .filter(|para| !para.is_empty())
+}
+
+/// The `(name, address)` pairs of an author string, where authors are separated by `:` as in
+/// `CARGO_PKG_AUTHORS` or by `,`, and each is `Name <address>`, `Name` or `<address>`.
+fn split_authors(authors: &str) -> Vec<(&str, Option<&str>)> {
+    let mut ret = vec![];
+    let mut start = 0;
+    let mut depth = 0usize;
+    for (idx, c) in authors.char_indices().chain(Some((authors.len(), ':'))) {
+        match c {
+            '<' => depth += 1,
+            '>' => depth = depth.saturating_sub(1),
+            ':' | ',' if depth == 0 => {
+                let author = authors[start..idx].trim();
+                start = idx + 1;
+                if author.is_empty() {
+                    continue;
+                }
+                ret.push(
+                    match author
+                        .strip_suffix('>')
+                        .and_then(|rest| rest.rsplit_once('<'))
+                    {
+                        Some((name, address)) => {
+                            let address = address.trim();
+                            (name.trim(), Some(address).filter(|a| !a.is_empty()))
+                        }
+                        None => (author, None),
+                    },
+                );
+            }
+            _ => {}
+        }
+    }
+    ret
+}
+
+impl std::fmt::Display for Manpage {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.write_str(&self.render())
+    }
+}
@@ -0,0 +1,129 @@
+// Copyright 2022 Manos Pitsidianakis <epilys@nessuent.net>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Writing rendered pages to disk.
+//!
+//! This is the only part of the manpage module that touches the filesystem.
+
+use super::{lint, roff, Format, Manpage};
+
+#[cfg(feature = "gzip")]
+use flate2::{write::GzEncoder, Compression};
+
+#[cfg(feature = "gzip")]
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+#[cfg(feature = "gzip")]
+use std::path::PathBuf;
+
+/// Write a rendering of the page, unless it is hardened roff that fails the check.
+///
+/// Mdoc is linted first, with what the linter finds added to `findings`. Compressed pages are
+/// written to `path` with a `.gz` extension appended.
+fn write_rendered(
+    page: &Manpage,
+    path: &Path,
+    contents: &str,
+    findings: &mut Vec<String>,
+) -> Result<(), String> {
+    if page.hardened && page.format.is_roff() {
+        roff::check_hardened(contents)
+            .map_err(|err| format!("refusing to write {}: {}", path.display(), err))?;
+    }
+    if page.format == Format::Mdoc {
+        for warning in lint::mdoc(contents) {
+            findings.push(format!("{}: {}", path.display(), warning));
+        }
+    }
+    #[cfg(feature = "gzip")]
+    if page.compress {
+        return write_to_file(&gz_path(path), &gzip(contents.as_bytes()));
+    }
+    write_to_file(path, contents.as_bytes())
+}
+
+#[cfg(feature = "gzip")]
+fn gz_path(path: &Path) -> PathBuf {
+    if path.extension() == Some("gz".as_ref()) {
+        return path.to_path_buf();
+    }
+    let mut ret = OsString::from(path);
+    ret.push(".gz");
+    ret.into()
+}
+
+/// gzip `contents` without a timestamp or file name, so that builds are reproducible.
+#[cfg(feature = "gzip")]
+fn gzip(contents: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(vec![], Compression::best());
+    encoder
+        .write_all(contents)
+        .and_then(|()| encoder.finish())
+        .expect("compressing to memory cannot fail")
+}
+
+fn write_to_file(path: &Path, contents: &[u8]) -> Result<(), String> {
+    File::create(path)
+        .map_err(|err| format!("couldn't create {}: {}", path.display(), err))?
+        .write_all(contents)
+        .map_err(|err| format!("couldn't write to {}: {}", path.display(), err))
+}
+
+/// Write every output the page has a path configured for.
+///
+/// A page whose path is a directory is written in it as `name.section`, as `man` looks it up. The
+/// pages of its subcommands, when they have their own, are written whole beside it as
+/// `name-subcommand.section`.
+/// What the linter finds wrong in the outputs is returned for the caller to report, and the
+/// first output that can't be written is an error.
+pub fn write(page: &Manpage) -> Result<Vec<String>, String> {
+    let mut findings = vec![];
+    if let Some(path) = page.path.as_ref() {
+        if path.is_dir() {
+            let path = path.join(format!("{}.{}", page.name, page.manual_section()));
+            write_rendered(page, &path, &page.render(), &mut findings)?;
+        } else {
+            write_rendered(page, path, &page.render(), &mut findings)?;
+        }
+        for own in page.own_pages() {
+            let file = format!("{}.{}", own.name, own.manual_section());
+            let own_path = if path.is_dir() {
+                path.join(file)
+            } else {
+                path.with_file_name(file)
+            };
+            write_rendered(&own, &own_path, &own.render_page(), &mut findings)?;
+        }
+    }
+
+    if let Some(path) = page.header_path.as_ref() {
+        write_rendered(page, path, &page.render_header(), &mut findings)?;
+    }
+
+    if let Some(path) = page.footer_path.as_ref() {
+        write_rendered(page, path, &page.render_footer(), &mut findings)?;
+    }
+
+    if let Some(path) = page.cheatsheet_path.as_ref() {
+        write_rendered(page, path, &page.render_cheatsheet(), &mut findings)?;
+    }
+
+    #[cfg(feature = "json")]
+    if let Some(path) = page.json_path.as_ref() {
+        write_to_file(path, page.to_json().as_bytes())?;
+    }
+
+    #[cfg(feature = "yaml")]
+    if let Some(path) = page.yaml_path.as_ref() {
+        write_to_file(path, page.to_yaml().as_bytes())?;
+    }
+
+    Ok(findings)
+}
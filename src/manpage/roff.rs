@@ -0,0 +1,455 @@
+// Copyright 2022 Manos Pitsidianakis <epilys@nessuent.net>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! roff text handling shared by the roff renderers.
+
+use super::{Format, Manpage, Part};
+
+/// The column source lines of prose are wrapped at.
+const WIDTH: usize = 78;
+
+/// User text emitted on text lines, one sentence per line and wrapped at [`WIDTH`] columns.
+///
+/// Paragraphs are separated by a macro rather than a blank line, which mdoc does not read as a
+/// paragraph break and hardening drops: `.Pp` in mdoc, and in man `.IP`, which keeps the indent
+/// of the list item the text is usually in.
+pub(super) fn text(page: &Manpage, val: &str) -> String {
+    let separator = if page.format == Format::Mdoc {
+        "\n.Pp\n"
+    } else {
+        "\n.IP\n"
+    };
+    sentence_per_line(val)
+        .split("\n\n")
+        .map(|para| {
+            let para = wrap(para, WIDTH);
+            let para = if page.hardened {
+                harden_text(&para)
+            } else {
+                escape_text(&para)
+            };
+            glyphs(page, hyphens(page, para))
+        })
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// User text emitted as the lines of a literal block, as written. Blank lines are kept as empty
+/// text lines, which roff does not read as paragraph breaks.
+pub(super) fn literal(page: &Manpage, val: &str) -> String {
+    let val = val
+        .trim_matches('\n')
+        .trim_end()
+        .split('\n')
+        .map(|line| {
+            let line = escape_text(&strip_controls(line));
+            if line.trim().is_empty() {
+                "\\&".to_string()
+            } else {
+                line
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    glyphs(page, hyphens(page, val))
+}
+
+/// `val` with its non-ASCII characters spelled as glyph escapes, unless the page is for
+/// toolchains that read UTF-8.
+///
+/// This must only be given text that has already been escaped, with its hyphens spelled as
+/// [`hyphens`] spells them, since glyph names such as `->` contain hyphens of their own.
+pub(super) fn glyphs(page: &Manpage, val: String) -> String {
+    if page.utf8 || val.is_ascii() {
+        return val;
+    }
+    let mut ret = String::with_capacity(val.len());
+    for c in val.chars() {
+        match glyph(c) {
+            Some(name) => ret.push_str(&format!("\\({}", name)),
+            None if c.is_ascii() => ret.push(c),
+            None => ret.push_str(&format!("\\[u{:04X}]", c as u32)),
+        }
+    }
+    ret
+}
+
+/// The glyphs groff and mandoc both know by name, with the characters they stand for. Other
+/// non-ASCII characters are spelled by their code point.
+const GLYPHS: &[(char, &str)] = &[
+    ('\u{2010}', "hy"),
+    ('\u{2013}', "en"),
+    ('\u{2014}', "em"),
+    ('\u{2018}', "oq"),
+    ('\u{2019}', "cq"),
+    ('\u{201c}', "lq"),
+    ('\u{201d}', "rq"),
+    ('\u{2022}', "bu"),
+    ('\u{2020}', "dg"),
+    ('\u{2190}', "<-"),
+    ('\u{2192}', "->"),
+    ('\u{2194}', "<>"),
+    ('\u{21d0}', "lA"),
+    ('\u{21d2}', "rA"),
+    ('\u{2260}', "!="),
+    ('\u{2264}', "<="),
+    ('\u{2265}', ">="),
+    ('\u{2122}', "tm"),
+    ('\u{a7}', "sc"),
+    ('\u{a9}', "co"),
+    ('\u{ab}', "Fo"),
+    ('\u{ae}', "rg"),
+    ('\u{b0}', "de"),
+    ('\u{b1}', "+-"),
+    ('\u{bb}', "Fc"),
+    ('\u{d7}', "mu"),
+    ('\u{f7}', "di"),
+];
+
+fn glyph(c: char) -> Option<&'static str> {
+    GLYPHS
+        .iter()
+        .find(|(glyph, _)| *glyph == c)
+        .map(|(_, name)| *name)
+}
+
+/// `val` with the glyph escapes [`glyphs`] writes turned back into the characters they stand
+/// for.
+pub(super) fn unglyph(val: &str) -> String {
+    let mut ret = String::with_capacity(val.len());
+    let mut rest = val;
+    while let Some(idx) = rest.find('\\') {
+        ret.push_str(&rest[..idx]);
+        rest = &rest[idx..];
+        let named = rest
+            .get(2..4)
+            .filter(|_| rest.starts_with("\\("))
+            .and_then(|name| GLYPHS.iter().find(|(_, glyph)| *glyph == name))
+            .map(|(c, _)| (*c, 4));
+        let code_point = rest
+            .strip_prefix("\\[u")
+            .and_then(|hex| Some((hex, hex.find(']')?)))
+            .and_then(|(hex, end)| {
+                Some((
+                    char::from_u32(u32::from_str_radix(&hex[..end], 16).ok()?)?,
+                    end + 4,
+                ))
+            });
+        match named.or(code_point) {
+            Some((c, len)) => {
+                ret.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                ret.push('\\');
+                rest = &rest[1..];
+            }
+        }
+    }
+    ret.push_str(rest);
+    ret
+}
+
+/// `val` with its hyphens spelled as minus signs if the page asks for it.
+///
+/// This must only be given text that has already been escaped.
+pub(super) fn hyphens(page: &Manpage, val: String) -> String {
+    if page.escape_hyphens {
+        val.replace('-', "\\-")
+    } else {
+        val
+    }
+}
+
+/// A leading comment saying where the file comes from, if `part` comes first in it.
+pub(super) fn provenance(page: &Manpage, part: Part) -> String {
+    match page.provenance.as_ref().filter(|_| page.leads(part)) {
+        Some(val) => format!(".\\\" Generated by {}\n", val.replace('\n', " ")),
+        None => String::new(),
+    }
+}
+
+/// Escape the characters roff gives a meaning to in `text` emitted as text lines.
+///
+/// Backslashes are printed literally and lines that would otherwise be read as requests or
+/// macros are protected with `\&`. The roff renderers apply this to all user text.
+pub fn escape_text(text: &str) -> String {
+    text.split('\n')
+        .map(|line| {
+            let line = escape_backslashes(line);
+            if line.starts_with('.') || line.starts_with('\'') {
+                format!("\\&{}", line)
+            } else {
+                line
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Escape the characters roff and mdoc give a meaning to in `text` emitted as macro arguments.
+///
+/// In addition to what [`escape_text`] does, line breaks are folded into spaces, double quotes
+/// are replaced by a glyph and words mdoc would parse as macros or delimiters are protected with
+/// `\&`.
+pub fn escape_arg(text: &str) -> String {
+    protect_words(text, is_callable_macro)
+}
+
+/// `text` folded onto one line with its backslashes and double quotes escaped, and every word
+/// `is_macro` accepts protected with `\&` along with delimiters.
+fn protect_words(text: &str, is_macro: fn(&str) -> bool) -> String {
+    let text = escape_backslashes(&text.replace('\n', " "));
+    text.split_whitespace()
+        .map(|word| {
+            let word = word.replace('"', "\\(dq");
+            if is_macro(&word) || is_delimiter(&word) || word.starts_with('\'') {
+                format!("\\&{}", word)
+            } else {
+                word
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Make `text` safe to emit as one or more roff text lines, whatever it contains.
+///
+/// On top of [`escape_text`], control characters are dropped and blank lines are removed.
+pub fn harden_text(text: &str) -> String {
+    text.split('\n')
+        .map(strip_controls)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| escape_text(line.trim()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Make `text` safe to emit as arguments of a macro line, whatever it contains.
+///
+/// On top of [`escape_arg`], control characters are dropped and every word of the shape of a
+/// macro name is protected, in case a formatter knows more macros than mdoc(7) documents.
+pub fn harden_arg(text: &str) -> String {
+    protect_words(&strip_controls(text), is_macro_like)
+}
+
+/// Reflow `text` so that every sentence starts on a line of its own.
+///
+/// Line breaks inside a paragraph are not kept, so the output only changes where the sentences
+/// themselves change. Paragraphs stay separated by a blank line.
+pub fn sentence_per_line(text: &str) -> String {
+    let mut paragraphs = vec![];
+    let mut words: Vec<&str> = vec![];
+    for line in text.split('\n').chain(std::iter::once("")) {
+        if !line.trim().is_empty() {
+            words.extend(line.split_whitespace());
+            continue;
+        }
+        if words.is_empty() {
+            continue;
+        }
+        let mut paragraph = String::new();
+        for (i, word) in words.iter().enumerate() {
+            if i > 0 {
+                let starts_sentence = word.starts_with(|c: char| c.is_uppercase());
+                let prev = words[i - 1].trim_end_matches(&[')', '"', '\''][..]);
+                if starts_sentence && prev.ends_with(&['.', '?', '!'][..]) {
+                    paragraph.push('\n');
+                } else {
+                    paragraph.push(' ');
+                }
+            }
+            paragraph.push_str(word);
+        }
+        paragraphs.push(paragraph);
+        words.clear();
+    }
+    paragraphs.join("\n\n")
+}
+
+/// Break every line of `text` longer than `width` at the spaces before it overflows.
+///
+/// Words longer than `width` are kept whole on a line of their own.
+pub fn wrap(text: &str, width: usize) -> String {
+    let mut ret = String::with_capacity(text.len());
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            ret.push('\n');
+        }
+        let mut column = 0;
+        for word in line.split_whitespace() {
+            let len = word.chars().count();
+            if column > 0 && column + 1 + len > width {
+                ret.push('\n');
+                column = 0;
+            } else if column > 0 {
+                ret.push(' ');
+                column += 1;
+            }
+            ret.push_str(word);
+            column += len;
+        }
+    }
+    ret
+}
+
+/// Check that `output` only contains constructs [`harden_text`] and [`harden_arg`] allow
+/// through, together with the macros and font escapes the renderers emit themselves.
+pub fn check_hardened(output: &str) -> Result<(), String> {
+    for (idx, line) in output.lines().enumerate() {
+        let err = |msg: &str| Err(format!("line {}: {}: {:?}", idx + 1, msg, line));
+        if line.starts_with('\'') {
+            return err("control line");
+        }
+        if line.chars().any(|c| c.is_control() && c != '\t') {
+            return err("control character");
+        }
+        if line == "." || line.starts_with(".\\\"") {
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix('.') {
+            let name = rest.split(' ').next().unwrap_or_default();
+            // `.br` is the one roff request the renderers emit.
+            if !is_macro_like(name) && name != "br" {
+                return err("unknown macro");
+            }
+            if line.matches('"').count() % 2 != 0 {
+                return err("unbalanced quotes");
+            }
+        }
+        let mut chars = line.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                continue;
+            }
+            match chars.next() {
+                Some('e') | Some('&') | Some('-') | Some(' ') => {}
+                Some('f') => {
+                    if !matches!(chars.next(), Some('B') | Some('I') | Some('R') | Some('P')) {
+                        return err("unknown font escape");
+                    }
+                }
+                Some('(') => {
+                    if chars.next().is_none() || chars.next().is_none() {
+                        return err("truncated glyph escape");
+                    }
+                }
+                Some('[') => {
+                    if !chars.any(|c| c == ']') {
+                        return err("unterminated glyph escape");
+                    }
+                }
+                _ => return err("unsafe escape sequence"),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn strip_controls(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_control() || *c == '\t')
+        .collect()
+}
+
+fn escape_backslashes(text: &str) -> String {
+    text.replace('\\', "\\e")
+}
+
+/// Words of the shape of an mdoc macro name, such as `Fl`, `Xr` or `Bsx`.
+fn is_macro_like(word: &str) -> bool {
+    let mut chars = word.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_uppercase())
+        && (2..=3).contains(&word.len())
+        && chars.all(|c| c.is_ascii_alphabetic())
+}
+
+/// The mdoc macros that are parsed when they appear as arguments of another macro.
+pub fn is_callable_macro(word: &str) -> bool {
+    matches!(
+        word,
+        "Ac" | "Ad"
+            | "An"
+            | "Ao"
+            | "Ap"
+            | "Aq"
+            | "Ar"
+            | "At"
+            | "Bc"
+            | "Bo"
+            | "Bq"
+            | "Brc"
+            | "Bro"
+            | "Brq"
+            | "Bsx"
+            | "Bx"
+            | "Cm"
+            | "Dc"
+            | "Do"
+            | "Dq"
+            | "Dv"
+            | "Dx"
+            | "Ec"
+            | "Em"
+            | "En"
+            | "Eo"
+            | "Er"
+            | "Es"
+            | "Ev"
+            | "Fa"
+            | "Fc"
+            | "Fl"
+            | "Fn"
+            | "Fo"
+            | "Fx"
+            | "Ic"
+            | "Li"
+            | "Lk"
+            | "Ms"
+            | "Mt"
+            | "Nm"
+            | "No"
+            | "Ns"
+            | "Nx"
+            | "Oc"
+            | "Oo"
+            | "Op"
+            | "Ox"
+            | "Pa"
+            | "Pc"
+            | "Pf"
+            | "Po"
+            | "Pq"
+            | "Qc"
+            | "Ql"
+            | "Qo"
+            | "Qq"
+            | "Sc"
+            | "So"
+            | "Sq"
+            | "St"
+            | "Sx"
+            | "Sy"
+            | "Ta"
+            | "Tn"
+            | "Ux"
+            | "Va"
+            | "Vt"
+            | "Xc"
+            | "Xo"
+            | "Xr"
+    )
+}
+
+/// Words mdoc treats as punctuation when they stand alone on a macro line.
+pub fn is_delimiter(word: &str) -> bool {
+    matches!(
+        word,
+        "(" | ")" | "[" | "]" | "." | "," | ";" | ":" | "?" | "!" | "|"
+    )
+}
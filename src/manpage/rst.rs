@@ -0,0 +1,508 @@
+// Copyright 2022 Manos Pitsidianakis <epilys@nessuent.net>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! reStructuredText renderer, for inclusion in Sphinx documentation.
+
+use super::roff::sentence_per_line;
+use super::{
+    bracketed, bugs_text, doc_sentence, example_intro, flag_doc, sentence_case, separated,
+    std_exit_status, summary, verbatim_doc, verbatim_text, Definition, Example, Flag, Keybinding,
+    Manpage, Part, Settings, Subsection, TakesValue,
+};
+
+/// Escape the characters that start inline markup.
+fn escape(val: &str) -> String {
+    let mut ret = String::with_capacity(val.len());
+    for c in val.chars().filter(|c| !c.is_control() || *c == '\n') {
+        if matches!(c, '\\' | '*' | '`' | '_' | '|') {
+            ret.push('\\');
+        }
+        ret.push(c);
+    }
+    ret
+}
+
+/// User text emitted as one or more paragraph lines.
+fn text(page: &Manpage, val: &str) -> String {
+    let val = if page.stable {
+        sentence_per_line(val)
+    } else {
+        val.trim().to_string()
+    };
+    if page.hardened {
+        escape(&val)
+    } else {
+        val
+    }
+}
+
+/// User text inlined into a line the renderer builds.
+fn inline(page: &Manpage, val: &str) -> String {
+    let val = val.split_whitespace().collect::<Vec<_>>().join(" ");
+    if page.hardened {
+        escape(&val)
+    } else {
+        val
+    }
+}
+
+/// Indent every non-blank line of `val` by `width` spaces.
+fn indent(val: &str, width: usize) -> String {
+    val.lines()
+        .map(|line| {
+            if line.trim().is_empty() {
+                String::new()
+            } else {
+                format!("{:width$}{}", "", line, width = width)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn heading(title: &str, underline: char) -> String {
+    format!(
+        "{}\n{}\n",
+        title,
+        underline.to_string().repeat(title.chars().count())
+    )
+}
+
+/// A leading comment saying where the file comes from, if `part` comes first in it.
+fn provenance(page: &Manpage, part: Part) -> String {
+    match page.provenance.as_ref().filter(|_| page.leads(part)) {
+        Some(val) => format!(".. Generated by {}\n\n", val.replace('\n', " ")),
+        None => String::new(),
+    }
+}
+
+/// ` <name>` or ` <name> ...`, for an option or subcommand that takes values.
+fn placeholder(page: &Manpage, args: &Option<TakesValue>, fallback: &str) -> String {
+    match args {
+        Some(value) => value.optional(format!(
+            "{}{}",
+            value
+                .placeholders(page, fallback)
+                .iter()
+                .map(|name| format!(" <{}>", name))
+                .collect::<String>(),
+            if value.multiple { " ..." } else { "" }
+        )),
+        None => String::new(),
+    }
+}
+
+/// User text set as a literal block, as written.
+fn literal_block(val: &str) -> String {
+    format!("::\n\n{}", indent(&verbatim_text(val), 3))
+}
+
+/// Text set as an inline literal, which cannot be escaped.
+fn literal(val: &str) -> String {
+    format!(
+        "``{}``",
+        val.split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .replace('`', "")
+    )
+}
+
+/// A flag's spellings joined by `separator`, for example `--output <output>, -o <output>`.
+fn option_list(page: &Manpage, flag: &Flag, separator: &str) -> Option<String> {
+    let Flag {
+        long, short, args, ..
+    } = flag;
+    let fallback = long
+        .as_ref()
+        .or(short.as_ref())
+        .map(String::as_str)
+        .unwrap_or("ARGUMENT");
+    let placeholder = format!(
+        "{}{}",
+        placeholder(page, args, fallback),
+        if flag.trailing_ellipsis() { " ..." } else { "" }
+    );
+    let mut names = match (long, short) {
+        (None, None) => return None,
+        (Some(l), Some(s)) => vec![format!("--{}", l), format!("-{}", s)],
+        (Some(l), None) => vec![format!("--{}", l)],
+        (None, Some(s)) => vec![format!("-{}", s)],
+    };
+    names.extend(
+        flag.other_longs()
+            .iter()
+            .map(|other| format!("--{}", other)),
+    );
+    Some(
+        names
+            .iter()
+            .map(|name| format!("{}{}", name, placeholder))
+            .collect::<Vec<_>>()
+            .join(separator)
+            .replace(char::is_control, ""),
+    )
+}
+
+/// An `option` directive documenting `flag`.
+fn option_directive(page: &Manpage, flag: &Flag) -> Option<String> {
+    let mut ret = format!(".. option:: {}\n", option_list(page, flag, ", ")?);
+    if let Some(doc) = verbatim_doc(flag) {
+        ret.push_str(&format!("\n{}\n", indent(&literal_block(doc), 3)));
+    }
+    if let Some(doc) = flag_doc(flag) {
+        ret.push_str(&format!("\n{}\n", indent(&text(page, &doc), 3)));
+    }
+    Some(ret)
+}
+
+pub fn header(page: &Manpage) -> String {
+    let name = inline(page, &page.name);
+    let mut ret = format!(
+        "{}{}\n{}",
+        provenance(page, Part::Header),
+        heading(&name, '='),
+        inline(
+            page,
+            &doc_sentence(page.description.as_deref().unwrap_or_default())
+        ),
+    );
+    if !page.invocations.is_empty() {
+        ret.push_str(&format!(
+            "\n\nAlso invoked as {}.",
+            page.invocations
+                .iter()
+                .map(|name| format!("**{}**", inline(page, name)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    // Leave a blank line before whatever follows once the parts are concatenated.
+    ret.push('\n');
+    ret
+}
+
+/// Each example's description, introducing its command line in a literal block.
+fn examples(page: &Manpage) -> String {
+    if page.examples.is_empty() {
+        return String::new();
+    }
+    let mut ret = format!("{}\n", heading("Examples", '-'));
+    for Example { command, doc } in page.examples.iter() {
+        if let Some(doc) = doc {
+            ret.push_str(&format!("{}\n\n", text(page, &example_intro(doc))));
+        }
+        ret.push_str(&format!("{}\n\n", literal_block(command)));
+    }
+    ret
+}
+
+fn definitions(page: &Manpage) -> String {
+    term_list(page, "Definitions", &page.definitions)
+}
+
+fn diagnostics(page: &Manpage) -> String {
+    term_list(page, "Diagnostics", &page.diagnostics)
+}
+
+/// The sections flags have entries in with `#[manpage(under = "...")]`, such as FILES.
+fn routed_sections(page: &Manpage) -> String {
+    page.routed_sections()
+        .iter()
+        .map(|(title, entries)| term_list(page, &sentence_case(title), entries))
+        .collect()
+}
+
+/// A section with a definition list item per term.
+fn term_list(page: &Manpage, title: &str, entries: &[Definition]) -> String {
+    if entries.is_empty() {
+        return String::new();
+    }
+    format!("{}\n{}", heading(title, '-'), term_items(page, entries))
+}
+
+/// A definition list item per term.
+fn term_items(page: &Manpage, entries: &[Definition]) -> String {
+    let mut ret = String::new();
+    for Definition {
+        term,
+        doc,
+        overridden_by,
+    } in entries.iter()
+    {
+        let mut doc = text(page, doc);
+        if let Some(flag) = overridden_by {
+            let note = format!("Overridden by {}.", literal(&flag.display_name()));
+            doc = format!("{}\n{}", doc, note).trim_start().to_string();
+        }
+        ret.push_str(&format!("{}\n{}\n\n", inline(page, term), indent(&doc, 3)));
+    }
+    ret
+}
+
+/// The exit status section: the usual exit statuses if the program has them, then the declared
+/// codes.
+fn exit_status(page: &Manpage) -> String {
+    if !page.std_exit_status && page.exit_statuses.is_empty() {
+        return String::new();
+    }
+    let mut ret = format!("{}\n", heading("Exit status", '-'));
+    if page.std_exit_status {
+        ret.push_str(&format!("{}\n\n", text(page, &std_exit_status(page))));
+    }
+    ret.push_str(&term_items(page, &page.exit_codes()));
+    ret
+}
+
+fn keybindings(page: &Manpage) -> String {
+    if page.keybindings.is_empty() {
+        return String::new();
+    }
+    let mut ret = format!("{}\n", heading("Key bindings", '-'));
+    for Keybinding { key, action, doc } in page.keybindings.iter() {
+        ret.push_str(&literal(key));
+        if let Some(action) = action {
+            ret.push_str(&format!(" ({})", inline(page, action)));
+        }
+        ret.push('\n');
+        ret.push_str(&indent(&text(page, doc.as_deref().unwrap_or_default()), 3));
+        ret.push_str("\n\n");
+    }
+    ret
+}
+
+/// The paragraph explaining which setting wins when it is given more than once.
+fn settings(page: &Manpage) -> String {
+    let Settings {
+        environment,
+        config_file,
+    } = match page.settings.as_ref() {
+        Some(settings) => settings,
+        None => return String::new(),
+    };
+    let mut ret = "Settings are taken from the following sources, \
+                   in order of decreasing precedence:\n\n#. command line options\n"
+        .to_string();
+    if *environment {
+        ret.push_str("#. environment variables\n");
+    }
+    if let Some(path) = config_file {
+        ret.push_str(&format!("#. the configuration file {}\n", literal(path)));
+    }
+    ret.push_str("#. built-in defaults\n");
+    ret
+}
+
+pub fn footer(page: &Manpage) -> String {
+    format!(
+        "{provenance}{definitions}{sections}{exit_status}{keybindings}{examples}{diagnostics}{see_also}{standards}{history}{closing}",
+        provenance = provenance(page, Part::Footer),
+        definitions = definitions(page),
+        sections = routed_sections(page),
+        exit_status = exit_status(page),
+        keybindings = keybindings(page),
+        examples = examples(page),
+        diagnostics = diagnostics(page),
+        see_also = see_also(page),
+        standards = standards(page),
+        history = history(page),
+        closing = closing(page),
+    )
+}
+
+fn see_also(page: &Manpage) -> String {
+    if page.see_also.is_empty() {
+        return String::new();
+    }
+    format!(
+        "{}\n{}\n\n",
+        heading("See also", '-'),
+        text(page, &page.see_also_text())
+    )
+}
+
+fn standards(page: &Manpage) -> String {
+    match &page.standards {
+        Some(doc) => format!("{}\n{}\n\n", heading("Standards", '-'), text(page, doc)),
+        None => String::new(),
+    }
+}
+
+fn history(page: &Manpage) -> String {
+    match &page.history {
+        Some(doc) => format!("{}\n{}\n\n", heading("History", '-'), text(page, doc)),
+        None => String::new(),
+    }
+}
+
+fn authors(page: &Manpage) -> String {
+    match page.authors() {
+        Some(authors) => format!("{}\n{}", heading("Authors", '-'), text(page, authors)),
+        None => String::new(),
+    }
+}
+
+fn caveats(page: &Manpage) -> String {
+    match &page.caveats {
+        Some(doc) => format!("{}\n{}", heading("Caveats", '-'), text(page, doc)),
+        None => String::new(),
+    }
+}
+
+fn bugs(page: &Manpage) -> String {
+    match &page.bugs {
+        Some(bugs) => format!("{}\n{}", heading("Bugs", '-'), text(page, &bugs_text(bugs))),
+        None => String::new(),
+    }
+}
+
+fn copyright(page: &Manpage) -> String {
+    match &page.copyright {
+        Some(doc) => format!("{}\n{}", heading("Copyright", '-'), text(page, doc)),
+        None => String::new(),
+    }
+}
+
+/// The authors, caveats, bugs and copyright sections, which end the page without a blank line.
+fn closing(page: &Manpage) -> String {
+    vec![authors(page), caveats(page), bugs(page), copyright(page)]
+        .into_iter()
+        .filter(|section| !section.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+pub fn body(page: &Manpage) -> String {
+    let mut synopsis = String::new();
+    let mut options = vec![];
+    for flag in page.options(&page.flags) {
+        if let Some(directive) = option_directive(page, flag) {
+            let names = literal(&option_list(page, flag, " | ").unwrap());
+            synopsis.push_str(&format!(" {}", bracketed(flag, &names)));
+            options.push(directive);
+        }
+    }
+    let mut operands = vec![];
+    for (name, flag) in page.operands() {
+        let operand = literal(&placeholder(page, &flag.args, name));
+        synopsis.push_str(&format!(
+            " {}",
+            separated(page, flag, &literal("--"), &operand)
+        ));
+        let mut item = format!("{}\n", operand);
+        if let Some(doc) = flag.doc.as_ref() {
+            item.push_str(&indent(&text(page, &doc_sentence(doc)), 3));
+        }
+        operands.push(item.trim_end().to_string());
+    }
+    let synopsis = std::iter::once(page.name.as_str())
+        .chain(page.invocations.iter().map(String::as_str))
+        .map(|name| format!("| **{}**{}\n", inline(page, name), synopsis))
+        .collect::<String>();
+    let mut subcommands = vec![];
+    for cmd in page.subcommands.iter() {
+        let mut item = format!(
+            "{}{}\n",
+            cmd.names()
+                .into_iter()
+                .map(|name| format!("**{}**", inline(page, name)))
+                .collect::<Vec<_>>()
+                .join(", "),
+            inline(page, &placeholder(page, &cmd.args, "ARGUMENT"))
+        );
+        if let Some(doc) = &cmd.doc {
+            item.push_str(&indent(&text(page, &doc_sentence(doc)), 3));
+            item.push('\n');
+        }
+        for directive in page
+            .options(&cmd.flags)
+            .into_iter()
+            .filter_map(|flag| option_directive(page, flag))
+        {
+            item.push_str(&format!("\n{}\n", indent(&directive, 3)));
+        }
+        subcommands.push(item.trim_end().to_string());
+    }
+    let mut parts = vec![];
+    if !page.flags.is_empty() || !page.invocations.is_empty() {
+        parts.push(synopsis.trim_end().to_string());
+    }
+    match page.description_text() {
+        Some(doc) if page.verbatim => parts.push(literal_block(doc)),
+        Some(doc) => parts.push(text(page, doc)),
+        None => {}
+    }
+    if !options.is_empty() {
+        parts.push(options.join("\n").trim_end().to_string());
+    }
+    if !operands.is_empty() {
+        parts.push(operands.join("\n\n"));
+    }
+    if !subcommands.is_empty() {
+        parts.push(subcommands.join("\n\n"));
+    }
+    let settings = settings(page);
+    if !settings.is_empty() {
+        parts.push(settings.trim_end().to_string());
+    }
+    for Subsection { title, doc } in page.subsections.iter() {
+        parts.push(format!(
+            "{}\n{}",
+            heading(&inline(page, title), '~'),
+            text(page, doc)
+        ));
+    }
+    if parts.is_empty() {
+        return provenance(page, Part::Body);
+    }
+    format!("{}{}\n\n", provenance(page, Part::Body), parts.join("\n\n"))
+}
+
+/// A two-column `list-table`, or nothing if `rows` is empty.
+fn list_table(title: &str, rows: Vec<(String, String)>) -> String {
+    if rows.is_empty() {
+        return String::new();
+    }
+    let mut ret = format!("\n{}\n.. list-table::\n\n", heading(title, '-'));
+    for (item, doc) in rows {
+        ret.push_str(&format!("   * - {}\n     - {}\n", item, doc));
+    }
+    ret
+}
+
+pub fn cheatsheet(page: &Manpage) -> String {
+    let (flags, subcommands) = page.cheatsheet_items();
+    let options = flags
+        .into_iter()
+        .filter_map(|flag| {
+            Some((
+                literal(&option_list(page, flag, ", ")?),
+                inline(page, summary(&flag.doc)),
+            ))
+        })
+        .collect();
+    let commands = subcommands
+        .into_iter()
+        .map(|cmd| {
+            (
+                literal(&format!(
+                    "{}{}",
+                    cmd.name,
+                    placeholder(page, &cmd.args, "ARGUMENT")
+                )),
+                inline(page, summary(&cmd.doc)),
+            )
+        })
+        .collect();
+    format!(
+        "{}{}{}",
+        header(page),
+        list_table("Options", options),
+        list_table("Commands", commands)
+    )
+}
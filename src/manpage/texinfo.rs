@@ -0,0 +1,476 @@
+// Copyright 2022 Manos Pitsidianakis <epilys@nessuent.net>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Texinfo renderer, producing an "Invoking" node with `@deffn` option definitions.
+
+use super::roff::sentence_per_line;
+use super::{
+    bracketed, bugs_text, doc_sentence, example_intro, flag_doc, sentence_case, separated,
+    std_exit_status, summary, verbatim_doc, verbatim_text, Definition, Example, Flag, Keybinding,
+    Manpage, Part, Settings, Subsection, TakesValue,
+};
+
+/// Escape the characters Texinfo gives a meaning to.
+///
+/// This is not affected by hardening: an unescaped brace breaks the whole document.
+fn escape(val: &str) -> String {
+    let mut ret = String::with_capacity(val.len());
+    for c in val.chars().filter(|c| !c.is_control() || *c == '\n') {
+        if matches!(c, '@' | '{' | '}') {
+            ret.push('@');
+        }
+        ret.push(c);
+    }
+    ret
+}
+
+/// User text emitted as one or more paragraph lines.
+fn text(page: &Manpage, val: &str) -> String {
+    if page.stable {
+        escape(&sentence_per_line(val))
+    } else {
+        escape(val.trim())
+    }
+}
+
+/// User text set as a literal block, as written.
+fn literal_block(val: &str) -> String {
+    format!("@example\n{}\n@end example\n", escape(&verbatim_text(val)))
+}
+
+/// User text inlined into a line the renderer builds.
+fn inline(val: &str) -> String {
+    escape(&val.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// A leading comment saying where the file comes from, if `part` comes first in it.
+fn provenance(page: &Manpage, part: Part) -> String {
+    match page.provenance.as_ref().filter(|_| page.leads(part)) {
+        Some(val) => format!("@c Generated by {}\n", val.replace('\n', " ")),
+        None => String::new(),
+    }
+}
+
+/// ` @var{name}` or ` @var{name}@dots{}`, for an option or subcommand that takes values.
+fn placeholder(page: &Manpage, args: &Option<TakesValue>, fallback: &str) -> String {
+    match args {
+        Some(value) => value.optional(format!(
+            "{}{}",
+            value
+                .placeholders(page, fallback)
+                .iter()
+                .map(|name| format!(" @var{{{}}}", inline(name)))
+                .collect::<String>(),
+            if value.multiple { "@dots{}" } else { "" }
+        )),
+        None => String::new(),
+    }
+}
+
+/// Every spelling of a flag with its argument, for example `--output @var{output}`.
+fn spellings(page: &Manpage, flag: &Flag) -> Vec<String> {
+    let Flag {
+        long, short, args, ..
+    } = flag;
+    let fallback = long
+        .as_ref()
+        .or(short.as_ref())
+        .map(String::as_str)
+        .unwrap_or("ARGUMENT");
+    let placeholder = format!(
+        "{}{}",
+        placeholder(page, args, fallback),
+        if flag.trailing_ellipsis() {
+            " @dots{}"
+        } else {
+            ""
+        }
+    );
+    let mut names = match (long, short) {
+        (None, None) => vec![],
+        (Some(l), Some(s)) => vec![format!("--{}", l), format!("-{}", s)],
+        (Some(l), None) => vec![format!("--{}", l)],
+        (None, Some(s)) => vec![format!("-{}", s)],
+    };
+    names.extend(
+        flag.other_longs()
+            .iter()
+            .map(|other| format!("--{}", other)),
+    );
+    names
+        .iter()
+        .map(|name| format!("{}{}", inline(name), placeholder))
+        .collect()
+}
+
+/// A `@deffn` block of category `category` documenting `flag`.
+fn deffn(page: &Manpage, category: &str, flag: &Flag) -> Option<String> {
+    let spellings = spellings(page, flag);
+    let (first, rest) = spellings.split_first()?;
+    let mut ret = format!("@deffn {} {}\n", category, first);
+    for spelling in rest {
+        ret.push_str(&format!("@deffnx {} {}\n", category, spelling));
+    }
+    if let Some(doc) = verbatim_doc(flag) {
+        ret.push_str(&literal_block(doc));
+    }
+    if let Some(doc) = flag_doc(flag) {
+        ret.push_str(&format!("{}\n", text(page, &doc)));
+    }
+    ret.push_str("@end deffn\n");
+    Some(ret)
+}
+
+pub fn header(page: &Manpage) -> String {
+    let name = inline(&page.name);
+    let mut ret = format!(
+        "{provenance}@node Invoking {name}\n@section Invoking @command{{{name}}}\n@cindex invoking @command{{{name}}}\n",
+        provenance = provenance(page, Part::Header),
+        name = name,
+    );
+    for name in page.invocations.iter() {
+        ret.push_str(&format!("@cindex @command{{{}}}\n", inline(name)));
+    }
+    ret.push_str(&format!(
+        "\n{}",
+        inline(&doc_sentence(
+            page.description.as_deref().unwrap_or_default()
+        ))
+    ));
+    ret
+}
+
+/// Each example's description, introducing its command line in an example block.
+fn examples(page: &Manpage) -> String {
+    if page.examples.is_empty() {
+        return String::new();
+    }
+    let mut ret = "@subheading Examples\n\n".to_string();
+    for Example { command, doc } in page.examples.iter() {
+        if let Some(doc) = doc {
+            ret.push_str(&format!("{}\n\n", text(page, &example_intro(doc))));
+        }
+        ret.push_str(&format!("{}\n", literal_block(command)));
+    }
+    ret
+}
+
+fn definitions(page: &Manpage) -> String {
+    term_list(page, "Definitions", &page.definitions)
+}
+
+fn diagnostics(page: &Manpage) -> String {
+    term_list(page, "Diagnostics", &page.diagnostics)
+}
+
+/// The sections flags have entries in with `#[manpage(under = "...")]`, such as FILES.
+fn routed_sections(page: &Manpage) -> String {
+    page.routed_sections()
+        .iter()
+        .map(|(title, entries)| term_list(page, &sentence_case(title), entries))
+        .collect()
+}
+
+/// A subheading with a table item per term.
+fn term_list(page: &Manpage, title: &str, entries: &[Definition]) -> String {
+    if entries.is_empty() {
+        return String::new();
+    }
+    format!(
+        "@subheading {}\n\n{}",
+        inline(title),
+        term_items(page, entries)
+    )
+}
+
+/// A table with an item per term.
+fn term_items(page: &Manpage, entries: &[Definition]) -> String {
+    let mut ret = "@table @asis\n".to_string();
+    for Definition {
+        term,
+        doc,
+        overridden_by,
+    } in entries.iter()
+    {
+        ret.push_str(&format!("@item {}\n", inline(term)));
+        if !doc.is_empty() {
+            ret.push_str(&format!("{}\n", text(page, doc)));
+        }
+        if let Some(flag) = overridden_by {
+            ret.push_str(&format!(
+                "Overridden by @option{{{}}}.\n",
+                escape(&flag.display_name())
+            ));
+        }
+    }
+    ret.push_str("@end table\n\n");
+    ret
+}
+
+/// The exit status subheading: the usual exit statuses if the program has them, then the
+/// declared codes.
+fn exit_status(page: &Manpage) -> String {
+    if !page.std_exit_status && page.exit_statuses.is_empty() {
+        return String::new();
+    }
+    let mut ret = "@subheading Exit status\n\n".to_string();
+    if page.std_exit_status {
+        ret.push_str(&format!("{}\n\n", text(page, &std_exit_status(page))));
+    }
+    if !page.exit_statuses.is_empty() {
+        ret.push_str(&term_items(page, &page.exit_codes()));
+    }
+    ret
+}
+
+fn keybindings(page: &Manpage) -> String {
+    if page.keybindings.is_empty() {
+        return String::new();
+    }
+    let mut ret = "@subheading Key bindings\n\n@table @kbd\n".to_string();
+    for Keybinding { key, action, doc } in page.keybindings.iter() {
+        ret.push_str(&format!("@item {}\n", inline(key)));
+        if let Some(action) = action {
+            ret.push_str(&format!("(@code{{{}}}) ", inline(action)));
+        }
+        ret.push_str(&text(page, doc.as_deref().unwrap_or_default()));
+        ret.push('\n');
+    }
+    ret.push_str("@end table\n\n");
+    ret
+}
+
+/// The paragraph explaining which setting wins when it is given more than once.
+fn settings(page: &Manpage) -> String {
+    let Settings {
+        environment,
+        config_file,
+    } = match page.settings.as_ref() {
+        Some(settings) => settings,
+        None => return String::new(),
+    };
+    let mut ret = "Settings are taken from the following sources, \
+                   in order of decreasing precedence:\n\n@enumerate\n@item\ncommand line options\n"
+        .to_string();
+    if *environment {
+        ret.push_str("@item\nenvironment variables\n");
+    }
+    if let Some(path) = config_file {
+        ret.push_str(&format!(
+            "@item\nthe configuration file @file{{{}}}\n",
+            inline(path)
+        ));
+    }
+    ret.push_str("@item\nbuilt-in defaults\n@end enumerate\n");
+    ret
+}
+
+pub fn footer(page: &Manpage) -> String {
+    format!(
+        "{provenance}{definitions}{sections}{exit_status}{keybindings}{examples}{diagnostics}{see_also}{standards}{history}{closing}",
+        provenance = provenance(page, Part::Footer),
+        definitions = definitions(page),
+        sections = routed_sections(page),
+        exit_status = exit_status(page),
+        keybindings = keybindings(page),
+        examples = examples(page),
+        diagnostics = diagnostics(page),
+        see_also = see_also(page),
+        standards = standards(page),
+        history = history(page),
+        closing = closing(page),
+    )
+}
+
+fn see_also(page: &Manpage) -> String {
+    if page.see_also.is_empty() {
+        return String::new();
+    }
+    format!(
+        "@subheading See also\n\n{}\n\n",
+        text(page, &page.see_also_text())
+    )
+}
+
+fn standards(page: &Manpage) -> String {
+    match &page.standards {
+        Some(doc) => format!("@subheading Standards\n\n{}\n\n", text(page, doc)),
+        None => String::new(),
+    }
+}
+
+fn history(page: &Manpage) -> String {
+    match &page.history {
+        Some(doc) => format!("@subheading History\n\n{}\n\n", text(page, doc)),
+        None => String::new(),
+    }
+}
+
+fn authors(page: &Manpage) -> String {
+    match page.authors() {
+        Some(authors) => format!("@subheading Authors\n\n{}", text(page, authors)),
+        None => String::new(),
+    }
+}
+
+fn caveats(page: &Manpage) -> String {
+    match &page.caveats {
+        Some(doc) => format!("@subheading Caveats\n\n{}", text(page, doc)),
+        None => String::new(),
+    }
+}
+
+fn bugs(page: &Manpage) -> String {
+    match &page.bugs {
+        Some(bugs) => format!("@subheading Bugs\n\n{}", text(page, &bugs_text(bugs))),
+        None => String::new(),
+    }
+}
+
+fn copyright(page: &Manpage) -> String {
+    match &page.copyright {
+        Some(doc) => format!("@subheading Copyright\n\n{}", text(page, doc)),
+        None => String::new(),
+    }
+}
+
+/// The authors, caveats, bugs and copyright sections, which end the page without a blank line.
+fn closing(page: &Manpage) -> String {
+    vec![authors(page), caveats(page), bugs(page), copyright(page)]
+        .into_iter()
+        .filter(|section| !section.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+pub fn body(page: &Manpage) -> String {
+    let mut synopsis = String::new();
+    let mut options = vec![];
+    for flag in page.options(&page.flags) {
+        if let Some(block) = deffn(page, "Option", flag) {
+            synopsis.push_str(&format!(
+                " {}",
+                bracketed(flag, &spellings(page, flag).join(" | "))
+            ));
+            options.push(block);
+        }
+    }
+    for (name, flag) in page.operands() {
+        let operand = placeholder(page, &flag.args, name);
+        synopsis.push_str(&format!(
+            " {}",
+            separated(page, flag, "--", operand.trim_start())
+        ));
+        let mut block = format!("@deffn Argument {}\n", operand.trim_start());
+        if let Some(doc) = flag.doc.as_ref() {
+            block.push_str(&format!("{}\n", text(page, &doc_sentence(doc))));
+        }
+        block.push_str("@end deffn\n");
+        options.push(block);
+    }
+    let synopsis = std::iter::once(page.name.as_str())
+        .chain(page.invocations.iter().map(String::as_str))
+        .map(|name| format!("{}{}\n", inline(name), synopsis))
+        .collect::<String>();
+    let mut subcommands = vec![];
+    for cmd in page.subcommands.iter() {
+        let name = inline(&cmd.name);
+        let args = placeholder(page, &cmd.args, "ARGUMENT");
+        let mut block = format!("@deffn Command {}{}\n", name, args);
+        for alias in cmd.visible_aliases.iter() {
+            block.push_str(&format!("@deffnx Command {}{}\n", inline(alias), args));
+        }
+        if let Some(doc) = &cmd.doc {
+            block.push_str(&format!("{}\n", text(page, &doc_sentence(doc))));
+        }
+        block.push_str("@end deffn\n");
+        let category = format!("{{@command{{{}}} option}}", name);
+        for flag in page.options(&cmd.flags) {
+            if let Some(option) = deffn(page, &category, flag) {
+                block.push('\n');
+                block.push_str(&option);
+            }
+        }
+        subcommands.push(block);
+    }
+    let mut parts = vec![];
+    if !page.flags.is_empty() || !page.invocations.is_empty() {
+        parts.push(format!("@example\n{}@end example\n", synopsis));
+    }
+    match page.description_text() {
+        Some(doc) if page.verbatim => parts.push(literal_block(doc)),
+        Some(doc) => parts.push(format!("{}\n", text(page, doc))),
+        None => {}
+    }
+    parts.extend(options);
+    parts.extend(subcommands);
+    let settings = settings(page);
+    if !settings.is_empty() {
+        parts.push(settings);
+    }
+    for Subsection { title, doc } in page.subsections.iter() {
+        parts.push(format!(
+            "@subsubheading {}\n\n{}\n",
+            inline(title),
+            text(page, doc)
+        ));
+    }
+    if parts.is_empty() {
+        return provenance(page, Part::Body);
+    }
+    format!("{}{}\n", provenance(page, Part::Body), parts.join("\n"))
+}
+
+/// A two-column `@multitable` under a heading, or nothing if `rows` is empty.
+fn multitable(title: &str, rows: Vec<(String, String)>) -> String {
+    if rows.is_empty() {
+        return String::new();
+    }
+    let mut ret = format!(
+        "\n@subheading {}\n\n@multitable @columnfractions .4 .6\n",
+        title
+    );
+    for (item, doc) in rows {
+        ret.push_str(&format!("@item {} @tab {}\n", item, doc));
+    }
+    ret.push_str("@end multitable\n");
+    ret
+}
+
+pub fn cheatsheet(page: &Manpage) -> String {
+    let (flags, subcommands) = page.cheatsheet_items();
+    let options = flags
+        .into_iter()
+        .filter(|flag| !spellings(page, flag).is_empty())
+        .map(|flag| {
+            (
+                format!("@option{{{}}}", spellings(page, flag).join(", ")),
+                inline(summary(&flag.doc)),
+            )
+        })
+        .collect();
+    let commands = subcommands
+        .into_iter()
+        .map(|cmd| {
+            (
+                format!(
+                    "@command{{{}}}{}",
+                    inline(&cmd.name),
+                    placeholder(page, &cmd.args, "ARGUMENT")
+                ),
+                inline(summary(&cmd.doc)),
+            )
+        })
+        .collect();
+    format!(
+        "{}\n{}{}",
+        header(page),
+        multitable("Options", options),
+        multitable("Commands", commands)
+    )
+}
@@ -0,0 +1,537 @@
+// Copyright 2022 Manos Pitsidianakis <epilys@nessuent.net>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Pre-rendered plain text, laid out the way `mandoc -Tascii` shows a page.
+//!
+//! Plain text has no comment syntax, so no provenance line is emitted. Since there is no way to
+//! add section headings around it, the body comes with its own SYNOPSIS and OPTIONS headings.
+
+use super::{
+    bracketed, bugs_text, doc_sentence, example_intro, flag_doc, separated, std_exit_status,
+    summary, verbatim_doc, verbatim_text, Definition, Example, Flag, Keybinding, Manpage, Settings,
+    Subsection, TakesValue,
+};
+
+/// The width of the output in columns.
+const WIDTH: usize = 78;
+/// Where section content starts.
+const INDENT: usize = 5;
+/// How far list item bodies are indented past their tag.
+const TAG_WIDTH: usize = 8;
+
+/// Accumulates wrapped and indented lines.
+#[derive(Default)]
+struct Layout {
+    out: String,
+}
+
+impl Layout {
+    /// Separate what follows from what precedes it by a single blank line.
+    fn blank(&mut self) {
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    /// The entry of `flag` under `names` at `indent`, with a verbatim doc set below the names
+    /// as written.
+    fn entry(&mut self, indent: usize, names: &str, flag: &Flag) {
+        let doc = doc_text(&flag_doc(flag));
+        match verbatim_doc(flag) {
+            Some(verbatim) => {
+                self.tagged(indent, names, TAG_WIDTH, "");
+                self.literal(indent + TAG_WIDTH, verbatim);
+                if !doc.is_empty() {
+                    self.paragraphs(indent + TAG_WIDTH, &doc);
+                }
+            }
+            None => self.tagged(indent, names, TAG_WIDTH, &doc),
+        }
+    }
+
+    /// The lines of `text` at `indent`, as written.
+    fn literal(&mut self, indent: usize, text: &str) {
+        for line in verbatim_text(text).split('\n') {
+            if !line.is_empty() {
+                self.out.push_str(&" ".repeat(indent));
+                self.out.push_str(line);
+            }
+            self.out.push('\n');
+        }
+    }
+
+    fn heading(&mut self, title: &str) {
+        self.blank();
+        self.out.push_str(title);
+        self.out.push('\n');
+    }
+
+    /// Fill `words` into lines, the first starting at column `first` and the rest at `rest`.
+    ///
+    /// The first line may start at a column already reached by the previous output.
+    fn fill<'a>(&mut self, first: usize, rest: usize, words: impl Iterator<Item = &'a str>) {
+        let mut column = first;
+        let mut line_empty = true;
+        for word in words {
+            let len = word.chars().count();
+            if !line_empty && column + 1 + len > WIDTH {
+                self.out.push('\n');
+                self.out.push_str(&" ".repeat(rest));
+                column = rest;
+                line_empty = true;
+            }
+            if !line_empty {
+                self.out.push(' ');
+                column += 1;
+            }
+            self.out.push_str(word);
+            column += len;
+            line_empty = false;
+        }
+        self.out.push('\n');
+    }
+
+    /// Every paragraph of `text` filled at `indent`.
+    fn paragraphs(&mut self, indent: usize, text: &str) {
+        for (i, para) in paragraphs(text).enumerate() {
+            if i > 0 {
+                self.out.push('\n');
+            }
+            self.out.push_str(&" ".repeat(indent));
+            self.fill(indent, indent, para.split_whitespace());
+        }
+    }
+
+    /// `tag` at `indent` followed by `text` at `indent + width`, on the same line when the tag
+    /// leaves room for it.
+    fn tagged(&mut self, indent: usize, tag: &str, width: usize, text: &str) {
+        self.out.push_str(&" ".repeat(indent));
+        self.out.push_str(tag);
+        let body = indent + width;
+        let tag_len = tag.chars().count();
+        let mut paras = paragraphs(text);
+        match paras.next() {
+            Some(first) if tag_len < width => {
+                self.out.push_str(&" ".repeat(width - tag_len));
+                self.fill(body, body, first.split_whitespace());
+            }
+            Some(first) => {
+                self.out.push('\n');
+                self.out.push_str(&" ".repeat(body));
+                self.fill(body, body, first.split_whitespace());
+            }
+            None => self.out.push('\n'),
+        }
+        for para in paras {
+            self.out.push('\n');
+            self.out.push_str(&" ".repeat(body));
+            self.fill(body, body, para.split_whitespace());
+        }
+    }
+
+    /// `words` filled at `indent`, with continuation lines indented past the first word.
+    fn hanging(&mut self, indent: usize, words: &[String]) {
+        let hang = indent + words.first().map_or(0, |w| w.chars().count() + 1);
+        self.out.push_str(&" ".repeat(indent));
+        self.fill(indent, hang, words.iter().map(String::as_str));
+    }
+
+    fn finish(self) -> String {
+        self.out
+    }
+}
+
+/// The non-blank paragraphs of `text`, without control characters.
+fn paragraphs(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split("\n\n")
+        .map(|para| {
+            para.chars()
+                .filter(|c| !c.is_control() || c.is_whitespace())
+                .collect::<String>()
+        })
+        .filter(|para| !para.trim().is_empty())
+}
+
+/// User text inlined into a line the renderer builds.
+fn inline(val: &str) -> String {
+    val.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace(char::is_control, "")
+}
+
+/// ` name` or ` name ...`, for an option or subcommand that takes values.
+fn placeholder(page: &Manpage, args: &Option<TakesValue>, fallback: &str) -> String {
+    match args {
+        Some(value) => value.optional(format!(
+            "{}{}",
+            value
+                .placeholders(page, fallback)
+                .iter()
+                .map(|name| format!(" {}", inline(name)))
+                .collect::<String>(),
+            if value.multiple { " ..." } else { "" }
+        )),
+        None => String::new(),
+    }
+}
+
+/// A flag's names and argument, for example `--output | -o output`.
+fn flag_names(page: &Manpage, flag: &Flag) -> Option<String> {
+    let Flag {
+        long, short, args, ..
+    } = flag;
+    let mut names = match (long, short) {
+        (None, None) => return None,
+        (Some(l), Some(s)) => format!("--{} | -{}", inline(l), inline(s)),
+        (Some(l), None) => format!("--{}", inline(l)),
+        (None, Some(s)) => format!("-{}", inline(s)),
+    };
+    for other in flag.other_longs() {
+        names.push_str(&format!(" | --{}", inline(&other)));
+    }
+    let fallback = long
+        .as_ref()
+        .or(short.as_ref())
+        .map(String::as_str)
+        .unwrap_or("ARGUMENT");
+    Some(format!(
+        "{}{}{}",
+        names,
+        placeholder(page, args, fallback),
+        if flag.trailing_ellipsis() { " ..." } else { "" }
+    ))
+}
+
+fn doc_text(doc: &Option<String>) -> String {
+    match doc {
+        Some(doc) => doc_sentence(doc),
+        None => String::new(),
+    }
+}
+
+fn title(page: &Manpage) -> String {
+    let section = page.manual_section();
+    let title = format!("{}({})", inline(&page.name.to_uppercase()), inline(section));
+    // The volume titles mandoc prints for the standard sections.
+    let middle = match section.get(..1) {
+        Some("2") => "System Calls Manual",
+        Some("3") => "Library Functions Manual",
+        Some("4") => "Device Drivers Manual",
+        Some("5") => "File Formats Manual",
+        Some("6") => "Games Manual",
+        Some("7") => "Miscellaneous Information Manual",
+        Some("8") => "System Manager's Manual",
+        Some("9") => "Kernel Developer's Manual",
+        _ => "General Commands Manual",
+    };
+    let gap = WIDTH.saturating_sub(2 * title.chars().count() + middle.len());
+    format!(
+        "{}{}{}{}{}",
+        title,
+        " ".repeat(gap / 2),
+        middle,
+        " ".repeat(gap - gap / 2),
+        title
+    )
+}
+
+pub fn header(page: &Manpage) -> String {
+    let mut layout = Layout::default();
+    layout.out.push_str(&title(page));
+    layout.out.push('\n');
+    layout.heading("NAME");
+    let names = std::iter::once(page.name.as_str())
+        .chain(page.invocations.iter().map(String::as_str))
+        .map(inline)
+        .collect::<Vec<_>>()
+        .join(", ");
+    let description = inline(page.name_description());
+    layout.paragraphs(INDENT, &format!("{} - {}", names, description));
+    layout.finish()
+}
+
+/// Each example's description, introducing its command line set as written.
+fn examples(layout: &mut Layout, page: &Manpage) {
+    if page.examples.is_empty() {
+        return;
+    }
+    layout.heading("EXAMPLES");
+    for (idx, Example { command, doc }) in page.examples.iter().enumerate() {
+        if idx > 0 {
+            layout.blank();
+        }
+        if let Some(doc) = doc {
+            layout.paragraphs(INDENT, &example_intro(doc));
+            layout.blank();
+        }
+        layout.literal(INDENT + TAG_WIDTH, command);
+    }
+}
+
+fn definitions(layout: &mut Layout, page: &Manpage) {
+    term_list(layout, "DEFINITIONS", &page.definitions);
+}
+
+fn diagnostics(layout: &mut Layout, page: &Manpage) {
+    term_list(layout, "DIAGNOSTICS", &page.diagnostics);
+}
+
+/// The sections flags have entries in with `#[manpage(under = "...")]`, such as FILES.
+fn routed_sections(layout: &mut Layout, page: &Manpage) {
+    for (title, entries) in page.routed_sections().iter() {
+        term_list(layout, title, entries);
+    }
+}
+
+/// A section with a tagged paragraph per term.
+fn term_list(layout: &mut Layout, title: &str, entries: &[Definition]) {
+    if entries.is_empty() {
+        return;
+    }
+    layout.heading(title);
+    term_items(layout, entries);
+}
+
+/// A tagged paragraph per term.
+fn term_items(layout: &mut Layout, entries: &[Definition]) {
+    for Definition {
+        term,
+        doc,
+        overridden_by,
+    } in entries.iter()
+    {
+        let doc = match overridden_by {
+            Some(flag) => format!("{} Overridden by {}.", doc, flag.display_name()),
+            None => doc.clone(),
+        };
+        layout.tagged(INDENT, &inline(term), TAG_WIDTH, doc.trim_start());
+    }
+}
+
+/// The EXIT STATUS section: the usual exit statuses if the program has them, then the declared
+/// codes.
+fn exit_status(layout: &mut Layout, page: &Manpage) {
+    if !page.std_exit_status && page.exit_statuses.is_empty() {
+        return;
+    }
+    layout.heading("EXIT STATUS");
+    if page.std_exit_status {
+        layout.paragraphs(INDENT, &std_exit_status(page));
+    }
+    term_items(layout, &page.exit_codes());
+}
+
+fn keybindings(layout: &mut Layout, page: &Manpage) {
+    if page.keybindings.is_empty() {
+        return;
+    }
+    layout.heading("KEY BINDINGS");
+    for Keybinding { key, action, doc } in page.keybindings.iter() {
+        let mut tag = inline(key);
+        if let Some(action) = action {
+            tag.push_str(&format!(" ({})", inline(action)));
+        }
+        layout.tagged(INDENT, &tag, TAG_WIDTH, doc.as_deref().unwrap_or_default());
+    }
+}
+
+/// The paragraph explaining which setting wins when it is given more than once.
+fn settings(layout: &mut Layout, page: &Manpage) {
+    let Settings {
+        environment,
+        config_file,
+    } = match page.settings.as_ref() {
+        Some(settings) => settings,
+        None => return,
+    };
+    layout.blank();
+    layout.paragraphs(
+        INDENT,
+        "Settings are taken from the following sources, in order of decreasing precedence:",
+    );
+    let mut sources = vec!["command line options".to_string()];
+    if *environment {
+        sources.push("environment variables".to_string());
+    }
+    if let Some(path) = config_file {
+        sources.push(format!("the configuration file {}", inline(path)));
+    }
+    sources.push("built-in defaults".to_string());
+    for (i, source) in sources.iter().enumerate() {
+        layout.tagged(INDENT, &format!("{}.", i + 1), 5, source);
+    }
+}
+
+pub fn footer(page: &Manpage) -> String {
+    let mut layout = Layout::default();
+    definitions(&mut layout, page);
+    routed_sections(&mut layout, page);
+    exit_status(&mut layout, page);
+    keybindings(&mut layout, page);
+    examples(&mut layout, page);
+    diagnostics(&mut layout, page);
+    if !page.see_also.is_empty() {
+        layout.heading("SEE ALSO");
+        layout.paragraphs(INDENT, &page.see_also_text());
+    }
+    if let Some(standards) = &page.standards {
+        layout.heading("STANDARDS");
+        layout.paragraphs(INDENT, standards);
+    }
+    if let Some(history) = &page.history {
+        layout.heading("HISTORY");
+        layout.paragraphs(INDENT, history);
+    }
+    if let Some(authors) = page.authors() {
+        layout.heading("AUTHORS");
+        layout.paragraphs(INDENT, authors);
+    }
+    if let Some(caveats) = &page.caveats {
+        layout.heading("CAVEATS");
+        layout.paragraphs(INDENT, caveats);
+    }
+    if let Some(bugs) = &page.bugs {
+        layout.heading("BUGS");
+        layout.paragraphs(INDENT, &bugs_text(bugs));
+    }
+    if let Some(copyright) = &page.copyright {
+        layout.heading("COPYRIGHT");
+        layout.paragraphs(INDENT, copyright);
+    }
+    layout.finish()
+}
+
+pub fn body(page: &Manpage) -> String {
+    let mut layout = Layout::default();
+    let flags = page
+        .options(&page.flags)
+        .into_iter()
+        .filter_map(|flag| Some((flag_names(page, flag)?, flag)))
+        .collect::<Vec<_>>();
+    if !page.flags.is_empty() || !page.invocations.is_empty() {
+        layout.heading("SYNOPSIS");
+        for name in std::iter::once(&page.name).chain(page.invocations.iter()) {
+            let words = std::iter::once(inline(name))
+                .chain(flags.iter().map(|(names, flag)| bracketed(flag, names)))
+                .chain(page.operands().into_iter().map(|(name, flag)| {
+                    separated(
+                        page,
+                        flag,
+                        "--",
+                        placeholder(page, &flag.args, name).trim_start(),
+                    )
+                }))
+                .collect::<Vec<_>>();
+            layout.hanging(INDENT, &words);
+        }
+    }
+    let description = page.description_text();
+    if description.is_some() || !page.subsections.is_empty() {
+        layout.heading("DESCRIPTION");
+    }
+    match description {
+        Some(doc) if page.verbatim => layout.literal(INDENT, doc),
+        Some(doc) => layout.paragraphs(INDENT, doc),
+        None => {}
+    }
+    for (i, Subsection { title, doc }) in page.subsections.iter().enumerate() {
+        if i > 0 || description.is_some() {
+            layout.blank();
+        }
+        // Indented like mandoc indents `.Ss` titles.
+        layout.out.push_str(&format!("   {}\n", inline(title)));
+        layout.paragraphs(INDENT, doc);
+    }
+    if !flags.is_empty() || !page.subcommands.is_empty() || page.settings.is_some() {
+        layout.heading("OPTIONS");
+    }
+    for (i, (names, flag)) in flags.iter().enumerate() {
+        if i > 0 {
+            layout.blank();
+        }
+        layout.entry(INDENT, names, flag);
+    }
+    for cmd in page.subcommands.iter() {
+        if !layout.out.ends_with("OPTIONS\n") {
+            layout.blank();
+        }
+        let tag = format!(
+            "{}{}",
+            cmd.names()
+                .into_iter()
+                .map(inline)
+                .collect::<Vec<_>>()
+                .join(", "),
+            placeholder(page, &cmd.args, "ARGUMENT")
+        );
+        layout.tagged(INDENT, &tag, TAG_WIDTH, &doc_text(&cmd.doc));
+        for flag in page.options(&cmd.flags) {
+            if let Some(names) = flag_names(page, flag) {
+                layout.entry(INDENT + TAG_WIDTH, &names, flag);
+            }
+        }
+    }
+    settings(&mut layout, page);
+    let operands = page.operands();
+    if !operands.is_empty() {
+        layout.heading("ARGUMENTS");
+    }
+    for (i, (name, flag)) in operands.into_iter().enumerate() {
+        if i > 0 {
+            layout.blank();
+        }
+        let tag = placeholder(page, &flag.args, name);
+        layout.tagged(INDENT, tag.trim_start(), TAG_WIDTH, &doc_text(&flag.doc));
+    }
+    // Leave a blank line before the footer once the parts are concatenated.
+    layout.blank();
+    layout.finish()
+}
+
+/// A two-column list under a heading, sized after its widest item.
+fn columns(layout: &mut Layout, title: &str, rows: Vec<(String, String)>) {
+    if rows.is_empty() {
+        return;
+    }
+    let width = rows
+        .iter()
+        .map(|(item, _)| item.chars().count() + 2)
+        .max()
+        .unwrap_or(TAG_WIDTH)
+        .min(WIDTH / 2);
+    layout.heading(title);
+    for (item, doc) in rows {
+        layout.tagged(INDENT, &item, width, &doc);
+    }
+}
+
+pub fn cheatsheet(page: &Manpage) -> String {
+    let (flags, subcommands) = page.cheatsheet_items();
+    let options = flags
+        .into_iter()
+        .filter_map(|flag| Some((flag_names(page, flag)?, inline(summary(&flag.doc)))))
+        .collect();
+    let commands = subcommands
+        .into_iter()
+        .map(|cmd| {
+            (
+                format!(
+                    "{}{}",
+                    inline(&cmd.name),
+                    placeholder(page, &cmd.args, "ARGUMENT")
+                ),
+                inline(summary(&cmd.doc)),
+            )
+        })
+        .collect();
+    let mut layout = Layout { out: header(page) };
+    columns(&mut layout, "OPTIONS", options);
+    columns(&mut layout, "COMMANDS", commands);
+    layout.finish()
+}
@@ -1,14 +1,16 @@
 use std::iter::FromIterator;
 
+use proc_macro2::TokenStream;
 use proc_macro_error::{abort, ResultExt};
 use quote::ToTokens;
 use syn::{
     self, parenthesized,
-    parse::{Parse, ParseBuffer, ParseStream},
+    parse::{Parse, ParseStream, Parser},
     punctuated::Punctuated,
-    Attribute, Expr, ExprLit, Ident, Lit, LitBool, LitStr, Token,
+    Attribute, Expr, ExprLit, Ident, Lit, LitBool, LitInt, LitStr, NestedMeta, Token,
 };
 
+#[allow(dead_code)]
 pub enum StructOptAttr {
     // single-identifier attributes
     Short(Ident),
@@ -33,18 +35,26 @@ pub enum StructOptAttr {
     Manpage(Ident, LitStr),
     ManpageHeader(Ident, LitStr),
     ManpageFooter(Ident, LitStr),
+    HelpHeading(Ident, LitStr),
 
     // parse(parser_kind [= parser_func])
     Parse(Ident, ParserSpec),
 
     // ident [= arbitrary_expr]
     Skip(Ident, Option<Expr>),
+    DefaultValueT(Ident, Option<Expr>),
+
+    // ident = path
+    ValueHint(Ident, Expr),
 
     // ident = arbitrary_expr
     NameExpr(Ident, Expr),
 
     // ident(arbitrary_expr,*)
     MethodCall(Ident, Vec<Expr>),
+
+    // attributes the derive does not understand, left out with a warning
+    Ignored(Ident),
 }
 
 impl Parse for StructOptAttr {
@@ -77,6 +87,14 @@ impl Parse for StructOptAttr {
                     "rename_all" => Ok(RenameAll(name, lit)),
                     "rename_all_env" => Ok(RenameAllEnv(name, lit)),
                     "default_value" => Ok(DefaultValue(name, Some(lit))),
+                    "help_heading" => Ok(HelpHeading(name, lit)),
+                    "default_value_t" => {
+                        let expr = ExprLit {
+                            attrs: vec![],
+                            lit: Lit::Str(lit),
+                        };
+                        Ok(DefaultValueT(name, Some(Expr::Lit(expr))))
+                    }
 
                     "version" => {
                         check_empty_lit("version");
@@ -124,6 +142,10 @@ impl Parse for StructOptAttr {
                     Ok(expr) => {
                         if name_str == "skip" {
                             Ok(Skip(name, Some(expr)))
+                        } else if name_str == "default_value_t" {
+                            Ok(DefaultValueT(name, Some(expr)))
+                        } else if name_str == "value_hint" {
+                            Ok(ValueHint(name, expr))
                         } else {
                             Ok(NameExpr(name, expr))
                         }
@@ -152,26 +174,29 @@ impl Parse for StructOptAttr {
                     }
                 }
 
-                "raw" => match nested.parse::<LitBool>() {
-                    Ok(bool_token) => {
-                        let expr = ExprLit {
-                            attrs: vec![],
-                            lit: Lit::Bool(bool_token),
-                        };
-                        let expr = Expr::Lit(expr);
-                        Ok(MethodCall(name, vec![expr]))
-                    }
+                "raw" => {
+                    let args: TokenStream = nested.parse()?;
+                    match syn::parse2::<LitBool>(args.clone()) {
+                        Ok(bool_token) => {
+                            let expr = ExprLit {
+                                attrs: vec![],
+                                lit: Lit::Bool(bool_token),
+                            };
+                            let expr = Expr::Lit(expr);
+                            Ok(MethodCall(name, vec![expr]))
+                        }
 
-                    Err(_) => {
-                        abort!(name,
-                            "`#[structopt(raw(...))` attributes are removed in structopt 0.3, \
-                            they are replaced with raw methods";
-                            help = "if you meant to call `clap::Arg::raw()` method \
-                                you should use bool literal, like `raw(true)` or `raw(false)`";
-                            note = raw_method_suggestion(nested);
-                        );
+                        Err(_) => Ok(ignored(
+                            name,
+                            &format!("raw({})", args),
+                            &format!(
+                                "`raw(...)` attributes are removed in structopt 0.3, \
+                                 they are replaced with raw methods; {}",
+                                raw_method_suggestion(args)
+                            ),
+                        )),
                     }
-                },
+                }
 
                 _ => {
                     let method_args: Punctuated<_, Token![,]> =
@@ -192,8 +217,9 @@ impl Parse for StructOptAttr {
                 "verbatim_doc_comment" => Ok(VerbatimDocComment(name)),
 
                 "default_value" => Ok(DefaultValue(name, None)),
-                "about" => (Ok(About(name, None))),
-                "author" => (Ok(Author(name, None))),
+                "default_value_t" => Ok(DefaultValueT(name, None)),
+                "about" => Ok(About(name, None)),
+                "author" => Ok(Author(name, None)),
 
                 "skip" => Ok(Skip(name, None)),
 
@@ -204,13 +230,277 @@ impl Parse for StructOptAttr {
                      no attribute needed"
                 ),
 
-                _ => abort!(name, "unexpected attribute: {}", name_str),
+                _ => Ok(ignored(name, &name_str, "unexpected attribute")),
             }
         }
     }
 }
 
+/// Warn that the attribute `#[structopt(attr)]` is left out, and why, so that an attribute the
+/// derive does not understand does not keep the rest of the item, or its manpage, from being
+/// generated.
+fn ignored(name: Ident, attr: &str, reason: &str) -> StructOptAttr {
+    crate::warnings::emit(
+        name.span(),
+        format!(
+            "`#[structopt({})]` is ignored: {}",
+            attr.replace(' ', ""),
+            reason
+        ),
+    );
+    StructOptAttr::Ignored(name)
+}
+
+/// Attributes of the `#[manpage(...)]` namespace, which only affect the generated manpage.
+#[allow(dead_code)]
+pub enum ManpageAttr {
+    // single-identifier attributes
+    Hardened(Ident),
+    NoProvenance(Ident),
+    NoBuiltinFlags(Ident),
+    StdExitStatus(Ident),
+    Stable(Ident),
+    Featured(Ident),
+    Negatable(Ident),
+    Compress(Ident),
+    EscapeHyphens(Ident),
+    SubcommandPages(Ident),
+    Utf8(Ident),
+    Skip(Ident),
+
+    // ident = "string literal"
+    Cheatsheet(Ident, LitStr),
+    Format(Ident, LitStr),
+    Json(Ident, LitStr),
+    Yaml(Ident, LitStr),
+    TagWidth(Ident, LitStr),
+    ValueNames(Ident, LitStr),
+    SortOptions(Ident, LitStr),
+    Date(Ident, LitStr),
+    Os(Ident, LitStr),
+    Version(Ident, LitStr),
+    Doc(Ident, LitStr),
+    Standards(Ident, LitStr),
+    Bugs(Ident, LitStr),
+    Copyright(Ident, LitStr),
+    CopyrightFile(Ident, LitStr),
+    History(Ident, LitStr),
+    HistoryFile(Ident, LitStr),
+    Caveats(Ident, LitStr),
+    CaveatsFile(Ident, LitStr),
+    Under(Ident, LitStr),
+
+    // ident = integer literal | "string literal"
+    Section(Ident, String),
+
+    // ident("string literal",*)
+    InvokedAs(Ident, Vec<LitStr>),
+    SeeAlso(Ident, Vec<LitStr>),
+
+    // ident(field = "string literal",*)
+    Keybinding(Ident, Vec<LitField>),
+    Example(Ident, Vec<LitField>),
+    ExitStatus(Ident, Vec<LitField>),
+    EnvVar(Ident, Vec<LitField>),
+    Diagnostic(Ident, Vec<LitField>),
+    File(Ident, Vec<LitField>),
+    Definition(Ident, Vec<LitField>),
+    Subsection(Ident, Vec<LitField>),
+    Value(Ident, Vec<LitField>),
+
+    // ident(ident | ident = "string literal",*)
+    Settings(Ident, Vec<NestedMeta>),
+    FrontMatter(Ident, Vec<NestedMeta>),
+    OptionsList(Ident, Vec<NestedMeta>),
+    SubcommandsList(Ident, Vec<NestedMeta>),
+}
+
+/// A `name = "string literal"` pair inside a `#[manpage(...)]` attribute.
+pub struct LitField {
+    pub name: Ident,
+    pub lit: LitStr,
+}
+
+impl Parse for LitField {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let name = input.parse()?;
+        let _: Token![=] = input.parse()?;
+        let lit = input.parse()?;
+        Ok(LitField { name, lit })
+    }
+}
+
+impl Parse for ManpageAttr {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        use self::ManpageAttr::*;
+
+        let name: Ident = input.parse()?;
+        let name_str = name.to_string();
+
+        if input.peek(Token![=]) {
+            // `name = value` attributes.
+            let assign_token = input.parse::<Token![=]>()?; // skip '='
+
+            if input.peek(LitStr) {
+                let lit: LitStr = input.parse()?;
+
+                match name_str.as_ref() {
+                    "cheatsheet" => Ok(Cheatsheet(name, lit)),
+                    "format" => Ok(Format(name, lit)),
+                    "json" => Ok(Json(name, lit)),
+                    "yaml" => Ok(Yaml(name, lit)),
+                    "tag_width" => Ok(TagWidth(name, lit)),
+                    "value_names" => Ok(ValueNames(name, lit)),
+                    "sort_options" => Ok(SortOptions(name, lit)),
+                    "date" => Ok(Date(name, lit)),
+                    "os" => Ok(Os(name, lit)),
+                    "version" => Ok(Version(name, lit)),
+                    "doc" => Ok(Doc(name, lit)),
+                    "standards" => Ok(Standards(name, lit)),
+                    "bugs" => Ok(Bugs(name, lit)),
+                    "copyright" => Ok(Copyright(name, lit)),
+                    "copyright_file" => Ok(CopyrightFile(name, lit)),
+                    "history" => Ok(History(name, lit)),
+                    "history_file" => Ok(HistoryFile(name, lit)),
+                    "caveats" => Ok(Caveats(name, lit)),
+                    "caveats_file" => Ok(CaveatsFile(name, lit)),
+                    "section" => Ok(Section(name, lit.value())),
+                    "under" => Ok(Under(name, lit)),
+
+                    _ => abort!(name, "unexpected manpage attribute: {}", name_str),
+                }
+            } else if input.peek(LitInt) && name_str == "section" {
+                let lit: LitInt = input.parse()?;
+                Ok(Section(name, lit.base10_digits().to_string()))
+            } else {
+                abort! {
+                    assign_token,
+                    "expected `string literal` after `=`"
+                }
+            }
+        } else if input.peek(syn::token::Paren) {
+            // `name(...)` attributes.
+            let nested;
+            parenthesized!(nested in input);
+
+            match name_str.as_ref() {
+                "invoked_as" => {
+                    let names: Punctuated<LitStr, Token![,]> =
+                        nested.parse_terminated(<LitStr as Parse>::parse)?;
+                    Ok(InvokedAs(name, Vec::from_iter(names)))
+                }
+
+                "see_also" => {
+                    let pages: Punctuated<LitStr, Token![,]> =
+                        nested.parse_terminated(<LitStr as Parse>::parse)?;
+                    Ok(SeeAlso(name, Vec::from_iter(pages)))
+                }
+
+                "keybinding" => {
+                    let fields: Punctuated<LitField, Token![,]> =
+                        nested.parse_terminated(LitField::parse)?;
+                    Ok(Keybinding(name, Vec::from_iter(fields)))
+                }
+
+                "example" => {
+                    let fields: Punctuated<LitField, Token![,]> =
+                        nested.parse_terminated(LitField::parse)?;
+                    Ok(Example(name, Vec::from_iter(fields)))
+                }
+
+                "exit_status" => {
+                    let fields: Punctuated<LitField, Token![,]> =
+                        nested.parse_terminated(LitField::parse)?;
+                    Ok(ExitStatus(name, Vec::from_iter(fields)))
+                }
+
+                "env_var" => {
+                    let fields: Punctuated<LitField, Token![,]> =
+                        nested.parse_terminated(LitField::parse)?;
+                    Ok(EnvVar(name, Vec::from_iter(fields)))
+                }
+
+                "diagnostic" => {
+                    let fields: Punctuated<LitField, Token![,]> =
+                        nested.parse_terminated(LitField::parse)?;
+                    Ok(Diagnostic(name, Vec::from_iter(fields)))
+                }
+
+                "file" => {
+                    let fields: Punctuated<LitField, Token![,]> =
+                        nested.parse_terminated(LitField::parse)?;
+                    Ok(File(name, Vec::from_iter(fields)))
+                }
+
+                "definition" => {
+                    let fields: Punctuated<LitField, Token![,]> =
+                        nested.parse_terminated(LitField::parse)?;
+                    Ok(Definition(name, Vec::from_iter(fields)))
+                }
+
+                "subsection" => {
+                    let fields: Punctuated<LitField, Token![,]> =
+                        nested.parse_terminated(LitField::parse)?;
+                    Ok(Subsection(name, Vec::from_iter(fields)))
+                }
+
+                "value" => {
+                    let fields: Punctuated<LitField, Token![,]> =
+                        nested.parse_terminated(LitField::parse)?;
+                    Ok(Value(name, Vec::from_iter(fields)))
+                }
+
+                "settings" => {
+                    let sources: Punctuated<NestedMeta, Token![,]> =
+                        nested.parse_terminated(NestedMeta::parse)?;
+                    Ok(Settings(name, Vec::from_iter(sources)))
+                }
+
+                "front_matter" => {
+                    let fields: Punctuated<NestedMeta, Token![,]> =
+                        nested.parse_terminated(NestedMeta::parse)?;
+                    Ok(FrontMatter(name, Vec::from_iter(fields)))
+                }
+
+                "options_list" => {
+                    let fields: Punctuated<NestedMeta, Token![,]> =
+                        nested.parse_terminated(NestedMeta::parse)?;
+                    Ok(OptionsList(name, Vec::from_iter(fields)))
+                }
+
+                "subcommands_list" => {
+                    let fields: Punctuated<NestedMeta, Token![,]> =
+                        nested.parse_terminated(NestedMeta::parse)?;
+                    Ok(SubcommandsList(name, Vec::from_iter(fields)))
+                }
+
+                _ => abort!(name, "unexpected manpage attribute: {}", name_str),
+            }
+        } else {
+            // Attributes represented with a sole identifier.
+            match name_str.as_ref() {
+                "hardened" => Ok(Hardened(name)),
+                "no_provenance" => Ok(NoProvenance(name)),
+                "no_builtin_flags" => Ok(NoBuiltinFlags(name)),
+                "std_exit_status" => Ok(StdExitStatus(name)),
+                "stable" => Ok(Stable(name)),
+                "featured" => Ok(Featured(name)),
+                "negatable" => Ok(Negatable(name)),
+                "compress" => Ok(Compress(name)),
+                "escape_hyphens" => Ok(EscapeHyphens(name)),
+                "subcommand_pages" => Ok(SubcommandPages(name)),
+                "utf8" => Ok(Utf8(name)),
+                "skip" => Ok(Skip(name)),
+
+                _ => abort!(name, "unexpected manpage attribute: {}", name_str),
+            }
+        }
+    }
+}
+
+/// Warns that the manpage attribute `name` is not understood, so the page can still be written.
 #[derive(Clone)]
+#[allow(dead_code)]
 pub struct ParserSpec {
     pub kind: Ident,
     pub eq_token: Option<Token![=]>,
@@ -235,11 +525,11 @@ impl Parse for ParserSpec {
     }
 }
 
-fn raw_method_suggestion(ts: ParseBuffer) -> String {
-    let do_parse = move || -> Result<(Ident, Punctuated<Expr, Token![,]>), syn::Error> {
-        let name = ts.parse()?;
-        let _eq: Token![=] = ts.parse()?;
-        let val: LitStr = ts.parse()?;
+fn raw_method_suggestion(ts: TokenStream) -> String {
+    let do_parse = |input: ParseStream<'_>| -> syn::Result<(Ident, Punctuated<Expr, Token![,]>)> {
+        let name = input.parse()?;
+        let _eq: Token![=] = input.parse()?;
+        let val: LitStr = input.parse()?;
         let exprs = val.parse_with(Punctuated::<Expr, Token![,]>::parse_terminated)?;
         Ok((name, exprs))
     };
@@ -251,7 +541,7 @@ fn raw_method_suggestion(ts: ParseBuffer) -> String {
             .replace(",", ", ")
     }
 
-    if let Ok((name, exprs)) = do_parse() {
+    if let Ok((name, exprs)) = do_parse.parse2(ts) {
         let suggestion = if exprs.len() == 1 {
             let val = to_string(&exprs[0]);
             format!(" = {}", val)
@@ -288,3 +578,14 @@ pub fn parse_structopt_attributes(all_attrs: &[Attribute]) -> Vec<StructOptAttr>
         })
         .collect()
 }
+
+pub fn parse_manpage_attributes(all_attrs: &[Attribute]) -> Vec<ManpageAttr> {
+    all_attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("manpage"))
+        .flat_map(|attr| {
+            attr.parse_args_with(Punctuated::<ManpageAttr, Token![,]>::parse_terminated)
+                .unwrap_or_abort()
+        })
+        .collect()
+}
@@ -0,0 +1,55 @@
+// Copyright 2022 Manos Pitsidianakis <epilys@nessuent.net>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Warnings that show on stable compilers.
+//!
+//! `proc_macro_error::emit_warning!` does nothing outside nightly, so each warning is expanded to
+//! the use of a deprecated constant instead, which rustc reports at the span of the use.
+
+use std::cell::RefCell;
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote_spanned;
+
+thread_local! {
+    /// The warnings of the derive being expanded, with the span and message each was emitted
+    /// with.
+    static WARNINGS: RefCell<Vec<(String, TokenStream)>> = RefCell::new(vec![]);
+}
+
+/// Warn with `message` at `span`, which has to be the span of tokens the user wrote: rustc
+/// keeps quiet about lints at spans of the derive itself. The attributes of an item are parsed
+/// more than once, so a warning already emitted at the same span is dropped.
+pub fn emit(span: Span, message: String) {
+    let key = format!("{:?} {}", span, message);
+    let warning = quote_spanned! { span=>
+        const _: () = {
+            #[deprecated(note = #message)]
+            #[allow(non_upper_case_globals)]
+            const manpage: () = ();
+            manpage
+        };
+    };
+    WARNINGS.with(|warnings| {
+        let mut warnings = warnings.borrow_mut();
+        if warnings.iter().all(|(emitted, _)| *emitted != key) {
+            warnings.push((key, warning));
+        }
+    });
+}
+
+/// The warnings emitted since the last call, as items to add to the output of the derive.
+pub fn take() -> TokenStream {
+    WARNINGS.with(|warnings| {
+        warnings
+            .borrow_mut()
+            .drain(..)
+            .map(|(_, warning)| warning)
+            .collect()
+    })
+}
@@ -0,0 +1,899 @@
+//! Pages written by the derive itself, for real structs and enums.
+//!
+//! The golden tests render hand-built models; these check what the derive puts in them. Each
+//! page is written to `tests/derived` when this file is compiled.
+
+// The `paw` impl this crate derives with its `paw` feature needs structopt's `paw` feature too.
+#![cfg(not(feature = "paw"))]
+
+use std::fs;
+use std::path::PathBuf;
+use structopt::clap::ArgGroup;
+use structopt::StructOpt;
+
+/// The page written to `tests/derived/name`.
+fn written(name: &str) -> String {
+    let path = format!("{}/tests/derived/{}", env!("CARGO_MANIFEST_DIR"), name);
+    fs::read_to_string(&path).unwrap_or_else(|err| panic!("couldn't read {}: {}", path, err))
+}
+
+/// Whether `page` has `lines` one after the other.
+fn has_lines(page: &str, lines: &[&str]) -> bool {
+    format!("\n{}\n", page).contains(&format!("\n{}\n", lines.join("\n")))
+}
+
+/// Fetch things from somewhere.
+#[derive(StructOpt)]
+#[structopt(
+    name = "fetch",
+    manpage = "tests/derived/fetch.mdoc",
+    manpage_header = "tests/derived/fetch.header.mdoc",
+    manpage_footer = "tests/derived/fetch.footer.mdoc"
+)]
+struct Fetch {
+    /// Where to write to.
+    #[structopt(long, parse(from_os_str))]
+    output: Option<PathBuf>,
+    /// What to fetch.
+    source: String,
+}
+
+#[derive(StructOpt)]
+#[structopt(name = "quiet", manpage_header = "tests/derived/quiet.header.mdoc")]
+struct Quiet {
+    /// Print nothing.
+    #[structopt(short)]
+    silent: bool,
+}
+
+/// Serve things.
+#[derive(StructOpt)]
+#[structopt(
+    name = "served",
+    manpage_header = "tests/derived/served.header.mdoc",
+    manpage_footer = "tests/derived/served.footer.mdoc"
+)]
+#[manpage(section = "8")]
+struct Served {
+    /// Read settings from here.
+    #[structopt(long, default_value = "/etc/served.conf")]
+    #[manpage(under = "FILES")]
+    config: String,
+}
+
+/// Connect to a server.
+#[derive(StructOpt)]
+#[structopt(
+    name = "connect",
+    manpage = "tests/derived/connect.mdoc",
+    manpage_footer = "tests/derived/connect.footer.mdoc"
+)]
+struct Connect {
+    /// The server to connect to.
+    #[structopt(long, env = "CONNECT_HOST")]
+    host: String,
+    /// Connect again when the connection drops.
+    #[structopt(long, visible_alias = "retry", alias = "reconnect")]
+    redial: bool,
+}
+
+/// Log in.
+#[derive(StructOpt)]
+#[structopt(name = "login", manpage = "tests/derived/login.mdoc")]
+struct Login {
+    /// Log in as this user.
+    #[structopt(long, requires = "password")]
+    user: Option<String>,
+    /// The password of the user.
+    #[structopt(long)]
+    password: Option<String>,
+    /// Log in without a user.
+    #[structopt(long, conflicts_with = "user")]
+    guest: bool,
+    /// The token to log in with.
+    #[structopt(long, required_unless = "guest")]
+    token: Option<String>,
+    /// The realm to log in to.
+    #[structopt(long, required_if("user", "root"))]
+    realm: Option<String>,
+}
+
+/// Plot points.
+#[derive(StructOpt)]
+#[structopt(name = "plot", manpage = "tests/derived/plot.mdoc")]
+struct Plot {
+    /// Where to put the origin.
+    #[structopt(long, value_names = &["X", "Y"])]
+    origin: Vec<f64>,
+    /// The points to plot.
+    points: Vec<String>,
+    /// Options for the plotter.
+    #[structopt(last = true)]
+    plotter: Vec<String>,
+}
+
+/// Trim lines.
+#[derive(StructOpt)]
+#[structopt(name = "trim", manpage = "tests/derived/trim.mdoc")]
+struct Trim {
+    /// Trim the start of lines too.
+    #[structopt(long)]
+    start: bool,
+    /// Trim internals.
+    #[structopt(long)]
+    #[manpage(skip)]
+    internals: bool,
+    #[structopt(skip)]
+    count: usize,
+}
+
+/// Release a crate.
+#[derive(StructOpt)]
+#[structopt(
+    name = "release",
+    manpage_header = "tests/derived/release.header.mdoc",
+    manpage_footer = "tests/derived/release.footer.mdoc"
+)]
+#[manpage(
+    std_exit_status,
+    exit_status(code = "2", doc = "The crate is already released."),
+    env_var(name = "RELEASE_TOKEN", doc = "The token to publish with."),
+    example(cmd = "release --dry-run", desc = "See what would be released."),
+    see_also("cargo(1)"),
+    bugs = "https://example.com/release/issues",
+    date = "May 1, 2022",
+    os = "Release 1.0"
+)]
+struct Release {
+    /// Only show what would be released.
+    #[structopt(long)]
+    dry_run: bool,
+}
+
+/// Launch programs.
+#[derive(StructOpt)]
+#[structopt(name = "launch", manpage = "tests/derived/launch.mdoc")]
+enum Launch {
+    /// Run the program.
+    #[structopt(visible_alias = "r", alias = "start")]
+    Run,
+    #[structopt(external_subcommand)]
+    Other(Vec<String>),
+}
+
+/// Say more.
+#[derive(StructOpt)]
+#[structopt(name = "loud", manpage = "tests/derived/loud.mdoc")]
+struct Loud {
+    /// Print more.
+    #[structopt(short, long)]
+    verbose: bool,
+    /// Print internals.
+    #[structopt(long, hidden = true)]
+    trace: bool,
+}
+
+/// Convert a file.
+#[derive(StructOpt)]
+#[structopt(name = "convert", manpage = "tests/derived/convert.mdoc")]
+struct Convert {
+    /// Read settings from here.
+    #[structopt(long, value_name = "FILE")]
+    config: Option<String>,
+    /// The file to convert.
+    #[structopt(value_name = "SOURCE")]
+    input: String,
+    /// How hard to try.
+    #[structopt(long, possible_values = &["fast", "best"], case_insensitive = true)]
+    #[manpage(value(name = "BEST", doc = "Take as long as it takes."))]
+    effort: Option<String>,
+}
+
+/// Build and clean.
+#[derive(StructOpt)]
+#[structopt(name = "tool", manpage = "tests/derived/tool.mdoc")]
+enum Tool {
+    /// Build the project.
+    Build {
+        /// Build with optimizations.
+        #[structopt(long)]
+        release: bool,
+    },
+    /// Remove the build artifacts.
+    Clean,
+}
+
+/// Buy and browse.
+#[derive(StructOpt)]
+#[structopt(
+    name = "shop",
+    manpage = "tests/derived/shop.mdoc",
+    manpage_footer = "tests/derived/shop.footer.mdoc"
+)]
+#[manpage(subcommand_pages)]
+enum Shop {
+    /// Buy things.
+    Buy {
+        /// How many to buy.
+        #[structopt(long)]
+        quantity: Option<u32>,
+    },
+    /// Look around.
+    Browse,
+}
+
+/// Keep track of changes.
+#[derive(StructOpt)]
+#[structopt(name = "track", manpage = "tests/derived/track.mdoc")]
+struct Track {
+    /// What to do with the changes.
+    #[structopt(subcommand)]
+    cmd: TrackCmd,
+}
+
+#[derive(StructOpt)]
+enum TrackCmd {
+    /// Record the changes.
+    Commit,
+}
+
+/// Work with a repository.
+#[derive(StructOpt)]
+#[structopt(name = "vcs", manpage = "tests/derived/vcs.mdoc")]
+struct Vcs {
+    /// Print more.
+    #[structopt(short, long, global = true)]
+    verbose: bool,
+    /// What to do with the repository.
+    #[structopt(subcommand)]
+    cmd: TrackCmd,
+}
+
+/// Look at a repository, or change it.
+#[derive(StructOpt)]
+#[structopt(name = "peek", manpage = "tests/derived/peek.mdoc")]
+struct Peek {
+    /// What to do instead of looking.
+    #[structopt(subcommand)]
+    cmd: Option<TrackCmd>,
+}
+
+/// Sort lines.
+#[derive(StructOpt)]
+#[structopt(
+    name = "sorter",
+    manpage = "tests/derived/sorter.mdoc",
+    group = ArgGroup::with_name("mode")
+)]
+struct Sorter {
+    /// Sort quickly.
+    #[structopt(long, group = "mode")]
+    fast_mode: bool,
+    /// Sort slowly.
+    #[structopt(long, group = "mode", conflicts_with = "level")]
+    slow: bool,
+    /// How thoroughly to sort.
+    #[structopt(short, long)]
+    level: Option<u8>,
+}
+
+/// Rank lines.
+#[derive(StructOpt)]
+#[structopt(
+    name = "ranker",
+    manpage = "tests/derived/ranker.mdoc",
+    group = ArgGroup::with_name("mode").required(true)
+)]
+struct Ranker {
+    /// Rank quickly.
+    #[structopt(long, group = "mode")]
+    fast_mode: bool,
+    /// Rank slowly.
+    #[structopt(long, group = "mode")]
+    slow: bool,
+    /// Rank at a fixed level instead.
+    #[structopt(short, long, conflicts_with_all = &["fast-mode", "slow"])]
+    level: Option<u8>,
+}
+
+/// Pick lines.
+#[derive(StructOpt)]
+#[structopt(
+    name = "picker",
+    manpage = "tests/derived/picker.mdoc",
+    group = ArgGroup::with_name("mode").required(true)
+)]
+struct Picker {
+    /// Pick the first line.
+    #[structopt(long, group = "mode")]
+    first: bool,
+    /// Pick the last line.
+    #[structopt(long, group = "mode")]
+    last: bool,
+}
+
+#[test]
+fn struct_header() {
+    let page = written("fetch.header.mdoc");
+    assert!(
+        has_lines(
+            &page,
+            &[
+                ".Dd $Mdocdate$",
+                ".Dt FETCH 1",
+                ".Os",
+                ".Sh NAME",
+                ".Nm fetch",
+                ".Nd Fetch things from somewhere",
+            ]
+        ),
+        "{}",
+        page
+    );
+}
+
+#[test]
+fn package_description() {
+    assert!(Quiet::from_iter(&["quiet", "-s"]).silent);
+
+    let page = written("quiet.header.mdoc");
+    let description = env!("CARGO_PKG_DESCRIPTION").trim_end_matches('.');
+    assert!(
+        has_lines(&page, &[".Nm quiet", &format!(".Nd {}", description)]),
+        "{}",
+        page
+    );
+}
+
+#[test]
+fn sections() {
+    assert_eq!(Served::from_iter(&["served"]).config, "/etc/served.conf");
+
+    let page = written("served.header.mdoc");
+    assert!(has_lines(&page, &[".Dt SERVED 8"]), "{}", page);
+    let page = written("served.footer.mdoc");
+    assert!(
+        has_lines(
+            &page,
+            &[
+                ".Sh FILES",
+                ".Bl -tag -width Ds",
+                ".It Pa /etc/served.conf",
+                "Read settings from here.",
+                ".El",
+            ]
+        ),
+        "{}",
+        page
+    );
+}
+
+#[test]
+fn provenance() {
+    let comment = ".\\\" Generated by structopt-derive-manpage";
+    let page = written("fetch.header.mdoc");
+    assert!(page.starts_with(comment), "{}", page);
+    for name in &["fetch.mdoc", "fetch.footer.mdoc"] {
+        let page = written(name);
+        assert!(!page.contains(comment), "{}", page);
+    }
+    let page = written("shop-buy.1");
+    assert_eq!(page.matches(comment).count(), 1, "{}", page);
+}
+
+#[test]
+fn struct_body() {
+    let opt = Fetch::from_iter(&["fetch", "--output", "out", "src"]);
+    assert_eq!(opt.output, Some(PathBuf::from("out")));
+    assert_eq!(opt.source, "src");
+
+    let page = written("fetch.mdoc");
+    assert!(
+        has_lines(
+            &page,
+            &[
+                ".Nm",
+                ".Bk -words",
+                ".Op Fl -output Pa OUTPUT",
+                ".Op Fl h , Fl -help",
+                ".Op Fl V , Fl -version",
+                ".Ar SOURCE",
+                ".Ek",
+            ]
+        ),
+        "{}",
+        page
+    );
+    assert!(
+        has_lines(&page, &[".It Fl -output Pa OUTPUT", "Where to write to."]),
+        "{}",
+        page
+    );
+    assert!(
+        has_lines(&page, &[".It Ar SOURCE", "What to fetch."]),
+        "{}",
+        page
+    );
+}
+
+#[test]
+fn short_and_long() {
+    let opt = Loud::from_iter(&["loud", "-v", "--trace"]);
+    assert!(opt.verbose && opt.trace);
+
+    let page = written("loud.mdoc");
+    assert!(has_lines(&page, &[".Op Fl v , Fl -verbose"]), "{}", page);
+    assert!(
+        has_lines(&page, &[".It Fl v , Fl -verbose", "Print more."]),
+        "{}",
+        page
+    );
+    assert!(!page.contains("trace"), "{}", page);
+}
+
+#[test]
+fn value_name() {
+    let opt = Convert::from_iter(&["convert", "--config", "settings", "in"]);
+    assert_eq!(opt.config.as_deref(), Some("settings"));
+    assert_eq!(opt.input, "in");
+    assert_eq!(opt.effort, None);
+
+    let page = written("convert.mdoc");
+    assert!(has_lines(&page, &[".Op Fl -config Ar FILE"]), "{}", page);
+    assert!(has_lines(&page, &[".Ar SOURCE", ".Ek"]), "{}", page);
+    assert!(
+        has_lines(
+            &page,
+            &[".It Fl -config Ar FILE", "Read settings from here."]
+        ),
+        "{}",
+        page
+    );
+    assert!(
+        has_lines(&page, &[".It Ar SOURCE", "The file to convert."]),
+        "{}",
+        page
+    );
+}
+
+#[test]
+fn documented_values() {
+    let opt = Convert::from_iter(&["convert", "--effort", "FAST", "in"]);
+    assert_eq!(opt.effort.as_deref(), Some("FAST"));
+
+    let page = written("convert.mdoc");
+    assert!(
+        has_lines(
+            &page,
+            &[
+                "How hard to try.",
+                "Can be one of:",
+                ".Bl -tag -width Ds -compact",
+                ".It Ql fast",
+                ".It Ql best",
+                "Take as long as it takes.",
+                ".El",
+            ]
+        ),
+        "{}",
+        page
+    );
+}
+
+#[test]
+fn enum_subcommands() {
+    match Tool::from_iter(&["tool", "build", "--release"]) {
+        Tool::Build { release } => assert!(release),
+        Tool::Clean => panic!("parsed `build` as `clean`"),
+    }
+
+    let page = written("tool.mdoc");
+    assert!(
+        has_lines(&page, &[".Cm build", ".Op Fl -release", ".Ek"]),
+        "{}",
+        page
+    );
+    assert!(
+        has_lines(
+            &page,
+            &[
+                ".It Ic build",
+                ".Fl -release",
+                "Build with optimizations.",
+                "Build the project.",
+                ".It Ic clean",
+                "Remove the build artifacts.",
+            ]
+        ),
+        "{}",
+        page
+    );
+}
+
+#[test]
+fn subcommand_pages() {
+    match Shop::from_iter(&["shop", "buy", "--quantity", "2"]) {
+        Shop::Buy { quantity } => assert_eq!(quantity, Some(2)),
+        Shop::Browse => panic!("parsed `buy` as `browse`"),
+    }
+
+    let page = written("shop.footer.mdoc");
+    assert!(
+        has_lines(
+            &page,
+            &[
+                ".It Ic buy",
+                "Buy things.",
+                "See",
+                ".Xr shop-buy 1 .",
+                ".It Ic browse",
+                "Look around.",
+                "See",
+                ".Xr shop-browse 1 .",
+            ]
+        ),
+        "{}",
+        page
+    );
+    let page = written("shop.mdoc");
+    assert!(!page.contains("--quantity"), "{}", page);
+
+    let page = written("shop-buy.1");
+    assert!(has_lines(&page, &[".Sh NAME", ".Nm shop-buy"]), "{}", page);
+    assert!(
+        has_lines(
+            &page,
+            &[
+                ".Sh SYNOPSIS",
+                ".Nm shop",
+                ".Cm buy",
+                ".Bk -words",
+                ".Op Fl -quantity Ar QUANTITY",
+                ".Ek",
+                ".Sh DESCRIPTION",
+            ]
+        ),
+        "{}",
+        page
+    );
+    assert!(
+        has_lines(&page, &[".Sh SEE ALSO", ".Xr shop 1"]),
+        "{}",
+        page
+    );
+    let page = written("shop-browse.1");
+    assert!(has_lines(&page, &[".Nm shop", ".Cm browse"]), "{}", page);
+}
+
+#[test]
+fn subcommand_field() {
+    match Track::from_iter(&["track", "commit"]).cmd {
+        TrackCmd::Commit => {}
+    }
+
+    let page = written("track.mdoc");
+    assert!(has_lines(&page, &[".Cm cmd", ".Ek"]), "{}", page);
+    assert!(
+        has_lines(&page, &[".It Ic cmd", "What to do with the changes."]),
+        "{}",
+        page
+    );
+}
+
+#[test]
+fn global_flags() {
+    let opt = Vcs::from_iter(&["vcs", "commit", "--verbose"]);
+    assert!(opt.verbose);
+    match opt.cmd {
+        TrackCmd::Commit => {}
+    }
+
+    let page = written("vcs.mdoc");
+    assert!(
+        has_lines(
+            &page,
+            &[
+                ".It Ic cmd",
+                "What to do with the repository.",
+                "Also takes the global option",
+                ".Fl -verbose .",
+            ]
+        ),
+        "{}",
+        page
+    );
+}
+
+#[test]
+fn optional_subcommand() {
+    assert!(Peek::from_iter(&["peek"]).cmd.is_none());
+
+    let page = written("peek.mdoc");
+    assert!(
+        has_lines(&page, &[".Oo", ".Cm cmd", ".Oc", ".Ek"]),
+        "{}",
+        page
+    );
+    assert!(!has_lines(&written("track.mdoc"), &[".Oo"]));
+}
+
+#[test]
+fn alternations() {
+    assert!(Sorter::from_iter_safe(&["sorter", "--fast-mode", "--slow"]).is_err());
+    assert!(Sorter::from_iter_safe(&["sorter", "--slow", "-l", "1"]).is_err());
+    let opt = Sorter::from_iter(&["sorter", "-l", "1"]);
+    assert!(!opt.fast_mode && !opt.slow);
+    assert_eq!(opt.level, Some(1));
+
+    let page = written("sorter.mdoc");
+    assert!(
+        has_lines(
+            &page,
+            &[
+                ".Bk -words",
+                ".Op Fl -fast-mode | Fl -slow",
+                ".Op Fl l , Fl -level Ar LEVEL",
+            ]
+        ),
+        "{}",
+        page
+    );
+}
+
+#[test]
+fn required_groups() {
+    assert!(Picker::from_iter_safe(&["picker"]).is_err());
+    let opt = Picker::from_iter(&["picker", "--last"]);
+    assert!(!opt.first && opt.last);
+
+    let page = written("picker.mdoc");
+    assert!(
+        has_lines(&page, &[".Bk -words", ".Fl -first | Fl -last"]),
+        "{}",
+        page
+    );
+
+    let opt = Ranker::from_iter(&["ranker", "--slow"]);
+    assert!(!opt.fast_mode && opt.slow && opt.level.is_none());
+
+    let page = written("ranker.mdoc");
+    assert!(
+        has_lines(
+            &page,
+            &[
+                ".Bk -words",
+                ".Op Fl -fast-mode | Fl -slow | Fl l , Fl -level Ar LEVEL",
+            ]
+        ),
+        "{}",
+        page
+    );
+}
+
+#[test]
+fn env_and_aliases() {
+    std::env::set_var("CONNECT_HOST", "example.com");
+    let opt = Connect::from_iter(&["connect", "--reconnect"]);
+    assert_eq!(opt.host, "example.com");
+    assert!(opt.redial);
+    assert!(Connect::from_iter(&["connect", "--retry"]).redial);
+
+    let page = written("connect.mdoc");
+    assert!(
+        has_lines(
+            &page,
+            &[
+                ".It Fl -host Ar HOST",
+                "The server to connect to.",
+                "May also be set with the",
+                ".Ev CONNECT_HOST",
+                "environment variable.",
+                ".It Fl -redial , Fl -retry",
+                "Connect again when the connection drops.",
+                "Also known as",
+                ".Fl -reconnect .",
+            ]
+        ),
+        "{}",
+        page
+    );
+    let page = written("connect.footer.mdoc");
+    assert!(
+        has_lines(
+            &page,
+            &[
+                ".Sh ENVIRONMENT",
+                ".Bl -tag -width Ds",
+                ".It Ev CONNECT_HOST",
+                "The server to connect to.",
+                "Overridden by",
+                ".Fl -host .",
+                ".El",
+            ]
+        ),
+        "{}",
+        page
+    );
+}
+
+#[test]
+fn requirements() {
+    assert!(Login::from_iter_safe(&["login", "--user", "me", "--token", "t"]).is_err());
+    assert!(Login::from_iter_safe(&["login", "--guest", "--user", "me"]).is_err());
+    assert!(Login::from_iter_safe(&["login"]).is_err());
+    let root = &["login", "--user", "root", "--password", "p", "--token", "t"];
+    assert!(Login::from_iter_safe(&root[..]).is_err());
+    let opt = Login::from_iter(&["login", "--guest"]);
+    assert!(opt.guest && opt.user.is_none() && opt.password.is_none());
+    let opt = Login::from_iter(&[
+        "login",
+        "--user",
+        "root",
+        "--password",
+        "p",
+        "--token",
+        "t",
+        "--realm",
+        "home",
+    ]);
+    assert_eq!(opt.realm.as_deref(), Some("home"));
+    let opt = Login::from_iter(&["login", "--token", "t"]);
+    assert_eq!(opt.token.as_deref(), Some("t"));
+
+    let page = written("login.mdoc");
+    for lines in &[
+        &[
+            ".It Fl -user Ar USER",
+            "Log in as this user.",
+            "Cannot be combined with",
+            ".Fl -guest .",
+            "Requires",
+            ".Fl -password .",
+        ][..],
+        &[
+            ".It Fl -token Ar TOKEN",
+            "The token to log in with.",
+            "Required unless",
+            ".Fl -guest",
+            "is given.",
+        ],
+        &[
+            ".It Fl -realm Ar REALM",
+            "The realm to log in to.",
+            "Required when",
+            ".Fl -user",
+            "is",
+            ".Ql root .",
+        ],
+    ] {
+        assert!(has_lines(&page, lines), "{}", page);
+    }
+}
+
+#[test]
+fn value_names_and_last() {
+    let opt = Plot::from_iter(&["plot", "--origin", "1", "2", "a", "--", "-x"]);
+    assert_eq!(opt.origin, vec![1.0, 2.0]);
+    assert_eq!(opt.points, vec!["a"]);
+    assert_eq!(opt.plotter, vec!["-x"]);
+
+    let page = written("plot.mdoc");
+    assert!(
+        has_lines(
+            &page,
+            &[".Op Ar POINTS ...", ".Op Fl - Ar PLOTTER ...", ".Ek"]
+        ),
+        "{}",
+        page
+    );
+    assert!(
+        has_lines(
+            &page,
+            &[".It Fl -origin Ar X Ar Y ...", "Where to put the origin.",]
+        ),
+        "{}",
+        page
+    );
+    assert!(
+        has_lines(
+            &page,
+            &[
+                ".It Ar PLOTTER ...",
+                "Options for the plotter.",
+                "Only given after",
+                ".Fl - ,",
+            ]
+        ),
+        "{}",
+        page
+    );
+}
+
+#[test]
+fn skipped() {
+    let opt = Trim::from_iter(&["trim", "--internals"]);
+    assert!(opt.internals && !opt.start);
+    assert_eq!(opt.count, 0);
+
+    let page = written("trim.mdoc");
+    assert!(has_lines(&page, &[".Op Fl -start"]), "{}", page);
+    assert!(!page.contains("internals"), "{}", page);
+    assert!(!page.contains("count"), "{}", page);
+}
+
+#[test]
+fn manpage_keys() {
+    assert!(Release::from_iter(&["release", "--dry-run"]).dry_run);
+
+    let page = written("release.header.mdoc");
+    assert!(
+        has_lines(
+            &page,
+            &[".Dd May 1, 2022", ".Dt RELEASE 1", ".Os Release 1.0"]
+        ),
+        "{}",
+        page
+    );
+    let page = written("release.footer.mdoc");
+    for lines in &[
+        &[
+            ".Sh ENVIRONMENT",
+            ".Bl -tag -width Ds",
+            ".It Ev RELEASE_TOKEN",
+            "The token to publish with.",
+            ".El",
+        ][..],
+        &[
+            ".Sh EXIT STATUS",
+            ".Ex -std",
+            ".Bl -tag -width Ds",
+            ".It 2",
+            "The crate is already released.",
+            ".El",
+        ],
+        &[
+            ".Sh EXAMPLES",
+            "See what would be released:",
+            ".Pp",
+            ".Dl release --dry-run",
+        ],
+        &[".Sh SEE ALSO", ".Xr cargo 1"],
+        &[
+            ".Sh BUGS",
+            "Report bugs to",
+            ".Lk https://example.com/release/issues .",
+        ],
+    ] {
+        assert!(has_lines(&page, lines), "{}", page);
+    }
+}
+
+#[test]
+fn external_subcommands() {
+    match Launch::from_iter(&["launch", "r"]) {
+        Launch::Run => {}
+        Launch::Other(args) => panic!("{:?}", args),
+    }
+    match Launch::from_iter(&["launch", "build", "-j"]) {
+        Launch::Other(args) => assert_eq!(args, vec!["build", "-j"]),
+        Launch::Run => panic!(),
+    }
+
+    let page = written("launch.mdoc");
+    assert!(
+        has_lines(
+            &page,
+            &[
+                ".It Ic run , Ic r",
+                "Run the program.",
+                "Also known as",
+                ".Ic start .",
+                ".El",
+                ".Pp",
+                "Any other command is passed on to an external program, with its arguments.",
+            ]
+        ),
+        "{}",
+        page
+    );
+}
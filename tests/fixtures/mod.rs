@@ -0,0 +1,588 @@
+// Copyright 2022 Manos Pitsidianakis <epilys@nessuent.net>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Reference manpage models and their expected renderings.
+//!
+//! Every fixture is a [`Manpage`] built through the same builder API the derive uses. The
+//! expected output of a renderer for fixture `name` lives next to this file as
+//! `name.<extension>`. Renderer authors include this module from their own test file and call
+//! [`run`] with the extension of their format and a rendering closure:
+//!
+//! ```ignore
+//! #[path = "../src/manpage/mod.rs"]
+//! mod manpage;
+//! mod fixtures;
+//!
+//! #[test]
+//! fn mdoc() {
+//!     fixtures::run("mdoc", fixtures::render).unwrap();
+//! }
+//! ```
+//!
+//! Set `STRUCTOPT_BLESS=1` to (re)write the expected files from the current output instead of
+//! comparing against them.
+
+use crate::manpage::{
+    ArgGroup, Definition, Example, ExitStatus, Flag, Format, FrontMatter, FrontMatterStyle,
+    Keybinding, ListStyle, Manpage, OptionOrder, PossibleValue, SeeAlso, Settings, Subsection,
+    TakesValue, ValueKind, ValueNames,
+};
+
+use std::fs;
+use std::path::PathBuf;
+
+pub struct Fixture {
+    pub name: &'static str,
+    pub page: Manpage,
+}
+
+fn flag(long: Option<&str>, short: Option<&str>, args: Option<TakesValue>, doc: &str) -> Flag {
+    let mut flag = Flag::new();
+    if let Some(long) = long {
+        flag.long(long.to_string());
+    }
+    if let Some(short) = short {
+        flag.short(short.to_string());
+    }
+    if let Some(args) = args {
+        flag.args(args);
+    }
+    flag.doc(doc.to_string());
+    flag
+}
+
+fn operand(name: &str, multiple: bool, doc: &str) -> Flag {
+    let mut flag = flag(None, None, value(multiple), doc);
+    flag.positional(name.to_string());
+    flag
+}
+
+fn value(multiple: bool) -> Option<TakesValue> {
+    Some(TakesValue {
+        kind: None,
+        multiple,
+        names: vec![],
+        number_of_values: None,
+        optional_value: false,
+    })
+}
+
+fn minimal() -> Manpage {
+    let mut page = Manpage::new();
+    page.name("minimal".to_string());
+    page
+}
+
+fn flags() -> Manpage {
+    let mut page = Manpage::new();
+    page.name("flags".to_string())
+        .description(Some("Exercise every kind of flag".to_string()))
+        .author(Some(
+            "Jane Doe <jane@example.com>, John Roe <https://example.com/~roe>".to_string(),
+        ))
+        .bugs(Some("https://example.com/flags/issues".to_string()))
+        .copyright(Some(
+            "flags is licensed under MIT OR Apache-2.0.".to_string(),
+        ))
+        .history(Some(
+            "The flags utility first appeared in version 0.3.".to_string(),
+        ));
+    page.flags = vec![
+        flag(Some("debug"), Some("d"), None, "Activate debug mode."),
+        flag(Some("quiet"), None, None, "Print nothing"),
+        flag(Some("output"), Some("o"), value(false), "Output file."),
+        flag(Some("include"), None, value(true), "Extra include paths."),
+        flag(None, Some("n"), None, "Only print what would be done."),
+        flag(
+            Some("target"),
+            None,
+            value(false),
+            "Where to send the output.",
+        ),
+        operand("input", false, "The input file."),
+        operand("files", true, "More files."),
+    ];
+    page.flags[2]
+        .args(TakesValue {
+            kind: Some(ValueKind::Path),
+            multiple: false,
+            names: vec![],
+            number_of_values: None,
+            optional_value: false,
+        })
+        .value_name("FILE".to_string());
+    page.flags[3]
+        .default_value("/usr/include".to_string())
+        .repeatable(true)
+        .section("FILES".to_string());
+    page.flags[7].value_name("SOURCE".to_string());
+    page.flags[0].id("debug".to_string()).negatable(true);
+    page.flags[1].push_conflict("debug".to_string());
+    page.flags[2]
+        .group("destination".to_string())
+        .push_visible_alias("out".to_string())
+        .push_alias("outfile".to_string());
+    page.flags[5]
+        .push_possible_value("stdout".to_string())
+        .push_possible_value("stderr".to_string())
+        .document_value(PossibleValue::new(
+            "stdout".to_string(),
+            Some("Print to the standard output".to_string()),
+        ));
+    page.flags[5]
+        .id("target".to_string())
+        .env("FLAGS_TARGET".to_string())
+        .required(true)
+        .group("destination".to_string())
+        .section("environment".to_string());
+    page.flags[6]
+        .id("input".to_string())
+        .push_required_unless("files".to_string());
+    page.flags[7].id("files".to_string()).last(true);
+    page.flags[4].push_requirement("input".to_string());
+    page.flags[3].push_required_if("target".to_string(), "stderr".to_string());
+    page.flags[4].id("dry-run".to_string());
+    let mut destination = ArgGroup::new("destination".to_string());
+    destination.required(true);
+    let mut mode = ArgGroup::new("mode".to_string());
+    mode.required(true)
+        .multiple(true)
+        .push_arg("debug".to_string())
+        .push_arg("dry-run".to_string());
+    page.groups = vec![destination, mode];
+    page.options_list(ListStyle {
+        offset: Some("Ds".to_string()),
+        compact: true,
+    });
+    page.front_matter = Some(FrontMatter {
+        title: Some("flags: \"every\" kind".to_string()),
+        ..FrontMatter::default()
+    });
+    page.settings = Some(Settings {
+        environment: true,
+        config_file: Some("~/.config/flags.toml".to_string()),
+    });
+    page
+}
+
+fn subcommands() -> Manpage {
+    let mut build = Manpage::new();
+    build
+        .name("build".to_string())
+        .description(Some("Build the project".to_string()));
+    build.flags = vec![
+        flag(Some("release"), None, None, "Build with optimizations."),
+        flag(
+            Some("jobs"),
+            Some("j"),
+            value(false),
+            "Number of parallel jobs.",
+        ),
+    ];
+    build.flags[1]
+        .args(TakesValue {
+            kind: Some(ValueKind::Number),
+            multiple: false,
+            names: vec![],
+            number_of_values: None,
+            optional_value: false,
+        })
+        .env("BUILD_JOBS".to_string());
+
+    let mut clean = Manpage::new();
+    clean
+        .name("clean".to_string())
+        .description(Some("Remove build artifacts".to_string()));
+    clean.flags = vec![flag(
+        Some("remote"),
+        None,
+        value(false),
+        "Also clear the remote cache.",
+    )];
+    clean.flags[0].args(TakesValue {
+        kind: Some(ValueKind::Url),
+        multiple: false,
+        names: vec![],
+        number_of_values: None,
+        optional_value: false,
+    });
+
+    let mut page = Manpage::new();
+    page.name("subcommands".to_string())
+        .description(Some("A tool with subcommands".to_string()))
+        .push_invocation("sc".to_string())
+        .version(Some("1.0.0".to_string()))
+        .provenance(Some(
+            "structopt-derive-manpage v0.4.18 from struct Cli".to_string(),
+        ));
+    page.sort_options(OptionOrder::Alphabetical)
+        .external_subcommands(true)
+        .optional_subcommand(true);
+    page.flags = vec![
+        flag(
+            Some("version"),
+            Some("V"),
+            None,
+            "Print version information.",
+        ),
+        flag(Some("verbose"), Some("v"), None, "Be verbose."),
+        flag(Some("color"), None, value(false), "When to use colors."),
+    ];
+    page.flags[1].featured(true).repeatable(true).global(true);
+    page.flags[2]
+        .args(TakesValue {
+            kind: None,
+            multiple: false,
+            names: vec![],
+            number_of_values: None,
+            optional_value: true,
+        })
+        .value_name("WHEN".to_string());
+    page.front_matter = Some(FrontMatter {
+        style: FrontMatterStyle::Toml,
+        date: Some("2022-03-01".to_string()),
+        section: Some("reference".to_string()),
+        ..FrontMatter::default()
+    });
+    page.subcommands_list(ListStyle {
+        offset: None,
+        compact: false,
+    });
+    build.featured(true);
+    page.push_subcommand(build);
+    page.push_subcommand(clean);
+    page.subcommands[0].push_visible_alias("b".to_string());
+    page.subcommands[1].push_alias("distclean".to_string());
+    page
+}
+
+/// The subcommands fixture, with each subcommand documented in a page of its own.
+fn commands() -> Manpage {
+    let mut page = subcommands();
+    page.name("commands".to_string()).subcommand_pages(true);
+    for cmd in page.subcommands.iter_mut() {
+        cmd.own_page(true);
+    }
+    page
+}
+
+fn stable() -> Manpage {
+    let mut page = Manpage::new();
+    page.name("stable".to_string())
+        .description(Some("Lay out prose for minimal diffs".to_string()))
+        .long_description(Some(
+            "Lay out prose for minimal diffs.\n\nEach sentence starts a line of its own. A change to one\nsentence then changes one line.".to_string(),
+        ))
+        .stable(true)
+        .tag_width(Some("Ds".to_string()))
+        .value_names(ValueNames::Verbatim)
+        .date(Some("May 1, 2022".to_string()));
+    page.flags = vec![
+        flag(
+            Some("config"),
+            Some("c"),
+            value(false),
+            "Read settings from the given file. The file is\nlooked up in the current directory (e.g. ./tool.toml) first.\n\nMissing files are ignored.",
+        ),
+        flag(Some("dry-run"), None, None, "Do nothing! Print what would happen?"),
+    ];
+    page.flags[1].heading("Safety options".to_string());
+    page.subsections = vec![
+        Subsection::new(
+            "Configuration files".to_string(),
+            "Files are read in order. Later files win.\n\nUnknown keys are an error.".to_string(),
+        ),
+        Subsection::new(
+            "Exit status".to_string(),
+            "Zero on success, one otherwise.".to_string(),
+        ),
+    ];
+    page
+}
+
+fn keybindings() -> Manpage {
+    let mut page = Manpage::new();
+    page.name("keybindings".to_string())
+        .description(Some("An interactive tool".to_string()))
+        .long_description(Some(
+            "An interactive tool.\n\nEach key runs an action.".to_string(),
+        ));
+    let mut quit = Keybinding::new("q".to_string());
+    quit.action("quit".to_string())
+        .doc("Exit the program.".to_string());
+    let mut help = Keybinding::new("?".to_string());
+    help.doc("Show the help screen.".to_string());
+    page.keybindings = vec![quit, help];
+    page
+}
+
+fn definitions() -> Manpage {
+    let mut page = Manpage::new();
+    page.hardened(true)
+        .name("definitions".to_string())
+        .description(Some("A tool with its own vocabulary".to_string()))
+        .section(Some("8".to_string()))
+        .utf8(true)
+        .os(Some("Definitions 2.1".to_string()));
+    page.flags = vec![
+        flag(
+            Some("shard"),
+            None,
+            value(false),
+            "Only process the given shard.",
+        ),
+        flag(
+            Some("lines"),
+            None,
+            value(false),
+            "Only process the lines in the given range.",
+        ),
+        operand("command", true, "The command to run on each shard."),
+    ];
+    page.flags[1]
+        .push_value_name("FIRST".to_string())
+        .push_value_name("LAST".to_string());
+    page.trailing_var_arg(true);
+    page.definitions = vec![
+        Definition::new(
+            "shard".to_string(),
+            "A slice of the input processed on its own.".to_string(),
+        ),
+        Definition::new(
+            "No op".to_string(),
+            "An operation that changes nothing.\n\n.Pp is not a macro here.".to_string(),
+        ),
+        Definition::new(
+            "Café".to_string(),
+            "Kept as UTF-8 — for toolchains that read it.".to_string(),
+        ),
+    ];
+    let mut count = Example::new("definitions --shard 2 -- wc -l".to_string());
+    count.doc("Count the lines of the second shard.".to_string());
+    page.examples = vec![
+        count,
+        Example::new("definitions --lines 1 10 \\\n    -- sort".to_string()),
+    ];
+    page.std_exit_status(true);
+    page.diagnostics = vec![Definition::new(
+        "definitions: shard 2 is empty".to_string(),
+        "The shard asked for has no lines; nothing is run.".to_string(),
+    )];
+    page.env_vars = vec![Definition::new(
+        "TMPDIR".to_string(),
+        "Where shards are spooled before sorting.".to_string(),
+    )];
+    page.files = vec![Definition::new(
+        "/run/definitions.sock".to_string(),
+        "The socket shards are handed out on.".to_string(),
+    )];
+    page.bugs(Some("mailto:bugs@example.com".to_string()))
+        .caveats(Some(
+            "Shards are counted from 1.\n\nThe command after -- runs once per shard.".to_string(),
+        ))
+        .copyright(Some(
+            "Copyright 2022 The definitions authors.\n\nPermission is granted to copy this page."
+                .to_string(),
+        ));
+    page.standards = Some(
+        "The sorting conforms to IEEE Std 1003.1-2017 (POSIX.1).\n\n\
+         Shards are split on the line boundaries of the C locale."
+            .to_string(),
+    );
+    page.exit_statuses = vec![
+        ExitStatus::new("2".to_string(), "A shard could not be read.".to_string()),
+        ExitStatus::new(
+            "3".to_string(),
+            "The command given after -- failed.".to_string(),
+        ),
+    ];
+    page.see_also = vec![
+        SeeAlso::new("git".to_string(), "1".to_string()),
+        SeeAlso::new("definitions.conf".to_string(), "5".to_string()),
+    ];
+    page
+}
+
+fn escaping() -> Manpage {
+    let mut sub = Manpage::new();
+    sub.name("run".to_string()).description(Some(
+        "Run the script, then print \\& and \\fBbold\\fR literally".to_string(),
+    ));
+
+    let mut page = Manpage::new();
+    page.escape_hyphens(true)
+        .name("escaping".to_string())
+        .description(Some("Match \"quoted\" C:\\Windows paths".to_string()))
+        .author(Some("Ad Hoc <adhoc@example.com>".to_string()))
+        .date(Some("Spring 2022. Revised".to_string()))
+        .os(Some("Acme 2.0. Beta".to_string()));
+    page.flags = vec![
+        flag(
+            Some("pattern"),
+            Some("p"),
+            value(false),
+            "Lines matching the pattern, for example .*\\.rs globs, file names or words or 'literal' strings.",
+        ),
+        flag(
+            Some("no-op"),
+            None,
+            None,
+            "Do nothing, as Fl does. A dry-run.\n\n...or close to nothing.",
+        ),
+        flag(
+            Some("compat"),
+            None,
+            value(false),
+            "Parse \"legacy\" files the way v1.0. did, or as \"v2\"...",
+        ),
+        flag(
+            Some("résumé"),
+            None,
+            None,
+            "Map “résumé” → CV entries — about 2×\u{2026}",
+        ),
+        flag(
+            Some("format"),
+            None,
+            value(false),
+            "One of:\n  json    for <programs> & `scripts`\n.text   for people\n\n  \\n      ends a line",
+        ),
+    ];
+    page.flags[4].verbatim(true).repeatable(true);
+    page.long_description(Some(
+        "Usage:\n    escaping -p '.*' FILE\n\n'.rs' files are matched too.".to_string(),
+    ))
+    .verbatim(true);
+    page.push_subcommand(sub);
+    page
+}
+
+/// Every fixture in the corpus.
+pub fn corpus() -> Vec<Fixture> {
+    vec![
+        Fixture {
+            name: "minimal",
+            page: minimal(),
+        },
+        Fixture {
+            name: "flags",
+            page: flags(),
+        },
+        Fixture {
+            name: "subcommands",
+            page: subcommands(),
+        },
+        Fixture {
+            name: "commands",
+            page: commands(),
+        },
+        Fixture {
+            name: "stable",
+            page: stable(),
+        },
+        Fixture {
+            name: "keybindings",
+            page: keybindings(),
+        },
+        Fixture {
+            name: "definitions",
+            page: definitions(),
+        },
+        Fixture {
+            name: "escaping",
+            page: escaping(),
+        },
+    ]
+}
+
+/// Build a hardened page out of arbitrary input, as the fuzz targets do.
+///
+/// The input is split on NUL bytes into the page name, description and author, followed by
+/// `(long, short, doc)` triples for flags. The last field also becomes a subcommand.
+pub fn from_fuzz_input(input: &str) -> Manpage {
+    let mut fields = input.split('\0');
+    let mut page = Manpage::new();
+    page.hardened(true)
+        .name(fields.next().unwrap_or_default().to_string())
+        .description(fields.next().map(str::to_string))
+        .author(fields.next().map(str::to_string));
+    let rest = fields.collect::<Vec<_>>();
+    for chunk in rest.chunks(3) {
+        let doc = chunk.get(2).copied().unwrap_or_default();
+        let args = if chunk.len() == 3 {
+            value(doc.len() % 2 == 0)
+        } else {
+            None
+        };
+        page.flags.push(flag(
+            chunk.first().copied(),
+            chunk.get(1).copied(),
+            args,
+            doc,
+        ));
+    }
+    if let Some(last) = rest.last() {
+        let mut cmd = Manpage::new();
+        cmd.name(last.to_string())
+            .description(Some(last.to_string()));
+        cmd.flags = page.flags.clone();
+        page.push_subcommand(cmd);
+    }
+    page
+}
+
+/// The complete page in the format it is set to, mdoc unless changed: header, body and footer.
+pub fn render(page: &Manpage) -> String {
+    page.render_page()
+}
+
+/// The mdoc cheat sheet.
+pub fn render_cheatsheet(page: &Manpage) -> String {
+    page.render_cheatsheet()
+}
+
+/// `page` switched to `format`.
+pub fn in_format(page: &Manpage, format: Format) -> Manpage {
+    let mut page = page.clone();
+    page.format(format);
+    page
+}
+
+/// Render every fixture with `render` and compare it to the expected `name.extension` file.
+///
+/// Returns a description of every mismatching or missing fixture.
+pub fn run(extension: &str, render: impl Fn(&Manpage) -> String) -> Result<(), String> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let bless = std::env::var_os("STRUCTOPT_BLESS").is_some();
+    let mut failures = vec![];
+
+    for Fixture { name, page } in corpus() {
+        let path = dir.join(format!("{}.{}", name, extension));
+        let actual = render(&page);
+        if bless {
+            fs::write(&path, &actual).unwrap();
+            continue;
+        }
+        match fs::read_to_string(&path) {
+            Ok(expected) if expected == actual => {}
+            Ok(expected) => failures.push(format!(
+                "{}: output differs\n--- expected\n{}\n--- actual\n{}",
+                path.display(),
+                expected,
+                actual
+            )),
+            Err(err) => failures.push(format!("{}: {}", path.display(), err)),
+        }
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(failures.join("\n\n"))
+    }
+}
@@ -0,0 +1,155 @@
+#![allow(dead_code)]
+
+mod fixtures;
+#[path = "../src/manpage/mod.rs"]
+mod manpage;
+
+#[test]
+fn mdoc() {
+    if let Err(failures) = fixtures::run("mdoc", fixtures::render) {
+        panic!("{}", failures);
+    }
+}
+
+#[test]
+fn cheatsheet() {
+    if let Err(failures) = fixtures::run("cheatsheet.mdoc", fixtures::render_cheatsheet) {
+        panic!("{}", failures);
+    }
+}
+
+#[test]
+#[cfg(feature = "man")]
+fn man() {
+    if let Err(failures) = fixtures::run("man", |page| {
+        fixtures::render(&fixtures::in_format(page, manpage::Format::Man))
+    }) {
+        panic!("{}", failures);
+    }
+}
+
+#[test]
+#[cfg(feature = "man")]
+fn man_cheatsheet() {
+    if let Err(failures) = fixtures::run("cheatsheet.man", |page| {
+        fixtures::in_format(page, manpage::Format::Man).render_cheatsheet()
+    }) {
+        panic!("{}", failures);
+    }
+}
+
+#[test]
+#[cfg(feature = "rst")]
+fn rst() {
+    if let Err(failures) = fixtures::run("rst", |page| {
+        fixtures::render(&fixtures::in_format(page, manpage::Format::Rst))
+    }) {
+        panic!("{}", failures);
+    }
+}
+
+#[test]
+#[cfg(feature = "rst")]
+fn rst_cheatsheet() {
+    if let Err(failures) = fixtures::run("cheatsheet.rst", |page| {
+        fixtures::in_format(page, manpage::Format::Rst).render_cheatsheet()
+    }) {
+        panic!("{}", failures);
+    }
+}
+
+#[test]
+#[cfg(feature = "docbook")]
+fn docbook() {
+    if let Err(failures) = fixtures::run("xml", |page| {
+        fixtures::render(&fixtures::in_format(page, manpage::Format::Docbook))
+    }) {
+        panic!("{}", failures);
+    }
+}
+
+#[test]
+#[cfg(feature = "docbook")]
+fn docbook_cheatsheet() {
+    if let Err(failures) = fixtures::run("cheatsheet.xml", |page| {
+        fixtures::in_format(page, manpage::Format::Docbook).render_cheatsheet()
+    }) {
+        panic!("{}", failures);
+    }
+}
+
+#[test]
+#[cfg(feature = "texinfo")]
+fn texinfo() {
+    if let Err(failures) = fixtures::run("texi", |page| {
+        fixtures::render(&fixtures::in_format(page, manpage::Format::Texinfo))
+    }) {
+        panic!("{}", failures);
+    }
+}
+
+#[test]
+#[cfg(feature = "texinfo")]
+fn texinfo_cheatsheet() {
+    if let Err(failures) = fixtures::run("cheatsheet.texi", |page| {
+        fixtures::in_format(page, manpage::Format::Texinfo).render_cheatsheet()
+    }) {
+        panic!("{}", failures);
+    }
+}
+
+#[test]
+#[cfg(feature = "text")]
+fn text() {
+    if let Err(failures) = fixtures::run("txt", |page| {
+        fixtures::render(&fixtures::in_format(page, manpage::Format::Text))
+    }) {
+        panic!("{}", failures);
+    }
+}
+
+#[test]
+#[cfg(feature = "text")]
+fn text_cheatsheet() {
+    if let Err(failures) = fixtures::run("cheatsheet.txt", |page| {
+        fixtures::in_format(page, manpage::Format::Text).render_cheatsheet()
+    }) {
+        panic!("{}", failures);
+    }
+}
+
+#[test]
+#[cfg(feature = "json")]
+fn json() {
+    if let Err(failures) = fixtures::run("json", |page| page.to_json()) {
+        panic!("{}", failures);
+    }
+}
+
+#[test]
+#[cfg(feature = "yaml")]
+fn yaml() {
+    if let Err(failures) = fixtures::run("yaml", |page| page.to_yaml()) {
+        panic!("{}", failures);
+    }
+}
+
+#[test]
+#[cfg(feature = "markdown")]
+fn markdown() {
+    if let Err(failures) = fixtures::run("md", |page| {
+        fixtures::render(&fixtures::in_format(page, manpage::Format::Markdown))
+    }) {
+        panic!("{}", failures);
+    }
+}
+
+#[test]
+#[cfg(feature = "markdown")]
+fn markdown_cheatsheet() {
+    if let Err(failures) = fixtures::run("cheatsheet.md", |page| {
+        fixtures::in_format(page, manpage::Format::Markdown).render_cheatsheet()
+    }) {
+        panic!("{}", failures);
+    }
+}
@@ -0,0 +1,108 @@
+#![allow(dead_code)]
+
+mod fixtures;
+#[path = "../src/manpage/mod.rs"]
+mod manpage;
+
+use manpage::Format;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Every input of the fuzz corpus must render to roff that passes `check_hardened`, in every
+/// format.
+#[test]
+fn fuzz_corpus() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fuzz/corpus/render");
+    let mut count = 0;
+    for entry in fs::read_dir(&dir).unwrap() {
+        let path = entry.unwrap().path();
+        let input = String::from_utf8_lossy(&fs::read(&path).unwrap()).into_owned();
+        let mut page = fixtures::from_fuzz_input(&input);
+        for format in &[
+            Format::Mdoc,
+            #[cfg(feature = "man")]
+            Format::Man,
+        ] {
+            page.format(*format);
+            for output in &[
+                page.render_header(),
+                page.render(),
+                page.render_footer(),
+                page.render_cheatsheet(),
+            ] {
+                if let Err(err) = manpage::roff::check_hardened(output) {
+                    panic!("{}: {}\n{}", path.display(), err, output);
+                }
+            }
+        }
+        count += 1;
+    }
+    assert!(count > 0, "empty fuzz corpus in {}", dir.display());
+}
+
+/// The messages of `mandoc -Tlint` at the error level or above for `output` in `macros`, or
+/// `None` when mandoc is not installed.
+fn mandoc_errors(output: &str, macros: &str) -> Option<String> {
+    let mut child = match Command::new("mandoc")
+        .args(["-Tlint", "-Werror", macros])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(err) if err.kind() == ErrorKind::NotFound => return None,
+        Err(err) => panic!("couldn't run mandoc: {}", err),
+    };
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(output.as_bytes())
+        .unwrap();
+    let result = child.wait_with_output().unwrap();
+    if result.status.success() {
+        return Some(String::new());
+    }
+    Some(format!(
+        "{}{}",
+        String::from_utf8_lossy(&result.stdout),
+        String::from_utf8_lossy(&result.stderr)
+    ))
+}
+
+/// mandoc must not reject any page rendered from the fuzz corpus. Skipped when mandoc is not
+/// installed.
+#[test]
+fn mandoc_lint() {
+    let formats = [
+        (Format::Mdoc, "-mdoc"),
+        #[cfg(feature = "man")]
+        (Format::Man, "-man"),
+    ];
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fuzz/corpus/render");
+    for entry in fs::read_dir(&dir).unwrap() {
+        let path = entry.unwrap().path();
+        let input = String::from_utf8_lossy(&fs::read(&path).unwrap()).into_owned();
+        let mut page = fixtures::from_fuzz_input(&input);
+        for (format, macros) in formats.iter() {
+            page.format(*format);
+            let output = fixtures::render(&page);
+            match mandoc_errors(&output, macros) {
+                None => {
+                    eprintln!("mandoc is not installed, skipping");
+                    return;
+                }
+                Some(errors) => assert!(
+                    errors.is_empty(),
+                    "{}: {}\n{}",
+                    path.display(),
+                    errors,
+                    output
+                ),
+            }
+        }
+    }
+}
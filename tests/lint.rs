@@ -0,0 +1,72 @@
+#![allow(dead_code)]
+
+mod fixtures;
+#[path = "../src/manpage/mod.rs"]
+mod manpage;
+
+use manpage::lint;
+
+/// Every fixture must render to mdoc the linter accepts, except for the empty `.Nd` of a page
+/// without a description.
+#[test]
+fn corpus() {
+    for fixture in fixtures::corpus() {
+        let page = &fixture.page;
+        for output in &[
+            format!(
+                "{}\n{}{}\n",
+                page.render_header(),
+                page.render(),
+                page.render_footer()
+            ),
+            page.render_cheatsheet(),
+        ] {
+            let expected = if page.description.is_none() && output.contains("\n.Nd") {
+                let lineno = output.lines().position(|line| line == ".Nd").unwrap() + 1;
+                vec![format!("line {}: empty .Nd", lineno)]
+            } else {
+                vec![]
+            };
+            assert_eq!(
+                lint::mdoc(output),
+                expected,
+                "{}:\n{}",
+                fixture.name,
+                output
+            );
+        }
+    }
+}
+
+#[test]
+fn mistakes() {
+    let output = r#".\" .Nm in a comment
+.Dd May 1, 2022
+.Sh NAME
+.Nm broken
+.Nd
+.Sh OPTIONS
+.It Fl v
+.Bl -tag -width Ds
+.It Fl q
+Be quiet, like
+.Fl s .
+Or pass .Fl s instead.
+.Bd -literal
+.El
+\&.Ar is printed.
+.Ek
+.Bl -bullet
+"#;
+    assert_eq!(
+        lint::mdoc(output),
+        vec![
+            "line 5: empty .Nd",
+            "line 7: .It outside of a .Bl list",
+            "line 12: stray macro \".Fl\" in text",
+            "line 14: .El closes .Bd opened on line 13",
+            "line 16: .Ek closes .Bl opened on line 8",
+            "line 17: unterminated .Bl",
+        ]
+    );
+}
@@ -0,0 +1,148 @@
+#![allow(dead_code)]
+
+mod fixtures;
+#[path = "../src/manpage/mod.rs"]
+mod manpage;
+
+use manpage::Manpage;
+
+/// The `names - description` entry `whatis` and `apropos` index a page under, read from the
+/// text lines of its NAME section the way man-db's `lexgrog` reads them.
+fn whatis_entry(name_section: &str) -> Option<(Vec<String>, String)> {
+    let line = name_section
+        .replace("\\-", "-")
+        .replace("\\e", "\\")
+        .replace("\\(dq", "\"")
+        .replace("\\&", "");
+    let (names, description) = line.split_once(" - ")?;
+    let names = names
+        .split(',')
+        .map(|name| name.trim().to_string())
+        .collect::<Vec<_>>();
+    if names.iter().any(String::is_empty) {
+        return None;
+    }
+    Some((names, description.trim().to_string()))
+}
+
+/// The entry a page should be indexed under.
+fn expected_entry(page: &Manpage) -> (Vec<String>, String) {
+    let names = std::iter::once(page.name.clone())
+        .chain(page.invocations.iter().cloned())
+        .collect();
+    let description = page
+        .description
+        .as_deref()
+        .unwrap_or_default()
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim();
+    let description = description.strip_suffix('.').unwrap_or(description);
+    (names, description.to_string())
+}
+
+/// The NAME section of an mdoc page as one `names \- description` line.
+fn mdoc_name_section(header: &str) -> String {
+    let mut names = vec![];
+    let mut description = String::new();
+    for line in header
+        .lines()
+        .skip_while(|line| *line != ".Sh NAME")
+        .skip(1)
+        .take_while(|line| !line.starts_with(".Sh "))
+    {
+        if let Some(name) = line.strip_prefix(".Nm ") {
+            names.push(name.trim_end_matches(" ,"));
+        } else if let Some(val) = line.strip_prefix(".Nd ") {
+            description = val.to_string();
+        }
+    }
+    format!("{} \\- {}", names.join(", "), description)
+}
+
+#[cfg(feature = "man")]
+fn man_name_section(header: &str) -> String {
+    header
+        .lines()
+        .skip_while(|line| *line != ".SH NAME")
+        .skip(1)
+        .take_while(|line| !line.starts_with(".SH "))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[test]
+fn mdoc() {
+    for fixture in fixtures::corpus() {
+        if fixture.page.description.is_none() {
+            continue;
+        }
+        let header = fixture.page.render_header();
+        assert_eq!(
+            whatis_entry(&mdoc_name_section(&header)),
+            Some(expected_entry(&fixture.page)),
+            "{}:\n{}",
+            fixture.name,
+            header
+        );
+    }
+}
+
+#[cfg(feature = "man")]
+#[test]
+fn man() {
+    for fixture in fixtures::corpus() {
+        if fixture.page.description.is_none() {
+            continue;
+        }
+        let page = fixtures::in_format(&fixture.page, manpage::Format::Man);
+        let header = page.render_header();
+        assert_eq!(
+            whatis_entry(&man_name_section(&header)),
+            Some(expected_entry(&page)),
+            "{}:\n{}",
+            fixture.name,
+            header
+        );
+    }
+}
+
+/// Every page with a description must also satisfy `lexgrog` itself, where man-db is installed.
+#[cfg(feature = "man")]
+#[test]
+fn lexgrog() {
+    use std::process::Command;
+
+    let dir = std::env::temp_dir().join(format!("structopt-whatis-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for fixture in fixtures::corpus() {
+        if fixture.page.description.is_none() {
+            continue;
+        }
+        let page = fixtures::in_format(&fixture.page, manpage::Format::Man);
+        let path = dir.join(format!("{}.1", fixture.name));
+        std::fs::write(
+            &path,
+            format!(
+                "{}\n{}{}\n",
+                page.render_header(),
+                page.render(),
+                page.render_footer()
+            ),
+        )
+        .unwrap();
+        let output = match Command::new("lexgrog").arg(&path).output() {
+            Ok(output) => output,
+            // Not installed.
+            Err(_) => break,
+        };
+        assert!(
+            output.status.success(),
+            "{}: {}",
+            fixture.name,
+            String::from_utf8_lossy(&output.stdout)
+        );
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}